@@ -1,6 +1,6 @@
 use {
-    scaffolding::datatypes::ArenaVec,
-    std::{hint::black_box, mem::size_of},
+    scaffolding::datatypes::{ArenaVec, SyncArenaVec},
+    std::{hint::black_box, mem::size_of, sync::Mutex, thread},
     test::{bench, Bencher},
 };
 
@@ -25,3 +25,130 @@ fn vec(b: &mut Bencher) {
         }
     });
 }
+
+// Push throughput for different element sizes and counts. Each iteration
+// starts with a new, empty vector, so growing is included in the measurement.
+macro_rules! push_benches {
+    ($($arenavec:ident $vec:ident $ty:ty, $count:expr;)*) => {$(
+        #[bench]
+        fn $arenavec(b: &mut Bencher) {
+            b.iter(|| {
+                let vec = ArenaVec::<$ty>::new();
+                for i in black_box(0..$count) {
+                    vec.push(black_box([i as u8; size_of::<$ty>()]));
+                }
+                vec
+            });
+        }
+        #[bench]
+        fn $vec(b: &mut Bencher) {
+            b.iter(|| {
+                let mut vec = Vec::<$ty>::new();
+                for i in black_box(0..$count) {
+                    vec.push(black_box([i as u8; size_of::<$ty>()]));
+                }
+                vec
+            });
+        }
+    )*};
+}
+push_benches! {
+    arenavec_push_1b_100 vec_push_1b_100 [u8; 1], 100;
+    arenavec_push_1b_10k vec_push_1b_10k [u8; 1], 10_000;
+    arenavec_push_8b_100 vec_push_8b_100 [u8; 8], 100;
+    arenavec_push_8b_10k vec_push_8b_10k [u8; 8], 10_000;
+    arenavec_push_64b_100 vec_push_64b_100 [u8; 64], 100;
+    arenavec_push_64b_10k vec_push_64b_10k [u8; 64], 10_000;
+}
+
+const THREADS: usize = 4;
+const PUSHES_PER_THREAD: usize = 2_500;
+
+#[bench]
+fn sync_arenavec_concurrent_push(b: &mut Bencher) {
+    b.iter(|| {
+        let vec = SyncArenaVec::new();
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for i in black_box(0..PUSHES_PER_THREAD) {
+                        vec.push(black_box(i));
+                    }
+                });
+            }
+        });
+        vec
+    });
+}
+#[bench]
+fn mutex_vec_concurrent_push(b: &mut Bencher) {
+    b.iter(|| {
+        let vec = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for i in black_box(0..PUSHES_PER_THREAD) {
+                        vec.lock().unwrap().push(black_box(i));
+                    }
+                });
+            }
+        });
+        vec
+    });
+}
+
+// Pushing while holding a pointer to the first item. The arenavec never
+// reallocates, so the pointer stays valid the whole time; `Vec` has to move
+// its items every time it grows.
+#[bench]
+fn arenavec_pointers_stay_put(b: &mut Bencher) {
+    b.iter(|| {
+        let vec = ArenaVec::new();
+        vec.push(0usize);
+        let first = vec.as_ptr();
+
+        for i in black_box(1..10_000) {
+            vec.push(black_box(i));
+        }
+
+        assert_eq!(first, vec.as_ptr());
+        vec
+    });
+}
+#[bench]
+fn vec_pointer_moves(b: &mut Bencher) {
+    b.iter(|| {
+        let mut vec = Vec::new();
+        vec.push(0usize);
+        let mut moves = 0;
+        let mut first = vec.as_ptr();
+
+        for i in black_box(1..10_000) {
+            vec.push(black_box(i));
+            if vec.as_ptr() != first {
+                moves += 1;
+                first = vec.as_ptr();
+            }
+        }
+
+        assert!(moves > 0);
+        black_box(moves)
+    });
+}
+
+#[bench]
+fn arenavec_retain_half(b: &mut Bencher) {
+    b.iter(|| {
+        let mut vec: ArenaVec<u32> = (0..100_000).collect();
+        vec.retain(|val| black_box(*val) % 2 == 0);
+        vec
+    });
+}
+#[bench]
+fn vec_retain_half(b: &mut Bencher) {
+    b.iter(|| {
+        let mut vec: Vec<u32> = (0..100_000).collect();
+        vec.retain(|val| black_box(*val) % 2 == 0);
+        vec
+    });
+}
@@ -5,46 +5,17 @@ struct AppState {
     counter: i32,
 }
 
-#[derive(Clone)]
 enum CommandType {
     Increment,
     Decrement,
 }
-
-#[derive(Default, Clone)]
-struct Commands {
-    ty: Option<CommandType>,
-}
-impl ExecutableArg for Commands {
-    type Arg<'a> = Self;
-    type Mutation = Self;
-
-    fn from_world_and_env<'a>(_: &'a ImmutableWorld, _: &'a TypeMap) -> Self::Arg<'a> {
-        Self::default()
-    }
-    fn build_mutation(self) -> Self::Mutation {
-        self
-    }
-}
-impl Mutation for Commands {
-    type Reverse = Self;
-
-    fn apply(self, world: &mut World) {
-        if let Some(ty) = self.ty {
-            let state: &mut AppState = world.get_state_mut();
-            match ty {
-                CommandType::Increment => state.counter += 1,
-                CommandType::Decrement => state.counter -= 1,
-            }
+impl CommandType {
+    fn apply(&self, state: &mut AppState) {
+        match self {
+            CommandType::Increment => state.counter += 1,
+            CommandType::Decrement => state.counter -= 1,
         }
     }
-    fn build_reverse(&self, _: &World) -> Self::Reverse {
-        let ty = self.ty.clone().map(|ty| match ty {
-            CommandType::Increment => CommandType::Decrement,
-            CommandType::Decrement => CommandType::Increment,
-        });
-        Self { ty }
-    }
 }
 
 const ITERATIONS: u32 = 1;
@@ -52,31 +23,29 @@ const ITERATIONS: u32 = 1;
 #[bench]
 fn scaffolding_mutations(b: &mut Bencher) {
     let mut world = World::new();
-    world.add_state(AppState::default());
+    world.add_singleton(AppState::default());
     b.iter(|| {
         for _ in 0..ITERATIONS {
-            world.execute(
-                |cmds: &mut Commands| {
-                    cmds.ty = Some(CommandType::Increment);
-                },
-                None,
-            );
+            world.execute(|commands: &CommandQueue| {
+                commands.queue(|world| {
+                    CommandType::Increment.apply(world.get_singleton_mut());
+                });
+            });
         }
     });
 }
 #[bench]
-fn scaffolding_dyn_mutations(b: &mut Bencher) {
+fn scaffolding_msg_mutations(b: &mut Bencher) {
     let mut world = World::new();
-    world.add_state(AppState::default());
+    world.add_singleton(AppState::default());
+    world.add_msg_handler(|world: &mut World, msg: Msg<CommandType>| {
+        msg.apply(world.get_singleton_mut());
+    });
     b.iter(|| {
         for _ in 0..ITERATIONS {
-            let (mutation, _) = black_box(world.execute_delayed_mutation(
-                |cmds: &mut Commands| {
-                    cmds.ty = Some(CommandType::Increment);
-                },
-                None,
-            ));
-            mutation.apply(&mut world);
+            world.execute(|msgs: &MsgSender| {
+                msgs.send(black_box(CommandType::Increment));
+            });
         }
     });
 }
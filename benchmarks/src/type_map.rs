@@ -296,6 +296,40 @@ fn hash_map_hashnt_read(b: &mut Bencher) {
     });
 }
 
+// Many types
+// Run with `--features mix-type-ids` to compare against mixed type IDs
+
+pub struct Numbered<const N: usize>(usize);
+
+macro_rules! many_types {
+    ($map:ident, $action:ident) => {
+        many_types!(@$action $map; 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63)
+    };
+    (@insert $map:ident; $($n:literal)*) => {
+        $($map.insert(Numbered::<$n>($n));)*
+    };
+    (@read $map:ident; $($n:literal)*) => {
+        $(black_box($map.get::<Numbered<$n>>().unwrap().0);)*
+    };
+}
+
+#[bench]
+fn typemap_many_types(b: &mut Bencher) {
+    b.iter(|| {
+        let mut map = TypeMap::new(64, 64 * 8);
+        many_types!(map, insert);
+        black_box(map);
+    });
+}
+#[bench]
+fn typemap_many_types_read(b: &mut Bencher) {
+    let mut map = TypeMap::new(64, 64 * 8);
+    many_types!(map, insert);
+    b.iter(|| {
+        many_types!(map, read);
+    });
+}
+
 // misc
 
 #[bench]
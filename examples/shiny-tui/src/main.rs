@@ -16,15 +16,21 @@ fn app(app: &App, terminal: &Singleton<Terminal>, uniqs: &Uniqs) {
     let text_input_buffer = uniqs.get(uniq_key!());
     app.draw(TextInput::new(text_input_buffer, uniq_key!()).placeholder("Text box"));
 
-    let btn = app.draw(Button::new("button :D").x(11).width(15).height(3));
-    app.draw(Button::new("Tol button :D").x(27).width(20).height(5));
+    let btn = app.draw(
+        Button::new("button :D", uniq_key!())
+            .x(11)
+            .width(15)
+            .height(3),
+    );
+    app.draw(
+        Button::new("Tol button :D", uniq_key!())
+            .x(27)
+            .width(20)
+            .height(5),
+    );
     app.draw(Checkbox::new("Checkbox", uniq_key!()).x(48).width(10).y(1));
 
-    terminal.draw(RawString {
-        x: 0,
-        y: 5,
-        text: format!("Button is: {:?}", btn),
-    });
+    terminal.render_fmt(format_args!("Button is: {:?}", btn), (0, 5));
     app.draw(
         Text::new("FANCY TEXT :DDDD")
             .x(0)
@@ -2,6 +2,7 @@ use {scaffolding::prelude::*, scaffolding_tui::prelude::*};
 
 struct TodoItem {
     label: String,
+    #[allow(dead_code)]
     complete: bool,
 }
 
@@ -12,7 +13,7 @@ fn main() {
     TuiRunloop::default().start(world, app_main);
 }
 
-fn app_main(terminal: &Singleton<Terminal>, msg_sender: &MsgSender, app: &App, uniqs: &Uniqs) {
+fn app_main(terminal: &Singleton<Terminal>, _msg_sender: &MsgSender, app: &App, uniqs: &Uniqs) {
     let todo_list: &mut Vec<TodoItem> = uniqs.get(uniq_key!());
     let mut y = 0;
 
@@ -34,7 +35,12 @@ fn app_main(terminal: &Singleton<Terminal>, msg_sender: &MsgSender, app: &App, u
         y += 1;
     }
 
-    let add_btn = app.draw(Button::new("+").y(y.saturating_add(1)).width(5).height(3));
+    let add_btn = app.draw(
+        Button::new("+", uniq_key!())
+            .y(y.saturating_add(1))
+            .width(5)
+            .height(3),
+    );
     if add_btn.state == ButtonState::Pressed {
         todo_list.push(TodoItem {
             label: String::new(),
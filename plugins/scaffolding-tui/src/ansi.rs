@@ -0,0 +1,217 @@
+//! Parsing ANSI escape sequences in strings, so pre-styled text can be drawn
+//! without its escapes being counted as characters.
+
+use {crate::Colour, unicode_segmentation::UnicodeSegmentation};
+
+/// A piece of a string that may contain ANSI escapes. See [`segments`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AnsiSegment<'a> {
+    /// Printable text.
+    Text(&'a str),
+    /// A single escape sequence, including its leading `ESC`.
+    Escape(&'a str),
+}
+
+/// Split a string into its printable text and escape sequences.
+pub fn segments(string: &str) -> AnsiSegments<'_> {
+    AnsiSegments { remaining: string }
+}
+
+/// The iterator returned by [`segments`].
+pub struct AnsiSegments<'a> {
+    remaining: &'a str,
+}
+impl<'a> Iterator for AnsiSegments<'a> {
+    type Item = AnsiSegment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining.is_empty() {
+            return None;
+        }
+
+        let len = if self.remaining.starts_with('\x1B') {
+            escape_len(self.remaining)
+        } else {
+            self.remaining.find('\x1B').unwrap_or(self.remaining.len())
+        };
+        let (segment, remaining) = self.remaining.split_at(len);
+        self.remaining = remaining;
+
+        if segment.starts_with('\x1B') {
+            Some(AnsiSegment::Escape(segment))
+        } else {
+            Some(AnsiSegment::Text(segment))
+        }
+    }
+}
+
+/// The length of the escape sequence at the start of `string`, which must
+/// start with `ESC`. Unterminated escapes run to the end of the string.
+fn escape_len(string: &str) -> usize {
+    let bytes = string.as_bytes();
+
+    match bytes.get(1) {
+        // CSI: parameter & intermediate bytes, then a final byte
+        Some(b'[') => bytes[2..]
+            .iter()
+            .position(|byte| (0x40..=0x7E).contains(byte))
+            .map(|idx| idx + 3)
+            .unwrap_or(bytes.len()),
+        // OSC: runs until BEL or ST (`ESC \`)
+        Some(b']') => {
+            let mut idx = 2;
+            while idx < bytes.len() {
+                match bytes[idx] {
+                    0x07 => return idx + 1,
+                    0x1B if bytes.get(idx + 1) == Some(&b'\\') => return idx + 2,
+                    _ => idx += 1,
+                }
+            }
+            bytes.len()
+        }
+        // Two-character escapes
+        Some(_) => 1 + string[1..].chars().next().unwrap().len_utf8(),
+        None => 1,
+    }
+}
+
+/// The number of cells `string` takes up when drawn, not counting escapes.
+pub fn visible_width(string: &str) -> usize {
+    segments(string)
+        .map(|segment| match segment {
+            AnsiSegment::Text(text) => text.graphemes(true).count(),
+            AnsiSegment::Escape(_) => 0,
+        })
+        .sum()
+}
+
+/// A single command in an SGR ("Select Graphic Rendition") escape, which is
+/// the escape that sets text colours and styles.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sgr {
+    /// Reset all colours and styles.
+    Reset,
+    /// Set the text colour. `None` is the default colour.
+    Foreground(Option<Colour>),
+    /// Set the background colour. `None` is the default colour.
+    Background(Option<Colour>),
+    /// Any other SGR code, like bold or underline.
+    Attribute(u8),
+}
+
+/// Parse an SGR escape (`ESC [ ... m`) into its commands. Returns `None` if
+/// `escape` isn't an SGR escape.
+pub fn parse_sgr(escape: &str) -> Option<Vec<Sgr>> {
+    let params = escape.strip_prefix("\x1B[")?.strip_suffix('m')?;
+    let mut params = params.split(';').map(|param| {
+        if param.is_empty() {
+            Some(0)
+        } else {
+            param.parse::<u8>().ok()
+        }
+    });
+    let mut commands = Vec::new();
+
+    while let Some(param) = params.next() {
+        let command = match param? {
+            0 => Sgr::Reset,
+            code @ 30..=37 => Sgr::Foreground(Some(Colour::from_ansi16(code - 30))),
+            38 => Sgr::Foreground(Some(parse_extended_colour(&mut params)?)),
+            39 => Sgr::Foreground(None),
+            code @ 40..=47 => Sgr::Background(Some(Colour::from_ansi16(code - 40))),
+            48 => Sgr::Background(Some(parse_extended_colour(&mut params)?)),
+            49 => Sgr::Background(None),
+            code @ 90..=97 => Sgr::Foreground(Some(Colour::from_ansi16(code - 90 + 8))),
+            code @ 100..=107 => Sgr::Background(Some(Colour::from_ansi16(code - 100 + 8))),
+            code => Sgr::Attribute(code),
+        };
+        commands.push(command);
+    }
+
+    Some(commands)
+}
+
+/// Parse a cursor position escape (`ESC [ row ; column H`) into the `(x, y)`
+/// cell it moves the cursor to, counting from 0. Returns `None` if `escape`
+/// isn't a cursor position escape.
+pub fn parse_cursor_position(escape: &str) -> Option<(u16, u16)> {
+    let params = escape.strip_prefix("\x1B[")?.strip_suffix('H')?;
+    let mut params = params.split(';').map(|param| {
+        if param.is_empty() {
+            Ok(1)
+        } else {
+            param.parse::<u16>()
+        }
+    });
+    let row = params.next().unwrap_or(Ok(1)).ok()?;
+    let column = params.next().unwrap_or(Ok(1)).ok()?;
+
+    Some((column.saturating_sub(1), row.saturating_sub(1)))
+}
+
+/// Parses the parameters after a 38 or 48 in an SGR escape: either `5;n` for
+/// a 256-colour palette colour, or `2;r;g;b` for an RGB colour.
+fn parse_extended_colour(params: &mut impl Iterator<Item = Option<u8>>) -> Option<Colour> {
+    match params.next()?? {
+        5 => Some(Colour::from_ansi256(params.next()??)),
+        2 => Some(Colour::new(
+            params.next()??,
+            params.next()??,
+            params.next()??,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_segments() {
+        let string = "\x1B[31mred\x1B[0m plain \x1B]0;title\x07end\x1B[";
+        assert_eq!(
+            segments(string).collect::<Vec<_>>(),
+            [
+                AnsiSegment::Escape("\x1B[31m"),
+                AnsiSegment::Text("red"),
+                AnsiSegment::Escape("\x1B[0m"),
+                AnsiSegment::Text(" plain "),
+                AnsiSegment::Escape("\x1B]0;title\x07"),
+                AnsiSegment::Text("end"),
+                AnsiSegment::Escape("\x1B["),
+            ]
+        );
+        assert_eq!(visible_width(string), 13);
+    }
+
+    #[test]
+    fn sgr() {
+        assert_eq!(parse_sgr("\x1B[m"), Some(vec![Sgr::Reset]));
+        assert_eq!(
+            parse_sgr("\x1B[1;31;48;2;1;2;3m"),
+            Some(vec![
+                Sgr::Attribute(1),
+                Sgr::Foreground(Some(Colour::from_ansi16(1))),
+                Sgr::Background(Some(Colour::new(1, 2, 3))),
+            ])
+        );
+        assert_eq!(
+            parse_sgr("\x1B[38;5;196;39m"),
+            Some(vec![
+                Sgr::Foreground(Some(Colour::new(255, 0, 0))),
+                Sgr::Foreground(None)
+            ])
+        );
+        assert_eq!(parse_sgr("\x1B[2J"), None);
+        assert_eq!(parse_sgr("\x1B[38;5m"), None);
+    }
+
+    #[test]
+    fn cursor_position() {
+        assert_eq!(parse_cursor_position("\x1B[3;12H"), Some((11, 2)));
+        assert_eq!(parse_cursor_position("\x1B[H"), Some((0, 0)));
+        assert_eq!(parse_cursor_position("\x1B[5H"), Some((0, 4)));
+        assert_eq!(parse_cursor_position("\x1B[31m"), None);
+    }
+}
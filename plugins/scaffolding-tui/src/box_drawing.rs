@@ -0,0 +1,98 @@
+//! Joining box-drawing characters that overlap, so adjacent [`Border`]s
+//! connect with junctions (`┬`, `┤`, etc) instead of mismatched corners.
+//!
+//! [`Border`]: crate::shapes::Border
+
+/// How thick each arm of a box-drawing character is.
+type Weight = u8;
+/// No line.
+const N: Weight = 0;
+/// A light line (`─`).
+const L: Weight = 1;
+/// A heavy line (`━`).
+const H: Weight = 2;
+/// A double line (`═`).
+const D: Weight = 3;
+
+/// Box-drawing characters, and the weight of each of their arms, in the order
+/// up, right, down, left.
+#[rustfmt::skip]
+const GLYPHS: &[(char, [Weight; 4])] = &[
+    ('─', [N, L, N, L]), ('━', [N, H, N, H]), ('│', [L, N, L, N]), ('┃', [H, N, H, N]),
+    ('┌', [N, L, L, N]), ('┍', [N, H, L, N]), ('┎', [N, L, H, N]), ('┏', [N, H, H, N]),
+    ('┐', [N, N, L, L]), ('┑', [N, N, L, H]), ('┒', [N, N, H, L]), ('┓', [N, N, H, H]),
+    ('└', [L, L, N, N]), ('┕', [L, H, N, N]), ('┖', [H, L, N, N]), ('┗', [H, H, N, N]),
+    ('┘', [L, N, N, L]), ('┙', [L, N, N, H]), ('┚', [H, N, N, L]), ('┛', [H, N, N, H]),
+    ('├', [L, L, L, N]), ('┝', [L, H, L, N]), ('┞', [H, L, L, N]), ('┟', [L, L, H, N]),
+    ('┠', [H, L, H, N]), ('┡', [H, H, L, N]), ('┢', [L, H, H, N]), ('┣', [H, H, H, N]),
+    ('┤', [L, N, L, L]), ('┥', [L, N, L, H]), ('┦', [H, N, L, L]), ('┧', [L, N, H, L]),
+    ('┨', [H, N, H, L]), ('┩', [H, N, L, H]), ('┪', [L, N, H, H]), ('┫', [H, N, H, H]),
+    ('┬', [N, L, L, L]), ('┭', [N, L, L, H]), ('┮', [N, H, L, L]), ('┯', [N, H, L, H]),
+    ('┰', [N, L, H, L]), ('┱', [N, L, H, H]), ('┲', [N, H, H, L]), ('┳', [N, H, H, H]),
+    ('┴', [L, L, N, L]), ('┵', [L, L, N, H]), ('┶', [L, H, N, L]), ('┷', [L, H, N, H]),
+    ('┸', [H, L, N, L]), ('┹', [H, L, N, H]), ('┺', [H, H, N, L]), ('┻', [H, H, N, H]),
+    ('┼', [L, L, L, L]), ('┽', [L, L, L, H]), ('┾', [L, H, L, L]), ('┿', [L, H, L, H]),
+    ('╀', [H, L, L, L]), ('╁', [L, L, H, L]), ('╂', [H, L, H, L]), ('╃', [H, L, L, H]),
+    ('╄', [H, H, L, L]), ('╅', [L, L, H, H]), ('╆', [L, H, H, L]), ('╇', [H, H, L, H]),
+    ('╈', [L, H, H, H]), ('╉', [H, L, H, H]), ('╊', [H, H, H, L]), ('╋', [H, H, H, H]),
+    ('═', [N, D, N, D]), ('║', [D, N, D, N]), ('╒', [N, D, L, N]), ('╓', [N, L, D, N]),
+    ('╔', [N, D, D, N]), ('╕', [N, N, L, D]), ('╖', [N, N, D, L]), ('╗', [N, N, D, D]),
+    ('╘', [L, D, N, N]), ('╙', [D, L, N, N]), ('╚', [D, D, N, N]), ('╛', [L, N, N, D]),
+    ('╜', [D, N, N, L]), ('╝', [D, N, N, D]), ('╞', [L, D, L, N]), ('╟', [D, L, D, N]),
+    ('╠', [D, D, D, N]), ('╡', [L, N, L, D]), ('╢', [D, N, D, L]), ('╣', [D, N, D, D]),
+    ('╤', [N, D, L, D]), ('╥', [N, L, D, L]), ('╦', [N, D, D, D]), ('╧', [L, D, N, D]),
+    ('╨', [D, L, N, L]), ('╩', [D, D, N, D]), ('╪', [L, D, L, D]), ('╫', [D, L, D, L]),
+    ('╬', [D, D, D, D]), ('╭', [N, L, L, N]), ('╮', [N, N, L, L]), ('╯', [L, N, N, L]),
+    ('╰', [L, L, N, N]), ('╴', [N, N, N, L]), ('╵', [L, N, N, N]), ('╶', [N, L, N, N]),
+    ('╷', [N, N, L, N]), ('╸', [N, N, N, H]), ('╹', [H, N, N, N]), ('╺', [N, H, N, N]),
+    ('╻', [N, N, H, N]), ('╼', [N, H, N, L]), ('╽', [L, N, H, N]), ('╾', [N, L, N, H]),
+    ('╿', [H, N, L, N]),
+];
+
+fn arms(glyph: char) -> Option<[Weight; 4]> {
+    GLYPHS
+        .iter()
+        .find(|(other, _)| *other == glyph)
+        .map(|(_, arms)| *arms)
+}
+fn glyph(arms: [Weight; 4]) -> Option<char> {
+    GLYPHS
+        .iter()
+        .find(|(_, other)| *other == arms)
+        .map(|(glyph, _)| *glyph)
+}
+
+/// Combines a box-drawing character that's being drawn (`above`) with the one
+/// that's already in that cell (`below`). Each arm of the result is as thick as
+/// the thickest arm from either character. If either isn't a box-drawing
+/// character, or there's no character for the combination, this is just
+/// `above`.
+pub(crate) fn join(below: char, above: char) -> char {
+    let (Some(below_arms), Some(above_arms)) = (arms(below), arms(above)) else {
+        return above;
+    };
+    let joined = [0, 1, 2, 3].map(|arm| below_arms[arm].max(above_arms[arm]));
+
+    // Checked first so rounded corners stay rounded
+    if joined == above_arms {
+        above
+    } else {
+        glyph(joined).unwrap_or(above)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::join;
+
+    #[test]
+    fn joins() {
+        assert_eq!(join('┐', '┌'), '┬');
+        assert_eq!(join('│', '─'), '┼');
+        assert_eq!(join('┃', '─'), '╂');
+        assert_eq!(join('╮', '│'), '┤');
+        assert_eq!(join('╭', '╭'), '╭');
+        assert_eq!(join('a', '─'), '─');
+        assert_eq!(join('─', 'a'), 'a');
+    }
+}
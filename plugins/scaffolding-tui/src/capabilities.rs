@@ -0,0 +1,79 @@
+//! Detecting what the terminal emulator supports.
+
+/// How colours are sent to the terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum ColourMode {
+    /// 24-bit RGB colours.
+    #[default]
+    TrueColour,
+    /// The 256-colour palette. RGB colours are rounded to the closest colour
+    /// in the palette.
+    Ansi256,
+    /// The 16 basic terminal colours. RGB colours are rounded to the closest
+    /// one.
+    Ansi16,
+    /// The terminal doesn't support colours, so they're never sent.
+    NoColour,
+}
+
+/// Guess which [`ColourMode`] the terminal supports from its environment
+/// variables. `env` gets an environment variable by name; normally this is
+/// `|name| std::env::var(name).ok()`.
+///
+/// `COLORTERM=truecolor` or `COLORTERM=24bit` means true colour is supported.
+/// Otherwise, `TERM` is checked for `256color`. If `TERM` isn't set at all
+/// (which is normal on Windows), we can't tell, so true colour is assumed.
+pub fn detect_colour_mode(env: impl Fn(&str) -> Option<String>) -> ColourMode {
+    if let Some(colorterm) = env("COLORTERM") {
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColourMode::TrueColour;
+        }
+    }
+
+    match env("TERM") {
+        None => ColourMode::TrueColour,
+        Some(term) if term == "dumb" => ColourMode::NoColour,
+        Some(term) if term.contains("256color") => ColourMode::Ansi256,
+        Some(_) => ColourMode::Ansi16,
+    }
+}
+
+/// Guess if the terminal supports mouse reporting from its environment
+/// variables. See [`detect_colour_mode`] for what `env` is.
+pub fn detect_mouse_support(env: impl Fn(&str) -> Option<String>) -> bool {
+    env("TERM").is_none_or(|term| term != "dumb")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, val)| val.to_string())
+        }
+    }
+
+    #[test]
+    fn truecolor() {
+        let vars = [("COLORTERM", "truecolor"), ("TERM", "xterm-256color")];
+        assert_eq!(detect_colour_mode(env(&vars)), ColourMode::TrueColour);
+        assert!(detect_mouse_support(env(&vars)));
+    }
+
+    #[test]
+    fn xterm_256color() {
+        let vars = [("TERM", "xterm-256color")];
+        assert_eq!(detect_colour_mode(env(&vars)), ColourMode::Ansi256);
+        assert!(detect_mouse_support(env(&vars)));
+    }
+
+    #[test]
+    fn dumb() {
+        let vars = [("TERM", "dumb")];
+        assert_eq!(detect_colour_mode(env(&vars)), ColourMode::NoColour);
+        assert!(!detect_mouse_support(env(&vars)));
+    }
+}
@@ -0,0 +1,97 @@
+//! An in-process clipboard, shared by every widget in the app.
+
+use {crate::prelude::Terminal, std::cell::RefCell};
+
+/// Text that was cut or copied from a widget, such as a
+/// [`TextInput`](crate::widgets::TextInput). This is a singleton added by
+/// [`TuiPlugin`](crate::TuiPlugin).
+///
+/// This clipboard only lives as long as the app; it doesn't read the OS
+/// clipboard, and only writes to it if [`Clipboard::osc52`] is enabled.
+#[derive(Default, Debug)]
+pub struct Clipboard {
+    contents: RefCell<String>,
+    /// If text copied with [`Clipboard::copy`] should also be sent to the
+    /// terminal emulator's clipboard, with [`Terminal::set_clipboard`]. This
+    /// works over SSH, but not every terminal emulator supports it, so it's
+    /// off by default.
+    pub osc52: bool,
+}
+impl Clipboard {
+    /// The text currently on the clipboard.
+    pub fn get(&self) -> String {
+        self.contents.borrow().clone()
+    }
+    /// Replace the text on the clipboard.
+    pub fn set(&self, contents: impl Into<String>) {
+        *self.contents.borrow_mut() = contents.into();
+    }
+    /// Replace the text on the clipboard, and copy it to the terminal
+    /// emulator's clipboard if [`Clipboard::osc52`] is enabled. Widgets use
+    /// this when the user cuts or copies text.
+    pub fn copy(&self, contents: impl Into<String>, terminal: &Terminal) {
+        let contents = contents.into();
+        if self.osc52 {
+            terminal.set_clipboard(&contents);
+        }
+        self.set(contents);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.contents.borrow().is_empty()
+    }
+}
+
+/// Encode `bytes` with standard, padded base64, which is what OSC 52 expects.
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (idx, byte)| {
+            group | (*byte as u32) << (16 - idx * 8)
+        });
+
+        // Every byte in the chunk is spread over one more character; the rest
+        // are padding
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                let sextet = (group >> (18 - idx * 6)) & 0b11_1111;
+                encoded.push(ALPHABET[sextet as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64() {
+        assert_eq!(super::base64(b""), "");
+        assert_eq!(super::base64(b"f"), "Zg==");
+        assert_eq!(super::base64(b"fo"), "Zm8=");
+        assert_eq!(super::base64(b"foo"), "Zm9v");
+        assert_eq!(super::base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(super::base64("かÿ".as_bytes()), "44GLw78=");
+        assert_eq!(super::base64(&[0xFB, 0xFF]), "+/8=");
+    }
+
+    #[test]
+    fn osc52() {
+        let terminal = Terminal::headless();
+        let mut clipboard = Clipboard::default();
+
+        clipboard.copy("quiet", &terminal);
+        assert!(terminal.output_buffer.is_empty());
+
+        clipboard.osc52 = true;
+        clipboard.copy("hello", &terminal);
+        assert_eq!(clipboard.get(), "hello");
+        assert_eq!(terminal.output_buffer.as_slice(), b"\x1B]52;c;aGVsbG8=\x07");
+    }
+}
@@ -1,4 +1,8 @@
-use std::fmt::{Display, Write};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Write},
+    time::{Duration, Instant},
+};
 
 #[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Key {
@@ -56,3 +60,115 @@ pub enum ScrollDirection {
     /// visible.
     Forwards,
 }
+
+/// Filters out key repeats that arrive faster than a set rate.
+///
+/// When a key is held, the terminal sends it over and over. This can make,
+/// for example, arrow-key navigation in lists move too fast. The filter lets
+/// the first press through immediately, then lets a repeat through after
+/// [`KeyRepeatFilter::initial_delay`], then one every
+/// [`KeyRepeatFilter::repeat_interval`].
+///
+/// Terminals don't report when keys are released, so a key is considered
+/// released once it hasn't been sent for longer than the initial delay. This
+/// means pressing the same key twice within the initial delay only counts as
+/// one press.
+pub struct KeyRepeatFilter {
+    /// How long a key must be held before it starts repeating.
+    pub initial_delay: Duration,
+    /// How long to wait between repeats, once a key has started repeating.
+    pub repeat_interval: Duration,
+    keys: HashMap<Key, KeyRepeatState>,
+}
+struct KeyRepeatState {
+    /// The last time the terminal sent this key.
+    last_seen: Instant,
+    /// The last time this key made it through the filter.
+    last_delivered: Instant,
+    /// If the key has already made it past the initial delay.
+    repeating: bool,
+}
+impl KeyRepeatFilter {
+    pub fn new(initial_delay: Duration, repeat_interval: Duration) -> Self {
+        Self {
+            initial_delay,
+            repeat_interval,
+            keys: HashMap::default(),
+        }
+    }
+
+    /// Filters the keys the terminal sent at `now` (`raw`), and stores the
+    /// keys that made it through the filter in `out`.
+    pub fn filter(&mut self, raw: &HashSet<Key>, now: Instant, out: &mut HashSet<Key>) {
+        out.clear();
+        self.keys
+            .retain(|_, state| now.duration_since(state.last_seen) <= self.initial_delay);
+
+        for key in raw {
+            let Some(state) = self.keys.get_mut(key) else {
+                self.keys.insert(
+                    *key,
+                    KeyRepeatState {
+                        last_seen: now,
+                        last_delivered: now,
+                        repeating: false,
+                    },
+                );
+                out.insert(*key);
+                continue;
+            };
+
+            let delay = if state.repeating {
+                self.repeat_interval
+            } else {
+                self.initial_delay
+            };
+            if now.duration_since(state.last_delivered) >= delay {
+                state.last_delivered = now;
+                state.repeating = true;
+                out.insert(*key);
+            }
+            state.last_seen = now;
+        }
+    }
+}
+impl Default for KeyRepeatFilter {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(300), Duration::from_millis(100))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_repeat_filter() {
+        let mut filter = KeyRepeatFilter::default();
+        let raw = HashSet::from([Key::ArrowDown]);
+        let mut out = HashSet::new();
+        let start = Instant::now();
+
+        // The terminal repeats the key every 25ms for 600ms
+        let mut delivered = Vec::new();
+        for ms in (0..=600).step_by(25) {
+            filter.filter(&raw, start + Duration::from_millis(ms), &mut out);
+            if out.contains(&Key::ArrowDown) {
+                delivered.push(ms);
+            }
+        }
+        assert_eq!(delivered, [0, 300, 400, 500, 600]);
+
+        // Nothing is sent for a while, so the key was released
+        filter.filter(
+            &HashSet::new(),
+            start + Duration::from_millis(1_000),
+            &mut out,
+        );
+        assert!(out.is_empty());
+
+        // So the next press goes through immediately
+        filter.filter(&raw, start + Duration::from_millis(1_010), &mut out);
+        assert!(out.contains(&Key::ArrowDown));
+    }
+}
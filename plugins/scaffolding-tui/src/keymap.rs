@@ -0,0 +1,157 @@
+//! Keyboard shortcuts that can be rebound.
+
+use {
+    crate::{
+        input::{Key, ModifierKeys},
+        terminal::Terminal,
+    },
+    std::collections::HashMap,
+};
+
+/// Maps keyboard shortcuts to actions, so apps can check if an action was
+/// triggered instead of checking for specific keys. Add one to the world as
+/// a singleton, with whatever type your app uses for its actions:
+///
+/// ```rs
+/// # use {scaffolding::world::World, scaffolding_tui::prelude::*};
+/// # let mut world = World::new();
+/// #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// enum Action {
+///     Save,
+///     Quit,
+/// }
+///
+/// let mut keymap = Keymap::default();
+/// keymap
+///     .bind(ModifierKeys { control: true, ..Default::default() }, Key::Text('s'), Action::Save)
+///     .bind(ModifierKeys::default(), Key::Escape, Action::Quit);
+/// world.add_singleton(keymap);
+/// ```
+///
+/// Then check for the action with [`Keymap::triggered`].
+pub struct Keymap<A> {
+    bindings: HashMap<(ModifierKeys, Key), A>,
+}
+impl<A: Copy + PartialEq> Keymap<A> {
+    /// Trigger `action` when `key` is pressed while holding exactly the
+    /// modifier keys in `modifiers`. This replaces any action that was
+    /// already bound to the same shortcut.
+    pub fn bind(&mut self, modifiers: ModifierKeys, key: Key, action: A) -> &mut Self {
+        self.bindings.insert((modifiers, key), action);
+        self
+    }
+    /// Remove the action bound to a shortcut, if there was one.
+    pub fn unbind(&mut self, modifiers: ModifierKeys, key: Key) -> Option<A> {
+        self.bindings.remove(&(modifiers, key))
+    }
+    /// The action bound to a shortcut, if there is one.
+    pub fn action(&self, modifiers: ModifierKeys, key: Key) -> Option<A> {
+        self.bindings.get(&(modifiers, key)).copied()
+    }
+
+    /// If any shortcut bound to `action` was pressed this frame.
+    pub fn triggered(&self, terminal: &Terminal, action: A) -> bool {
+        self.triggered_actions(terminal)
+            .any(|other| other == action)
+    }
+    /// Every action whose shortcut was pressed this frame.
+    pub fn triggered_actions<'a>(&'a self, terminal: &'a Terminal) -> impl Iterator<Item = A> + 'a {
+        terminal.pressed_keys.iter().flat_map(move |key| {
+            let modifiers = terminal.key_modifiers(*key);
+            let action = self.action(modifiers, *key);
+
+            // Terminals send control + a letter as an ASCII control
+            // character, so those can also trigger control + letter
+            // shortcuts
+            let control_action = match key {
+                Key::Text(char @ '\x01'..='\x1A') => self.action(
+                    ModifierKeys {
+                        control: true,
+                        ..modifiers
+                    },
+                    Key::Text((*char as u8 - 1 + b'a') as char),
+                ),
+                _ => None,
+            };
+
+            action.into_iter().chain(control_action)
+        })
+    }
+}
+impl<A> Default for Keymap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    enum Action {
+        Save,
+        Quit,
+    }
+
+    const CONTROL: ModifierKeys = ModifierKeys {
+        shift: false,
+        meta: false,
+        control: true,
+    };
+
+    fn keymap() -> Keymap<Action> {
+        let mut keymap = Keymap::default();
+        keymap.bind(CONTROL, Key::Text('s'), Action::Save).bind(
+            ModifierKeys::default(),
+            Key::Escape,
+            Action::Quit,
+        );
+        keymap
+    }
+
+    #[test]
+    fn control_shortcuts() {
+        let keymap = keymap();
+
+        // Most terminals send ctrl+s as ASCII control character 0x13
+        let mut terminal = Terminal::headless();
+        terminal.pressed_keys.insert(Key::Text('\x13'));
+        assert!(keymap.triggered(&terminal, Action::Save));
+        assert!(!keymap.triggered(&terminal, Action::Quit));
+
+        // ...and some report it as s with the control modifier
+        let mut terminal = Terminal::headless();
+        terminal.pressed_keys.insert(Key::Text('s'));
+        terminal.key_modifiers.insert(Key::Text('s'), CONTROL);
+        assert!(keymap.triggered(&terminal, Action::Save));
+
+        // The modifiers have to match
+        let mut terminal = Terminal::headless();
+        terminal.pressed_keys.insert(Key::Text('s'));
+        assert!(!keymap.triggered(&terminal, Action::Save));
+    }
+
+    #[test]
+    fn rebinding() {
+        let mut keymap = keymap();
+        let mut terminal = Terminal::headless();
+        terminal.pressed_keys.insert(Key::Escape);
+        assert_eq!(
+            keymap.triggered_actions(&terminal).collect::<Vec<_>>(),
+            [Action::Quit]
+        );
+
+        assert_eq!(
+            keymap.unbind(ModifierKeys::default(), Key::Escape),
+            Some(Action::Quit)
+        );
+        keymap.bind(CONTROL, Key::Text('q'), Action::Quit);
+        assert!(!keymap.triggered(&terminal, Action::Quit));
+
+        terminal.pressed_keys.insert(Key::Text('\x11'));
+        assert!(keymap.triggered(&terminal, Action::Quit));
+    }
+}
@@ -1,27 +1,43 @@
+pub mod ansi;
+mod box_drawing;
+pub mod capabilities;
+pub mod clipboard;
 pub mod input;
+pub mod keymap;
+pub mod logging;
 pub mod msg;
 pub mod os;
 pub mod runloop;
 pub mod shapes;
 pub mod terminal;
+pub mod toasts;
 pub mod widgets;
 
 pub mod prelude {
     pub use crate::{
-        input::Key,
+        capabilities::ColourMode,
+        clipboard::Clipboard,
+        input::{Key, ModifierKeys},
+        keymap::Keymap,
+        logging::{LogBuffer, LogLevel},
         msg::TuiMsg,
         runloop::TuiRunloop,
         shapes::*,
-        terminal::Terminal,
+        terminal::{CursorStyle, Terminal},
+        toasts::Toasts,
         widgets::{
-            Button, ButtonState, Checkbox, Frame, HAlign, HorizontalOverflowStyle, Text, TextInput,
-            TextStyle, VAlign, VerticalOverflowStyle,
+            Button, ButtonState, Canvas, Checkbox, Dropdown, Frame, Grid, HAlign,
+            HorizontalOverflowStyle, KeyHints, LogPane, Text, TextInput, TextStyle, VAlign,
+            VerticalOverflowStyle,
         },
         App, Colour, TuiPlugin,
     };
 }
 
-use {msg::TuiMsg, scaffolding::plugin_prelude::*, terminal::Terminal, widgets::Widget};
+use {
+    clipboard::Clipboard, logging::LogBuffer, msg::TuiMsg, scaffolding::plugin_prelude::*,
+    scaffolding::world::Time, terminal::Terminal, toasts::Toasts, widgets::Widget,
+};
 
 #[derive(Default)]
 pub struct TuiPlugin {}
@@ -29,6 +45,9 @@ impl Plugin for TuiPlugin {
     fn load(&mut self, world: &mut World) {
         world
             .add_singleton(Terminal::default())
+            .add_singleton(Clipboard::default())
+            .add_singleton(Toasts::default())
+            .add_singleton(LogBuffer::default())
             .add_msg_handler(msg::tui_msg_handler);
     }
 }
@@ -44,12 +63,49 @@ impl ExecutableArg for App<'_> {
 }
 impl App<'_> {
     pub fn draw<'a, Args, D: Drawable<'a, Args>>(&self, drawable: D) -> D::Output {
-        drawable.build().execute(self.0)
+        self.draw_labelled(core::any::type_name::<D>(), drawable)
+    }
+    /// Like [`App::draw`], but if the world has a [`ProfileData`] singleton,
+    /// the time it takes to draw is recorded under `label` instead of the
+    /// drawable's type name.
+    ///
+    /// [`ProfileData`]: scaffolding::world::ProfileData
+    pub fn draw_labelled<'a, Args, D: Drawable<'a, Args>>(
+        &self,
+        label: &'static str,
+        drawable: D,
+    ) -> D::Output {
+        let executable = drawable.build();
+        self.0.profile(label, || executable.execute(self.0))
+    }
+
+    /// Draw the [`Toasts`] singleton's toasts on top of everything else,
+    /// removing any that have expired. This needs a [`Time`] singleton,
+    /// which [`TuiRunloop`] adds.
+    ///
+    /// [`TuiRunloop`]: runloop::TuiRunloop
+    pub fn draw_toasts(&self) {
+        let now = self.0.get_singleton::<Time>().elapsed();
+        self.0
+            .get_singleton::<Toasts>()
+            .draw(self.0.get_singleton(), now);
     }
 
     pub fn exit(&self) {
         self.0.send_msg(TuiMsg::ExitRunloop);
     }
+    /// See [`TuiMsg::RequestRedraw`].
+    pub fn request_redraw(&self) {
+        self.0.send_msg(TuiMsg::RequestRedraw);
+    }
+    /// See [`TuiMsg::ForceFullRedraw`].
+    pub fn force_full_redraw(&self) {
+        self.0.send_msg(TuiMsg::ForceFullRedraw);
+    }
+    /// See [`TuiMsg::SetTargetFps`].
+    pub fn set_target_fps(&self, fps: u32) {
+        self.0.send_msg(TuiMsg::SetTargetFps(fps));
+    }
 }
 
 /// Types that can be used with [`App::draw`]. This is implemented for
@@ -73,7 +129,9 @@ impl<'a, Args: 'a, E: Executable<'a, Args>> Drawable<'a, Option<Args>> for E {
     }
 }
 
-#[derive(Clone, Copy)]
+/// An RGB colour. Colours are ordered by their red, then green, then blue
+/// channel.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct Colour {
     pub r: u8,
     pub g: u8,
@@ -90,4 +148,104 @@ impl Colour {
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
         Self { r, g, b }
     }
+
+    /// The closest colour in the terminal's 256-colour palette.
+    pub fn to_ansi256(self) -> u8 {
+        // Greys have their own, more precise, ramp
+        if self.r == self.g && self.g == self.b {
+            return match self.r {
+                0..8 => 16,
+                249.. => 231,
+                grey => 232 + ((grey as u16 - 8) * 24 / 241) as u8,
+            };
+        }
+
+        // Otherwise, it's in the 6x6x6 colour cube
+        let scale = |channel: u8| ((channel as u16 * 5 + 127) / 255) as u8;
+        16 + 36 * scale(self.r) + 6 * scale(self.g) + scale(self.b)
+    }
+    /// The RGB value of a colour in the terminal's 256-colour palette. Colours
+    /// 0-15 are the basic colours; see [`Colour::from_ansi16`].
+    pub fn from_ansi256(colour: u8) -> Self {
+        // The levels used by xterm's colour cube
+        const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+        match colour {
+            0..16 => Self::from_ansi16(colour),
+            16..232 => {
+                let colour = colour - 16;
+                Self::new(
+                    LEVELS[(colour / 36) as usize],
+                    LEVELS[(colour / 6 % 6) as usize],
+                    LEVELS[(colour % 6) as usize],
+                )
+            }
+            grey => {
+                let grey = 8 + (grey - 232) * 10;
+                Self::new(grey, grey, grey)
+            }
+        }
+    }
+    /// The RGB value of one of the 16 basic terminal colours, from 0 (black)
+    /// to 15 (bright white). Terminals all use slightly different values for
+    /// these; this uses the VGA palette.
+    pub fn from_ansi16(colour: u8) -> Self {
+        const PALETTE: [Colour; 16] = [
+            Colour::new(0, 0, 0),
+            Colour::new(170, 0, 0),
+            Colour::new(0, 170, 0),
+            Colour::new(170, 85, 0),
+            Colour::new(0, 0, 170),
+            Colour::new(170, 0, 170),
+            Colour::new(0, 170, 170),
+            Colour::new(170, 170, 170),
+            Colour::new(85, 85, 85),
+            Colour::new(255, 85, 85),
+            Colour::new(85, 255, 85),
+            Colour::new(255, 255, 85),
+            Colour::new(85, 85, 255),
+            Colour::new(255, 85, 255),
+            Colour::new(85, 255, 255),
+            Colour::new(255, 255, 255),
+        ];
+
+        PALETTE[colour as usize % 16]
+    }
+    /// Blend this colour over `existing`, as if this colour was drawn on top
+    /// of it with the given opacity. An `alpha` of 1 is just this colour, and
+    /// an `alpha` of 0 is just `existing`.
+    pub fn over_cell(&self, existing: Colour, alpha: f32) -> Self {
+        let alpha = alpha.clamp(0., 1.);
+        let blend =
+            |top: u8, bottom: u8| (top as f32 * alpha + bottom as f32 * (1. - alpha)).round() as u8;
+
+        Self::new(
+            blend(self.r, existing.r),
+            blend(self.g, existing.g),
+            blend(self.b, existing.b),
+        )
+    }
+    /// Multiply each channel by `factor`, so a `factor` below 1 makes the
+    /// colour darker.
+    pub fn scaled(&self, factor: f32) -> Self {
+        let scale = |channel: u8| (channel as f32 * factor).round().clamp(0., 255.) as u8;
+        Self::new(scale(self.r), scale(self.g), scale(self.b))
+    }
+    /// The closest of the 16 basic terminal colours, from 0 (black) to 15
+    /// (bright white).
+    pub fn to_ansi16(self) -> u8 {
+        let brightest = self.r.max(self.g).max(self.b);
+        if brightest < 64 {
+            return 0;
+        }
+
+        // Channels at least half as bright as the brightest one are on
+        let on = |channel: u8| (channel as u16 * 2 >= brightest as u16) as u8;
+        let colour = on(self.r) | (on(self.g) << 1) | (on(self.b) << 2);
+        if brightest > 191 {
+            colour + 8
+        } else {
+            colour
+        }
+    }
 }
@@ -0,0 +1,188 @@
+//! Recent log messages, for showing inside the app with a
+//! [`LogPane`](crate::widgets::LogPane).
+
+use {
+    crate::Colour,
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// How important a log message is, from most to least important.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+impl LogLevel {
+    /// The colour messages with this level are drawn in.
+    pub fn colour(self) -> Colour {
+        match self {
+            Self::Error => Colour::RED,
+            Self::Warn => Colour::new(255, 255, 0),
+            Self::Info => Colour::WHITE,
+            Self::Debug => Colour::new(0, 170, 255),
+            Self::Trace => Colour::GREY,
+        }
+    }
+    /// The level's name, padded so every level is the same width.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN ",
+            Self::Info => "INFO ",
+            Self::Debug => "DEBUG",
+            Self::Trace => "TRACE",
+        }
+    }
+}
+#[cfg(feature = "log")]
+impl From<log::Level> for LogLevel {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Self::Error,
+            log::Level::Warn => Self::Warn,
+            log::Level::Info => Self::Info,
+            log::Level::Debug => Self::Debug,
+            log::Level::Trace => Self::Trace,
+        }
+    }
+}
+
+/// A single log message.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub message: String,
+}
+
+/// The most recent log messages. This is a singleton added by
+/// [`TuiPlugin`](crate::TuiPlugin); once it's full, pushing a message drops
+/// the oldest one.
+///
+/// Clones share the same messages, so a clone can be moved somewhere else
+/// (like another thread, or a logger) to push messages to the app.
+#[derive(Clone)]
+pub struct LogBuffer {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+    capacity: usize,
+}
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::with_capacity(1_000)
+    }
+}
+impl LogBuffer {
+    /// Create a buffer that keeps up to `capacity` messages.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            records: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Add a message, dropping the oldest one if the buffer is full.
+    pub fn push(&self, level: LogLevel, message: impl Into<String>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut records = self.records.lock().unwrap();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(LogRecord {
+            level,
+            message: message.into(),
+        });
+    }
+    /// The `count` newest messages, oldest first.
+    pub fn newest(&self, count: usize) -> Vec<LogRecord> {
+        let records = self.records.lock().unwrap();
+        let skip = records.len().saturating_sub(count);
+        records.iter().skip(skip).cloned().collect()
+    }
+    /// Every message in the buffer, oldest first.
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.newest(self.capacity)
+    }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    /// Make this buffer the `log` crate's logger, so every record logged
+    /// with [`log`]'s macros at or below `max_level` is pushed to it. Like
+    /// [`log::set_logger`], this fails if there's already a logger.
+    #[cfg(feature = "log")]
+    pub fn install_logger(&self, max_level: log::LevelFilter) -> Result<(), log::SetLoggerError> {
+        log::set_logger(Box::leak(Box::new(self.clone())))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+#[cfg(feature = "log")]
+impl log::Log for LogBuffer {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+    fn log(&self, record: &log::Record) {
+        self.push(record.level().into(), record.args().to_string());
+    }
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity() {
+        let buffer = LogBuffer::with_capacity(3);
+        for idx in 0..5 {
+            buffer.push(LogLevel::Info, idx.to_string());
+        }
+
+        let messages: Vec<_> = buffer.records().into_iter().map(|r| r.message).collect();
+        assert_eq!(messages, ["2", "3", "4"]);
+        assert_eq!(buffer.newest(2)[0].message, "3");
+
+        // Clones share messages
+        buffer.clone().push(LogLevel::Error, "5");
+        assert_eq!(buffer.newest(1)[0].level, LogLevel::Error);
+        assert_eq!(buffer.len(), 3);
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn log_records() {
+        use log::Log;
+
+        let buffer = LogBuffer::default();
+        buffer.log(
+            &log::Record::builder()
+                .level(log::Level::Warn)
+                .args(format_args!("careful: {}", 42))
+                .build(),
+        );
+        assert_eq!(
+            buffer.records(),
+            [LogRecord {
+                level: LogLevel::Warn,
+                message: String::from("careful: 42"),
+            }]
+        );
+    }
+}
@@ -14,6 +14,26 @@ pub enum TuiMsg {
     ///
     /// [`TuiRunloop`]: crate::runloop::TuiRunloop
     UpdateTerminal,
+    /// If the app is running in a [`TuiRunloop`], this makes the runloop
+    /// start the next frame immediately, instead of waiting for the rest of
+    /// the current frame's time.
+    ///
+    /// If the runloop only redraws on demand (see
+    /// [`TuiRunloop::redraw_on_demand`]), this instead makes sure the next
+    /// frame is drawn, without cutting the current frame short. Widgets that
+    /// animate need to send this every frame in that mode.
+    ///
+    /// [`TuiRunloop::redraw_on_demand`]: crate::runloop::TuiRunloop::redraw_on_demand
+    /// [`TuiRunloop`]: crate::runloop::TuiRunloop
+    RequestRedraw,
+    /// Redraw the entire screen on the next [`TuiMsg::UpdateTerminal`], even
+    /// if nothing changed since the last frame.
+    ForceFullRedraw,
+    /// If the app is running in a [`TuiRunloop`], this changes how many
+    /// frames the runloop tries to render each second. 0 is treated as 1.
+    ///
+    /// [`TuiRunloop`]: crate::runloop::TuiRunloop
+    SetTargetFps(u32),
 }
 
 pub fn tui_msg_handler(world: &mut World, msg: Msg<TuiMsg>) {
@@ -22,5 +42,8 @@ pub fn tui_msg_handler(world: &mut World, msg: Msg<TuiMsg>) {
     match msg.read() {
         TuiMsg::ExitRunloop => terminal.exit = true,
         TuiMsg::UpdateTerminal => terminal.update(),
+        TuiMsg::RequestRedraw => terminal.redraw_requested = true,
+        TuiMsg::ForceFullRedraw => terminal.force_full_redraw = true,
+        TuiMsg::SetTargetFps(fps) => terminal.target_fps = Some(fps),
     }
 }
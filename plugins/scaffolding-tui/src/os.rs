@@ -5,10 +5,23 @@ use crate::terminal::Terminal;
 pub trait OsTrait: Default + Clone {
     /// Get the terminal's size, in rows and columns.
     fn terminal_size(&self) -> (u16, u16);
+    /// If the terminal may have been resized since the last time this was
+    /// called. [`Terminal::update`] only calls [`OsTrait::terminal_size`] when
+    /// this returns true.
+    fn take_resized(&self) -> bool {
+        true
+    }
+    /// Undo any process-wide changes made when the [`Os`] was created, such
+    /// as installing signal handlers. [`Terminal::on_drop`] calls this.
+    fn restore(&self) {}
     /// Toggle raw mode.
     ///
     /// In raw mode, the terminal will report key events to us immediately,
     /// instead of when the user hits enter.
+    ///
+    /// Errors disabling raw mode are ignored, since that happens while the
+    /// terminal is being reset and it may already be gone (see
+    /// [`Terminal::disconnected`]).
     fn set_raw_mode(&self, enabled: bool);
     /// Read input from the user and update the terminal's state. This updates
     /// the mouse location, pressed keys, etc.
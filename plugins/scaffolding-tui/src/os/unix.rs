@@ -3,13 +3,105 @@ use {
     crate::{input::*, prelude::Terminal},
     libc::termios as Termios,
     std::{
-        io::{stdin, ErrorKind, Read},
-        mem::MaybeUninit,
+        io::{self, stdin, ErrorKind},
+        mem::{self, MaybeUninit},
         os::fd::{AsRawFd, RawFd},
-        str,
+        ptr, str,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Mutex,
+        },
     },
 };
 
+/// Set by the `SIGWINCH` handler when the terminal is resized. This starts as
+/// true so the terminal's size is queried on the first frame.
+static RESIZED: AtomicBool = AtomicBool::new(true);
+/// The `SIGWINCH` action from before ours was installed, if ours is installed.
+/// [`uninstall_sigwinch_handler`] puts it back.
+static PREVIOUS_SIGWINCH_ACTION: Mutex<Option<libc::sigaction>> = Mutex::new(None);
+/// The previous `SIGWINCH` action's handler, and whether it was installed
+/// with `SA_SIGINFO`. These are copied out of [`PREVIOUS_SIGWINCH_ACTION`] so
+/// our handler can call the previous one without locking a mutex, which
+/// isn't safe in a signal handler.
+static PREVIOUS_SIGWINCH_HANDLER: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+static PREVIOUS_SIGWINCH_SIGINFO: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sigwinch(
+    signal: libc::c_int,
+    info: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    RESIZED.store(true, Ordering::Relaxed);
+
+    // The host app may have its own handler, which still needs to run
+    let previous = PREVIOUS_SIGWINCH_HANDLER.load(Ordering::Relaxed);
+    if previous == libc::SIG_DFL || previous == libc::SIG_IGN {
+        return;
+    }
+    unsafe {
+        if PREVIOUS_SIGWINCH_SIGINFO.load(Ordering::Relaxed) {
+            let previous: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                mem::transmute(previous);
+            previous(signal, info, context);
+        } else {
+            let previous: extern "C" fn(libc::c_int) = mem::transmute(previous);
+            previous(signal);
+        }
+    }
+}
+
+/// Installs a `SIGWINCH` handler that sets [`RESIZED`], so we only have to
+/// query the terminal's size after it actually changes. Any handler that was
+/// already installed is called from ours, and is put back by
+/// [`uninstall_sigwinch_handler`]. Installing the handler again before
+/// uninstalling it does nothing.
+///
+/// This also sets [`RESIZED`], so a new terminal queries its size on its
+/// first frame even if an earlier terminal already cleared the flag.
+fn install_sigwinch_handler() {
+    let mut previous_action = PREVIOUS_SIGWINCH_ACTION.lock().unwrap();
+    RESIZED.store(true, Ordering::Relaxed);
+    if previous_action.is_some() {
+        return;
+    }
+
+    unsafe {
+        let mut previous: libc::sigaction = mem::zeroed();
+        if libc::sigaction(libc::SIGWINCH, ptr::null(), &mut previous) != 0 {
+            panic!("scaffolding-tui: Failed to get the current SIGWINCH handler");
+        }
+        PREVIOUS_SIGWINCH_HANDLER.store(previous.sa_sigaction, Ordering::Relaxed);
+        PREVIOUS_SIGWINCH_SIGINFO
+            .store(previous.sa_flags & libc::SA_SIGINFO != 0, Ordering::Relaxed);
+
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = on_sigwinch
+            as extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void)
+            as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART | libc::SA_SIGINFO;
+        libc::sigemptyset(&mut action.sa_mask);
+
+        if libc::sigaction(libc::SIGWINCH, &action, ptr::null_mut()) != 0 {
+            panic!("scaffolding-tui: Failed to install SIGWINCH handler");
+        }
+
+        *previous_action = Some(previous);
+    }
+}
+
+/// Puts back the `SIGWINCH` action that was there before
+/// [`install_sigwinch_handler`], if our handler is installed.
+fn uninstall_sigwinch_handler() {
+    let Some(previous) = PREVIOUS_SIGWINCH_ACTION.lock().unwrap().take() else {
+        return;
+    };
+
+    unsafe { libc::sigaction(libc::SIGWINCH, &previous, ptr::null_mut()) };
+    PREVIOUS_SIGWINCH_HANDLER.store(libc::SIG_DFL, Ordering::Relaxed);
+    PREVIOUS_SIGWINCH_SIGINFO.store(false, Ordering::Relaxed);
+}
+
 #[repr(C)]
 #[derive(Default)]
 struct Winsize {
@@ -31,6 +123,10 @@ pub struct Os {
     stdin: RawFd,
     /// A buffer for reading text input from stdin.
     input_buffer: Vec<u8>,
+    /// Bytes at the end of the last read that were an incomplete escape
+    /// sequence or UTF-8 character. The rest of it should come with the next
+    /// read, so these are put at the start of the next read's input.
+    unparsed: Vec<u8>,
 }
 impl Default for Os {
     fn default() -> Self {
@@ -52,15 +148,30 @@ impl Default for Os {
             raw_termios.assume_init()
         };
 
+        install_sigwinch_handler();
+
         Self {
             original_termios: termios,
             raw_termios,
             stdin: stdin().as_raw_fd(),
             input_buffer: Vec::new(),
+            unparsed: Vec::new(),
         }
     }
 }
 impl Os {
+    /// An [`Os`] that never touches the real terminal, for tests.
+    #[cfg(test)]
+    pub fn headless() -> Self {
+        Self {
+            original_termios: unsafe { MaybeUninit::zeroed().assume_init() },
+            raw_termios: unsafe { MaybeUninit::zeroed().assume_init() },
+            stdin: stdin().as_raw_fd(),
+            input_buffer: Vec::new(),
+            unparsed: Vec::new(),
+        }
+    }
+
     /// Read from stdin without blocking the current thread.
     ///
     /// Normally, reading from stdin when it's empty causes the thread to block
@@ -68,11 +179,17 @@ impl Os {
     /// because it will cause the app to freeze when the user isn't actively
     /// typing/moving their mouse.
     ///
-    /// This method will clear `buffer`, then write the bytes from stdin (if
-    /// there are any) to `buffer` afterwards.
-    fn read_stdin_no_block(&mut self) {
-        self.input_buffer.clear();
-        self.input_buffer.resize(10, 0);
+    /// This method will clear [`Os::input_buffer`], except for the bytes that
+    /// weren't parsed last update, then write the bytes from stdin (if there
+    /// are any) to it afterwards.
+    ///
+    /// Returns true if stdin was closed, such as when the terminal emulator
+    /// is closed or the pipe it's reading from breaks. Any bytes read before
+    /// that are still put in the input buffer.
+    fn read_stdin_no_block(&mut self) -> bool {
+        self.reset_input_buffer();
+        let mut bytes_read = self.input_buffer.len();
+        self.input_buffer.resize(bytes_read + 10, 0);
 
         // https://stackoverflow.com/a/68174244
         let flags = unsafe { libc::fcntl(self.stdin, libc::F_GETFL) };
@@ -82,9 +199,22 @@ impl Os {
             libc::fcntl(self.stdin, libc::F_SETFL, flags_nonblock);
         }
 
-        let mut bytes_read = 0;
+        let mut closed = false;
         loop {
-            match stdin().read(&mut self.input_buffer[bytes_read..]) {
+            let unread = &mut self.input_buffer[bytes_read..];
+            let res = unsafe { libc::read(self.stdin, unread.as_mut_ptr().cast(), unread.len()) };
+            let res = if res < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(res as usize)
+            };
+
+            match res {
+                // EOF - nothing will ever be written to stdin again
+                Ok(0) => {
+                    closed = true;
+                    break;
+                }
                 Ok(len) => {
                     bytes_read += len;
                     self.input_buffer.resize(self.input_buffer.len() * 2, 0);
@@ -93,7 +223,13 @@ impl Os {
                     ErrorKind::WouldBlock => {
                         break;
                     }
-                    _ => panic!("Failed to read from stdin: {err}"),
+                    ErrorKind::Interrupted => {}
+                    // Reading from a terminal that's gone fails with `EIO`,
+                    // and a closed fd fails with `EBADF`
+                    _ => {
+                        closed = true;
+                        break;
+                    }
                 },
             }
         }
@@ -101,8 +237,17 @@ impl Os {
         self.input_buffer.truncate(bytes_read);
 
         unsafe {
-            libc::fcntl(self.stdin, flags);
+            libc::fcntl(self.stdin, libc::F_SETFL, flags);
         }
+
+        closed
+    }
+
+    /// Clear the input buffer, except for the bytes that weren't parsed in
+    /// the last update (see [`Os::unparsed`]).
+    fn reset_input_buffer(&mut self) {
+        self.input_buffer.clear();
+        self.input_buffer.append(&mut self.unparsed);
     }
 }
 impl OsTrait for Os {
@@ -116,6 +261,12 @@ impl OsTrait for Os {
 
         (size.col - 1, size.row - 1)
     }
+    fn take_resized(&self) -> bool {
+        RESIZED.swap(false, Ordering::Relaxed)
+    }
+    fn restore(&self) {
+        uninstall_sigwinch_handler();
+    }
     fn set_raw_mode(&self, enabled: bool) {
         let termios = if enabled {
             &self.raw_termios
@@ -125,12 +276,26 @@ impl OsTrait for Os {
 
         let res = unsafe { libc::tcsetattr(self.stdin, libc::TCSAFLUSH, termios) };
 
-        if res != 0 {
+        if res != 0 && enabled {
             panic!("scaffolding-tui::os::unix::Os::set_raw_mode: tcsetattr call had an error");
         }
     }
     fn update(terminal: &mut Terminal) {
-        terminal.os.read_stdin_no_block();
+        if terminal.os.read_stdin_no_block() {
+            terminal.disconnected = true;
+        }
+        Self::parse_input(terminal);
+    }
+}
+impl Os {
+    /// Update the terminal's state from the input in [`Os::input_buffer`].
+    fn parse_input(terminal: &mut Terminal) {
+        // Save anything that got cut off for the next update
+        let os = &mut terminal.os;
+        let complete = os.input_buffer.len() - incomplete_suffix(&os.input_buffer);
+        os.unparsed.extend_from_slice(&os.input_buffer[complete..]);
+        os.input_buffer.truncate(complete);
+
         let mut stdin = terminal
             .os
             .input_buffer
@@ -146,6 +311,30 @@ impl OsTrait for Os {
                     // movement or a special character
                     let next = stdin.next();
                     if matches!(next, Some((_, b'['))) {
+                        // Kitty keyboard protocol reports, in the format
+                        // ESC[<key code>;<modifiers>u, or ESC[?<flags>u when
+                        // the terminal acknowledges the protocol
+                        let input = &terminal.os.input_buffer;
+                        let params_start = idx + 2;
+                        let end = input[params_start..]
+                            .iter()
+                            .position(|byte| (0x40..=0x7E).contains(byte))
+                            .map(|len| params_start + len);
+                        if let Some(end) = end.filter(|end| input[*end] == b'u') {
+                            while stdin.next_if(|(idx, _)| *idx <= end).is_some() {}
+
+                            match parse_kitty_report(&input[params_start..end]) {
+                                Some(KittyReport::Flags(_)) => terminal.kitty_keyboard = true,
+                                Some(KittyReport::Key(key, modifiers)) => {
+                                    terminal.modifier_keys = modifiers;
+                                    terminal.pressed_keys.insert(key);
+                                    terminal.key_modifiers.insert(key, modifiers);
+                                }
+                                None => eprintln!("WARN: Unknown Kitty keyboard escape sequence"),
+                            }
+                            continue;
+                        }
+
                         let Some((_, next)) = stdin.next() else {
                             eprintln!("WARN: Received incomplete escape code from terminal");
                             continue;
@@ -246,8 +435,44 @@ impl OsTrait for Os {
                                 terminal.pressed_keys.insert(Key::ArrowLeft);
                             }
 
+                            // Keys pressed with modifiers, in the format
+                            // ESC[1;<modifiers><key>
+                            b'1' if stdin.peek().map(|(_, byte)| *byte) == Some(b';') => {
+                                stdin.next();
+                                let Some((_, modifiers)) = stdin.next() else {
+                                    eprintln!("WARN: Got incomplete modified key sequence");
+                                    continue;
+                                };
+
+                                // The modifiers are 1 + a bitmask of shift,
+                                // meta, and control
+                                let modifiers = modifiers.wrapping_sub(b'1');
+                                let modifiers = ModifierKeys {
+                                    shift: (modifiers & 0b001) != 0,
+                                    meta: (modifiers & 0b010) != 0,
+                                    control: (modifiers & 0b100) != 0,
+                                };
+                                terminal.modifier_keys = modifiers;
+
+                                let key = match stdin.next().map(|(_, byte)| byte) {
+                                    Some(b'A') => Key::ArrowUp,
+                                    Some(b'B') => Key::ArrowDown,
+                                    Some(b'C') => Key::ArrowRight,
+                                    Some(b'D') => Key::ArrowLeft,
+                                    Some(b'H') => Key::Home,
+                                    Some(b'F') => Key::End,
+                                    _ => {
+                                        eprintln!("WARN: Unknown modified key escape sequence");
+                                        continue;
+                                    }
+                                };
+                                terminal.pressed_keys.insert(key);
+                                terminal.key_modifiers.insert(key, modifiers);
+                            }
+
                             // Group of special keys that end with ~
-                            other if stdin.next().map(|(_, byte)| byte) == Some(b'~') => {
+                            other if stdin.peek().map(|(_, byte)| *byte) == Some(b'~') => {
+                                stdin.next();
                                 match other {
                                     b'5' => {
                                         terminal.pressed_keys.insert(Key::PageUp);
@@ -279,24 +504,21 @@ impl OsTrait for Os {
                             b'F' => {
                                 terminal.pressed_keys.insert(Key::End);
                             }
-                            b'O' => {
-                                let Some((_, next)) = stdin.next() else {
-                                    println!("WARN: Got incomplete control key sequence ESC[O");
-                                    continue;
-                                };
-                                match next {
-                                    b'H' => {
-                                        terminal.pressed_keys.insert(Key::Home);
-                                    }
-                                    b'F' => {
-                                        terminal.pressed_keys.insert(Key::End);
-                                    }
-                                    _ => println!(
-                                        "WARN: Unknown special key escape sequence: ESC[O{}",
-                                        next as char
-                                    ),
-                                }
+                            // Focus events
+                            b'I' => terminal.focused = true,
+                            // ESC[O on its own means the terminal lost focus
+                            b'O' if !matches!(stdin.peek(), Some((_, b'H' | b'F'))) => {
+                                terminal.focused = false;
                             }
+                            b'O' => match stdin.next() {
+                                Some((_, b'H')) => {
+                                    terminal.pressed_keys.insert(Key::Home);
+                                }
+                                Some((_, b'F')) => {
+                                    terminal.pressed_keys.insert(Key::End);
+                                }
+                                _ => unreachable!(),
+                            },
 
                             _ => {}
                         }
@@ -336,3 +558,370 @@ impl OsTrait for Os {
         }
     }
 }
+
+/// A `CSI u` sequence from the Kitty keyboard protocol.
+#[derive(PartialEq, Debug)]
+enum KittyReport {
+    /// The terminal's answer to a query for the protocol's flags. Only
+    /// terminals that support the protocol answer, so this means it's
+    /// enabled.
+    Flags(u16),
+    /// A key, and the modifiers it was pressed with.
+    Key(Key, ModifierKeys),
+}
+
+/// Parse the parameters of a Kitty keyboard protocol report; that's
+/// everything between `ESC[` and the final `u`.
+fn parse_kitty_report(params: &[u8]) -> Option<KittyReport> {
+    let params = str::from_utf8(params).ok()?;
+    if let Some(flags) = params.strip_prefix('?') {
+        return flags.parse().ok().map(KittyReport::Flags);
+    }
+
+    // Parameters can have sub-parameters after a `:`, like alternate key
+    // codes; we only need the first one
+    let mut params = params
+        .split(';')
+        .map(|param| param.split(':').next().unwrap());
+    let code: u32 = params.next()?.parse().ok()?;
+    // Like the legacy encoding, the modifiers are 1 + a bitmask of shift,
+    // meta, and control (and other modifiers we don't support)
+    let modifiers = match params.next() {
+        Some(modifiers) if !modifiers.is_empty() => {
+            modifiers.parse::<u16>().ok()?.checked_sub(1)?
+        }
+        _ => 0,
+    };
+
+    let key = match code {
+        27 => Key::Escape,
+        127 => Key::Backspace,
+        // Keys without a character, like F13 or keypad keys, use codes in
+        // Unicode's private use area; we don't support any of them yet
+        0xE000..=0xF8FF => return None,
+        // Tab and Enter are sent as Ctrl+I and Ctrl+M are in the legacy
+        // encoding, so they're still `Key::Text('\t')` and `Key::Text('\r')`
+        code => Key::Text(char::from_u32(code)?),
+    };
+    let modifiers = ModifierKeys {
+        shift: (modifiers & 0b001) != 0,
+        meta: (modifiers & 0b010) != 0,
+        control: (modifiers & 0b100) != 0,
+    };
+    Some(KittyReport::Key(key, modifiers))
+}
+
+/// How many bytes at the end of `input` are the start of an escape sequence
+/// or UTF-8 character that was cut off.
+fn incomplete_suffix(input: &[u8]) -> usize {
+    // Anything longer than this is garbage, not a sequence that got cut off
+    const MAX_SEQUENCE_LEN: usize = 32;
+
+    // Control sequences start with ESC[, and end with a byte in 0x40..=0x7E.
+    // A lone ESC is the escape key, so it's complete.
+    if let Some(start) = input.iter().rposition(|byte| *byte == b'\x1B') {
+        let sequence = &input[start..];
+        if sequence.get(1) == Some(&b'[')
+            && sequence.len() < MAX_SEQUENCE_LEN
+            && !sequence[2..]
+                .iter()
+                .any(|byte| (0x40..=0x7E).contains(byte))
+        {
+            return sequence.len();
+        }
+    }
+
+    // The first byte of a UTF-8 character says how many bytes it has
+    for len in 1..=3.min(input.len()) {
+        let byte = input[input.len() - len];
+        if byte & 0b1100_0000 == 0b1000_0000 {
+            // Continuation byte
+            continue;
+        }
+
+        let expected = match byte.leading_ones() {
+            expected @ 2..=4 => expected as usize,
+            _ => 1,
+        };
+        return if expected > len { len } else { 0 };
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse `input` as if it was read from stdin.
+    fn parse(input: &[u8]) -> Terminal {
+        let mut terminal = Terminal::headless();
+        feed(&mut terminal, input);
+        terminal
+    }
+    /// Parse `input` as if it was read from stdin in a new update.
+    fn feed(terminal: &mut Terminal, input: &[u8]) {
+        terminal.pressed_keys.clear();
+        terminal.os.reset_input_buffer();
+        terminal.os.input_buffer.extend_from_slice(input);
+        Os::parse_input(terminal);
+    }
+
+    #[test]
+    fn reading_restores_stdin_flags() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let [read, write] = fds;
+        assert_eq!(unsafe { libc::write(write, b"a".as_ptr().cast(), 1) }, 1);
+
+        let mut os = Os::headless();
+        os.stdin = read;
+        let flags = unsafe { libc::fcntl(read, libc::F_GETFL) };
+        os.read_stdin_no_block();
+        assert_eq!(os.input_buffer, b"a");
+        assert_eq!(unsafe { libc::fcntl(read, libc::F_GETFL) }, flags);
+
+        unsafe {
+            libc::close(read);
+            libc::close(write);
+        }
+    }
+
+    #[test]
+    fn eof_disconnects() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let [read, write] = fds;
+        assert_eq!(unsafe { libc::write(write, b"a".as_ptr().cast(), 1) }, 1);
+
+        let mut terminal = Terminal::headless();
+        terminal.os.stdin = read;
+        Os::update(&mut terminal);
+        assert!(!terminal.disconnected);
+        assert_eq!(terminal.pressed_keys, [Key::Text('a')].into());
+
+        // Input that was sent before stdin closed still gets read
+        assert_eq!(unsafe { libc::write(write, b"b".as_ptr().cast(), 1) }, 1);
+        unsafe { libc::close(write) };
+        terminal.pressed_keys.clear();
+        Os::update(&mut terminal);
+        assert!(terminal.disconnected);
+        assert_eq!(terminal.pressed_keys, [Key::Text('b')].into());
+
+        unsafe { libc::close(read) };
+    }
+
+    #[test]
+    fn split_input() {
+        let mut terminal = Terminal::headless();
+
+        // An escape sequence split between two reads
+        feed(&mut terminal, b"a\x1B[1;");
+        assert_eq!(terminal.pressed_keys, [Key::Text('a')].into());
+        feed(&mut terminal, b"5C");
+        assert_eq!(terminal.pressed_keys, [Key::ArrowRight].into());
+        assert!(terminal.key_pressed_with(
+            Key::ArrowRight,
+            ModifierKeys {
+                control: true,
+                ..Default::default()
+            }
+        ));
+
+        // A UTF-8 character split between two reads
+        feed(&mut terminal, &"か".as_bytes()[..2]);
+        assert!(terminal.pressed_keys.is_empty());
+        feed(&mut terminal, &"か".as_bytes()[2..]);
+        assert_eq!(terminal.pressed_keys, [Key::Text('か')].into());
+
+        // A lone escape is the escape key, not a cut off sequence
+        feed(&mut terminal, b"\x1B");
+        assert_eq!(terminal.pressed_keys, [Key::Escape].into());
+        assert!(terminal.os.unparsed.is_empty());
+    }
+
+    #[test]
+    fn modified_keys() {
+        let ctrl = ModifierKeys {
+            control: true,
+            ..Default::default()
+        };
+        let shift = ModifierKeys {
+            shift: true,
+            ..Default::default()
+        };
+
+        let terminal = parse(b"\x1B[1;5C");
+        assert!(terminal.pressed_keys.contains(&Key::ArrowRight));
+        assert_eq!(terminal.key_modifiers(Key::ArrowRight), ctrl);
+
+        let terminal = parse(b"\x1B[1;2A\x1B[B");
+        assert_eq!(terminal.key_modifiers(Key::ArrowUp), shift);
+        assert!(terminal.key_pressed_with(Key::ArrowUp, shift));
+        assert!(!terminal.key_pressed_with(Key::ArrowUp, ModifierKeys::default()));
+        // Keys without modifiers don't get the modifiers of other keys
+        assert_eq!(
+            terminal.key_modifiers(Key::ArrowDown),
+            ModifierKeys::default()
+        );
+        assert!(terminal.key_pressed_with(Key::ArrowDown, ModifierKeys::default()));
+    }
+
+    #[test]
+    fn kitty_keyboard() {
+        let ctrl = ModifierKeys {
+            control: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_kitty_report(b"9;5"),
+            Some(KittyReport::Key(Key::Text('\t'), ctrl))
+        );
+        assert_eq!(
+            parse_kitty_report(b"105;5"),
+            Some(KittyReport::Key(Key::Text('i'), ctrl))
+        );
+        assert_eq!(
+            parse_kitty_report(b"27"),
+            Some(KittyReport::Key(Key::Escape, ModifierKeys::default()))
+        );
+        assert_eq!(
+            parse_kitty_report(b"97:65;4"),
+            Some(KittyReport::Key(
+                Key::Text('a'),
+                ModifierKeys {
+                    shift: true,
+                    meta: true,
+                    control: false,
+                }
+            ))
+        );
+        assert_eq!(parse_kitty_report(b"?1"), Some(KittyReport::Flags(1)));
+        assert_eq!(parse_kitty_report(b"57399"), None);
+
+        // Tab and Ctrl+I are different keys, unlike in the legacy encoding
+        let mut terminal = parse(b"\x1B[9;5u");
+        assert!(!terminal.kitty_keyboard);
+        assert!(terminal.key_pressed_with(Key::Text('\t'), ctrl));
+        feed(&mut terminal, b"\x1B[105;5u");
+        assert!(terminal.key_pressed_with(Key::Text('i'), ctrl));
+        assert!(!terminal.pressed_keys.contains(&Key::Text('\t')));
+
+        // Reports mixed with legacy sequences and text
+        feed(&mut terminal, b"\x1B[?1ua\x1B[13u\x1B[A");
+        assert!(terminal.kitty_keyboard);
+        assert_eq!(
+            terminal.pressed_keys,
+            [Key::Text('a'), Key::Text('\r'), Key::ArrowUp].into()
+        );
+    }
+
+    #[test]
+    fn focus_events() {
+        let mut terminal = parse(b"\x1B[O");
+        assert!(!terminal.focused);
+
+        terminal.os.input_buffer = b"\x1B[I".to_vec();
+        Os::parse_input(&mut terminal);
+        assert!(terminal.focused);
+
+        // ESC[O followed by H or F is still home/end
+        terminal.os.input_buffer = b"\x1B[OH\x1B[O".to_vec();
+        Os::parse_input(&mut terminal);
+        assert!(terminal.pressed_keys.contains(&Key::Home));
+        assert!(!terminal.focused);
+    }
+
+    /// Held by tests that install the `SIGWINCH` handler, since it and
+    /// [`RESIZED`] are global.
+    static SIGWINCH_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn sigwinch_sets_resized() {
+        let _lock = SIGWINCH_LOCK.lock().unwrap();
+        static PREVIOUS_CALLS: AtomicUsize = AtomicUsize::new(0);
+        extern "C" fn previous_handler(_: libc::c_int) {
+            PREVIOUS_CALLS.fetch_add(1, Ordering::Relaxed);
+        }
+        let original = unsafe {
+            libc::signal(
+                libc::SIGWINCH,
+                previous_handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            )
+        };
+
+        install_sigwinch_handler();
+        let os = Os::headless();
+
+        RESIZED.store(false, Ordering::Relaxed);
+        assert!(!os.take_resized());
+
+        unsafe { libc::raise(libc::SIGWINCH) };
+        assert!(os.take_resized());
+        assert!(!os.take_resized());
+        // The handler that was already installed still runs
+        assert_eq!(PREVIOUS_CALLS.load(Ordering::Relaxed), 1);
+
+        // And it's the only one left after restoring it
+        os.restore();
+        unsafe { libc::raise(libc::SIGWINCH) };
+        assert!(!os.take_resized());
+        assert_eq!(PREVIOUS_CALLS.load(Ordering::Relaxed), 2);
+
+        unsafe { libc::signal(libc::SIGWINCH, original) };
+    }
+
+    #[test]
+    fn size_requeried_on_resize() {
+        let _lock = SIGWINCH_LOCK.lock().unwrap();
+
+        let mut size = libc::winsize {
+            ws_row: 24,
+            ws_col: 80,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let (mut master, mut slave) = (0, 0);
+        let status =
+            unsafe { libc::openpty(&mut master, &mut slave, ptr::null_mut(), ptr::null(), &size) };
+        assert_eq!(status, 0);
+
+        let mut terminal = Terminal::headless();
+        terminal.os.stdin = slave;
+        terminal.attached = true;
+
+        // A previous terminal already consumed the flag; installing the
+        // handler again re-arms it, so the first frame gets the size
+        RESIZED.store(false, Ordering::Relaxed);
+        install_sigwinch_handler();
+        terminal.update_input();
+        assert_eq!(terminal.size, (79, 23));
+
+        // Resizing without a SIGWINCH isn't noticed
+        size.ws_col = 100;
+        assert_eq!(unsafe { libc::ioctl(master, libc::TIOCSWINSZ, &size) }, 0);
+        terminal.update_input();
+        assert_eq!(terminal.size, (79, 23));
+
+        // But it is once the signal arrives
+        unsafe { libc::raise(libc::SIGWINCH) };
+        terminal.update_input();
+        assert_eq!(terminal.size, (99, 23));
+
+        terminal.attached = false;
+        uninstall_sigwinch_handler();
+        unsafe {
+            libc::close(slave);
+            libc::close(master);
+        }
+    }
+
+    #[test]
+    fn disable_raw_mode_without_terminal() {
+        // stdin isn't a terminal once it's disconnected, so resetting it
+        // fails; that shouldn't panic while the terminal is being dropped
+        let mut os = Os::headless();
+        os.stdin = -1;
+        os.set_raw_mode(false);
+    }
+}
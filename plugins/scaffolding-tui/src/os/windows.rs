@@ -57,6 +57,18 @@ impl Default for Os {
     }
 }
 impl Os {
+    /// An [`Os`] that never touches the real console, for tests.
+    #[cfg(test)]
+    pub fn headless() -> Self {
+        Self {
+            stdin_og_mode: ConsoleModes::default(),
+            stdout_og_mode: ConsoleModes::default(),
+            stdin_handle: unsafe { GetStdHandle(StdHandle::Input) },
+            stdout_handle: unsafe { GetStdHandle(StdHandle::Output) },
+            input_buffer: Vec::default(),
+        }
+    }
+
     fn read_input(&mut self) {
         let mut num_events = 0u32;
         let res = unsafe { GetNumberOfConsoleInputEvents(self.stdin_handle, &mut num_events) };
@@ -134,13 +146,9 @@ impl OsTrait for Os {
                 panic!("scaffolding-tui::os::windows::Os::set_raw_mode: SetConsoleMode on stdin call had an error. Error code: {}", unsafe { GetLastError() });
             }
         } else {
-            let res = unsafe { SetConsoleMode(self.stdin_handle, self.stdin_og_mode) };
-            if !res.as_bool() {
-                panic!("scaffolding-tui::os::windows::Os::set_raw_mode: SetConsoleMode call had an error. Error code: {}", unsafe { GetLastError() });
-            }
-            let res = unsafe { SetConsoleMode(self.stdout_handle, self.stdout_og_mode) };
-            if !res.as_bool() {
-                panic!("scaffolding-tui::os::windows::Os::set_raw_mode: SetConsoleMode call had an error. Error code: {}", unsafe { GetLastError() });
+            unsafe {
+                SetConsoleMode(self.stdin_handle, self.stdin_og_mode);
+                SetConsoleMode(self.stdout_handle, self.stdout_og_mode);
             }
         }
     }
@@ -159,6 +167,10 @@ impl OsTrait for Os {
                         mouse_event.mouse_position.y.try_into().unwrap(),
                     );
                 }
+                EventType::Focus => {
+                    let focus_event = unsafe { input.event.focus_event };
+                    terminal.focused = focus_event.set_focus.as_bool();
+                }
                 _ => {}
             }
         }
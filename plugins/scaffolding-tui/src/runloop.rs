@@ -1,45 +1,270 @@
 use {
     crate::{msg::TuiMsg, Terminal},
-    scaffolding::world::{Executable, World},
+    scaffolding::world::{Executable, Time, World},
     std::{
-        thread,
+        mem, thread,
         time::{Duration, Instant},
     },
 };
 
+/// A callback that runs when a [`TuiRunloop`] exits.
+pub type ExitCallback = Box<dyn FnOnce(&mut World)>;
+
 pub struct TuiRunloop {
+    /// How many frames the runloop tries to render each second. 0 is treated
+    /// as 1.
     pub fps: u32,
+    /// If the app only runs when something changed. See
+    /// [`TuiRunloop::redraw_on_demand`].
+    pub on_demand: bool,
+    /// If the next frame has to run the app, when [`TuiRunloop::on_demand`]
+    /// is set.
+    dirty: bool,
+    /// Runs once after the runloop exits, but before the [`World`] (and thus
+    /// the [`Terminal`]) is dropped. See [`TuiRunloop::on_exit`].
+    on_exit: Option<ExitCallback>,
 }
 impl TuiRunloop {
     pub fn new(fps: u32) -> Self {
-        Self { fps }
+        Self {
+            fps,
+            on_demand: false,
+            dirty: true,
+            on_exit: None,
+        }
+    }
+
+    /// Set a callback to run when the runloop exits. It runs after the last
+    /// frame, but before the [`Terminal`] is reset, so it can be used for
+    /// teardown logic like saving the app's state.
+    pub fn on_exit(mut self, on_exit: impl FnOnce(&mut World) + 'static) -> Self {
+        self.on_exit = Some(Box::new(on_exit));
+        self
+    }
+
+    /// Only run the app when the UI may have changed, instead of every frame.
+    /// The app runs on the first frame, after any input or resize, and after
+    /// [`TuiMsg::RequestRedraw`]; other frames only read input and then
+    /// sleep, which saves a lot of CPU time for apps that are mostly idle.
+    ///
+    /// Since the app doesn't run every frame, anything that changes on its
+    /// own, like an animation or a [`Toasts`](crate::toasts::Toasts)
+    /// countdown, has to request a redraw every frame it's shown.
+    pub fn redraw_on_demand(mut self) -> Self {
+        self.on_demand = true;
+        self
     }
 
-    pub fn start<Args, E>(self, mut world: World, mut app_main: E)
+    pub fn start<Args, E>(mut self, mut world: World, mut app_main: E)
     where
         for<'a> &'a mut E: Executable<'a, Args>,
     {
-        let time_between_frames = Duration::from_secs(1) / self.fps;
-        let mut goal = Instant::now() + time_between_frames;
+        if world.try_get_singleton::<Time>().is_none() {
+            world.add_singleton(Time::new());
+        }
+        let mut goal = Instant::now() + self.frame_time();
+
+        while self.frame(&mut world, &mut app_main) {
+            goal = self.apply_requests(world.get_singleton_mut(), goal);
+            thread::sleep(goal.saturating_duration_since(Instant::now()));
+            goal += self.frame_time();
+        }
 
-        loop {
-            (&mut app_main).execute(&world);
+        self.shutdown(&mut world);
+    }
+
+    /// Runs the app (unless it can be skipped; see
+    /// [`TuiRunloop::redraw_on_demand`]), then updates the terminal. Returns
+    /// false if the runloop should exit.
+    fn frame<Args, E>(&mut self, world: &mut World, app_main: &mut E) -> bool
+    where
+        for<'a> &'a mut E: Executable<'a, Args>,
+    {
+        world.get_singleton_mut::<Time>().tick();
+        let draw = !self.on_demand || mem::take(&mut self.dirty);
+        if draw {
+            app_main.execute(world);
+        }
 
-            let terminal: &Terminal = world.get_singleton();
-            if terminal.exit {
-                break;
-            }
+        let terminal: &Terminal = world.get_singleton();
+        if terminal.exit || terminal.disconnected {
+            return false;
+        }
 
-            world.process_msgs();
+        world.process_msgs();
+        if draw {
             world.send_msg_now(TuiMsg::UpdateTerminal);
+        } else {
+            world.get_singleton_mut::<Terminal>().update_input();
+        }
+        self.dirty |= world.get_singleton::<Terminal>().input_changed;
+
+        true
+    }
+
+    /// How long each frame should take, based on the runloop's FPS.
+    pub fn frame_time(&self) -> Duration {
+        Duration::from_secs(1) / self.fps.max(1)
+    }
 
-            thread::sleep(goal - Instant::now());
-            goal += time_between_frames;
+    /// Applies changes to the runloop requested via [`TuiMsg`]s, and returns
+    /// when the current frame should end.
+    fn apply_requests(&mut self, terminal: &mut Terminal, frame_end: Instant) -> Instant {
+        if let Some(fps) = terminal.target_fps.take() {
+            self.fps = fps.max(1);
+        }
+
+        if !mem::take(&mut terminal.redraw_requested) {
+            frame_end
+        } else if self.on_demand {
+            self.dirty = true;
+            frame_end
+        } else {
+            Instant::now()
+        }
+    }
+
+    /// Runs the exit callback, if there is one. The callback is taken, so it
+    /// only ever runs once.
+    fn shutdown(&mut self, world: &mut World) {
+        world.process_msgs();
+
+        if let Some(on_exit) = self.on_exit.take() {
+            on_exit(world);
         }
     }
 }
 impl Default for TuiRunloop {
     fn default() -> Self {
-        Self { fps: 60 }
+        Self::new(60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::TuiRunloop,
+        crate::{
+            input::Key,
+            msg::{tui_msg_handler, TuiMsg},
+            shapes::RawString,
+            terminal::Terminal,
+        },
+        scaffolding::world::{Singleton, Time, World},
+        std::{
+            cell::Cell,
+            rc::Rc,
+            time::{Duration, Instant},
+        },
+    };
+
+    #[test]
+    fn runloop_requests() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        world.add_msg_handler(tui_msg_handler);
+        let mut runloop = TuiRunloop::new(60);
+        let frame_end = Instant::now() + Duration::from_secs(60);
+
+        world.send_msg_now(TuiMsg::SetTargetFps(30));
+        let new_frame_end = runloop.apply_requests(world.get_singleton_mut(), frame_end);
+        assert_eq!(runloop.fps, 30);
+        assert_eq!(runloop.frame_time(), Duration::from_secs(1) / 30);
+        assert_eq!(new_frame_end, frame_end);
+
+        world.send_msg_now(TuiMsg::RequestRedraw);
+        let new_frame_end = runloop.apply_requests(world.get_singleton_mut(), frame_end);
+        assert!(new_frame_end <= Instant::now());
+
+        // Requests only apply once
+        let new_frame_end = runloop.apply_requests(world.get_singleton_mut(), frame_end);
+        assert_eq!(new_frame_end, frame_end);
+
+        // 0 FPS would mean dividing by 0 to get the frame time
+        world.send_msg_now(TuiMsg::SetTargetFps(0));
+        runloop.apply_requests(world.get_singleton_mut(), frame_end);
+        assert_eq!(runloop.fps, 1);
+        assert_eq!(runloop.frame_time(), Duration::from_secs(1));
+        runloop.fps = 0;
+        assert_eq!(runloop.frame_time(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn redraw_on_demand() {
+        let mut world = World::new();
+        world
+            .add_singleton(Terminal::headless())
+            .add_singleton(Time::new())
+            .add_msg_handler(tui_msg_handler);
+        let mut runloop = TuiRunloop::new(60).redraw_on_demand();
+        let frame_end = Instant::now() + Duration::from_secs(60);
+
+        let runs = Rc::new(Cell::new(0));
+        let app_runs = runs.clone();
+        let mut app = move |terminal: &Singleton<Terminal>| {
+            app_runs.set(app_runs.get() + 1);
+            terminal.draw(RawString {
+                x: 0,
+                y: 0,
+                text: "hi",
+            });
+        };
+
+        // The first frame is always drawn
+        assert!(runloop.frame(&mut world, &mut app));
+        assert_eq!(runs.get(), 1);
+        assert_eq!(
+            world.get_singleton::<Terminal>().last_frame.as_slice(),
+            b"\x1B[1;1Hhi"
+        );
+
+        // Nothing changed, so the app doesn't run, and the last frame is kept
+        assert!(runloop.frame(&mut world, &mut app));
+        assert_eq!(runs.get(), 1);
+        assert_eq!(
+            world.get_singleton::<Terminal>().last_frame.as_slice(),
+            b"\x1B[1;1Hhi"
+        );
+
+        // Requesting a redraw runs the app next frame, but doesn't end the
+        // current frame early
+        world.send_msg_now(TuiMsg::RequestRedraw);
+        let new_frame_end = runloop.apply_requests(world.get_singleton_mut(), frame_end);
+        assert_eq!(new_frame_end, frame_end);
+        assert!(runloop.frame(&mut world, &mut app));
+        assert_eq!(runs.get(), 2);
+        assert!(runloop.frame(&mut world, &mut app));
+        assert_eq!(runs.get(), 2);
+
+        // Input also makes the next frame run the app
+        world
+            .get_singleton_mut::<Terminal>()
+            .pressed_keys
+            .insert(Key::Text('a'));
+        assert!(runloop.frame(&mut world, &mut app));
+        assert_eq!(runs.get(), 2);
+        world.get_singleton_mut::<Terminal>().pressed_keys.clear();
+        assert!(runloop.frame(&mut world, &mut app));
+        assert_eq!(runs.get(), 3);
+        assert!(runloop.frame(&mut world, &mut app));
+        assert_eq!(runs.get(), 3);
+    }
+
+    #[test]
+    fn on_exit_runs_once() {
+        let runs = Rc::new(Cell::new(0));
+        let runs2 = runs.clone();
+
+        let mut world = World::new();
+        world.add_singleton(42_u32);
+
+        let mut runloop = TuiRunloop::default().on_exit(move |world| {
+            assert_eq!(*world.get_singleton::<u32>(), 42);
+            runs2.set(runs2.get() + 1);
+        });
+
+        runloop.shutdown(&mut world);
+        runloop.shutdown(&mut world);
+        assert_eq!(runs.get(), 1);
     }
 }
@@ -1,4 +1,4 @@
-use crate::prelude::Terminal;
+use crate::{ansi, prelude::Terminal, Colour};
 
 pub trait Shape {
     type Output;
@@ -37,6 +37,10 @@ impl Shape for Rect {
     type Output = ();
 
     fn draw(self, terminal: &Terminal) -> Self::Output {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
         let row = " ".repeat(self.width as usize);
         for current_row in 0..self.height {
             terminal.render_string(&row, (self.x, self.y + current_row))
@@ -44,6 +48,157 @@ impl Shape for Rect {
     }
 }
 
+/// A small RGB image. Each cell in the terminal shows two pixels, stacked
+/// vertically, using the `▀` character: its foreground colour is the top pixel
+/// and its background colour is the bottom pixel.
+pub struct Image<'a> {
+    /// The x-coordinate for the top left of the image.
+    pub x: u16,
+    /// The y-coordinate for the top left of the image.
+    pub y: u16,
+    /// How wide the image is, in pixels. Each pixel is one cell wide.
+    pub width: u16,
+    /// How tall the image is, in pixels. Each cell is two pixels tall, so the
+    /// image takes up half as many rows (rounded up).
+    pub height: u16,
+    /// The image's pixels, row by row. This should have `width * height`
+    /// pixels; if it has fewer, only the rows it has every pixel for are
+    /// drawn.
+    pub pixels: &'a [Colour],
+}
+impl Shape for Image<'_> {
+    type Output = ();
+
+    fn draw(self, terminal: &Terminal) -> Self::Output {
+        let width = self.width as usize;
+        if width == 0 {
+            return;
+        }
+        let height = (self.height as usize).min(self.pixels.len() / width);
+
+        for row in 0..height.div_ceil(2) {
+            terminal.render_string("", (self.x, self.y + row as u16));
+
+            let top = &self.pixels[row * 2 * width..][..width];
+            // If the image has an odd height, the last row has no bottom
+            // pixels, so it just uses the default background colour
+            let bottom =
+                (row * 2 + 1 < height).then(|| &self.pixels[(row * 2 + 1) * width..][..width]);
+
+            for col in 0..width {
+                terminal.set_fg(Some(top[col]));
+                terminal.set_bg(bottom.map(|bottom| bottom[col]));
+                terminal.render_string_unpositioned("▀");
+            }
+        }
+
+        terminal.set_fg(None);
+        terminal.set_bg(None);
+    }
+}
+
+/// The glyphs used by [`Sparkline`], from lowest to highest.
+const SPARKLINE_GLYPHS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+/// Partial blocks used by [`BarChart`], from 1/8th of a cell to 7/8ths.
+const PARTIAL_BAR_GLYPHS: [&str; 7] = ["▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+/// A one-row line chart, drawn with block characters (`▁▂▃▄▅▆▇█`). Values
+/// are scaled between the smallest and largest value in `data`; if they're
+/// all the same, the line is drawn halfway up.
+///
+/// Each value gets one cell. If there are more values than cells, neighbouring
+/// values are averaged together to fit.
+pub struct Sparkline<'a> {
+    pub x: u16,
+    pub y: u16,
+    /// The most cells the sparkline can take up.
+    pub width: u16,
+    pub data: &'a [f64],
+}
+impl Sparkline<'_> {
+    /// The value drawn in each cell.
+    fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        let len = self.data.len();
+        let cells = len.min(self.width as usize);
+
+        (0..cells).map(move |cell| {
+            let bucket = &self.data[cell * len / cells..(cell + 1) * len / cells];
+            bucket.iter().sum::<f64>() / bucket.len() as f64
+        })
+    }
+}
+impl Shape for Sparkline<'_> {
+    type Output = ();
+
+    fn draw(self, terminal: &Terminal) -> Self::Output {
+        if self.data.is_empty() || self.width == 0 {
+            return;
+        }
+
+        let (min, max) = self
+            .values()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), val| {
+                (min.min(val), max.max(val))
+            });
+        let top = (SPARKLINE_GLYPHS.len() - 1) as f64;
+
+        terminal.render_string("", (self.x, self.y));
+        for val in self.values() {
+            let level = if max > min {
+                ((val - min) / (max - min) * top).round()
+            } else {
+                // Flat line in the middle
+                (top / 2.).floor()
+            };
+            terminal.render_string_unpositioned(SPARKLINE_GLYPHS[level as usize]);
+        }
+    }
+}
+
+/// A horizontal bar chart. Each item in `data` gets a row, with its label on
+/// the left and a bar on the right. Bars are scaled so the largest value fills
+/// the rest of the chart's width; negative values don't get a bar.
+pub struct BarChart<'a> {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    /// The most rows the chart can take up. Items that don't fit aren't
+    /// drawn.
+    pub height: u16,
+    pub data: &'a [(&'a str, f64)],
+}
+impl Shape for BarChart<'_> {
+    type Output = ();
+
+    fn draw(self, terminal: &Terminal) -> Self::Output {
+        let data = &self.data[..self.data.len().min(self.height as usize)];
+        let label_width = data
+            .iter()
+            .map(|(label, _)| ansi::visible_width(label))
+            .max()
+            .unwrap_or(0);
+        // The labels are followed by a space
+        let bar_width = (self.width as usize).saturating_sub(label_width + 1);
+        let max = data.iter().map(|(_, val)| *val).fold(0., f64::max);
+
+        for (row, (label, val)) in data.iter().enumerate() {
+            terminal.render_string(label, (self.x, self.y + row as u16));
+            if max <= 0. || bar_width == 0 {
+                continue;
+            }
+
+            // The bar's length, in eighths of a cell
+            let eighths = (val.max(0.) / max * (bar_width * 8) as f64).round() as usize;
+            let bar_x = self.x + (label_width + 1) as u16;
+            terminal.render_string(&"█".repeat(eighths / 8), (bar_x, self.y + row as u16));
+            let partial = eighths % 8;
+            if partial > 0 {
+                terminal.render_string_unpositioned(PARTIAL_BAR_GLYPHS[partial - 1]);
+            }
+        }
+    }
+}
+
 /// A border that can go around another UI element. The characters that are
 /// used in the border are determined by the [`BorderStyle`] used.
 pub struct Border {
@@ -57,12 +212,30 @@ impl Shape for Border {
     type Output = ();
 
     fn draw(self, terminal: &Terminal) -> Self::Output {
+        // Borders too small to have corners on both sides are just a line
+        if self.width == 0 || self.height == 0 {
+            return;
+        } else if self.height == 1 {
+            let line: String = (0..self.width).map(|_| self.style.top).collect();
+            terminal.render_string(&line, (self.x, self.y));
+            return;
+        } else if self.width == 1 {
+            for row in 0..self.height {
+                terminal.render_box_char(self.style.left, (self.x, self.y + row));
+            }
+            return;
+        } else if terminal.join_borders {
+            return self.draw_joined(terminal);
+        }
+
         let btm: String = (0..self.width - 2).map(|_| self.style.bottom).collect();
         let top: String = (0..self.width - 2).map(|_| self.style.top).collect();
 
         // top & top corners
         terminal.render_char(self.style.top_left, (self.x, self.y));
-        terminal.render_string(&top, (self.x + 1, self.y));
+        if !top.is_empty() {
+            terminal.render_string(&top, (self.x + 1, self.y));
+        }
         terminal.render_char(self.style.top_right, (self.x + self.width - 1, self.y));
 
         // bottom & bottom corners
@@ -70,7 +243,9 @@ impl Shape for Border {
             self.style.bottom_right,
             (self.x + self.width - 1, self.y + self.height - 1),
         );
-        terminal.render_string(&btm, (self.x + 1, self.y + self.height - 1));
+        if !btm.is_empty() {
+            terminal.render_string(&btm, (self.x + 1, self.y + self.height - 1));
+        }
         terminal.render_char(self.style.bottom_left, (self.x, self.y + self.height - 1));
 
         // sides
@@ -81,6 +256,29 @@ impl Shape for Border {
     }
 }
 
+impl Border {
+    /// Draws the border one cell at a time, so each cell can be joined with
+    /// what's already there. See [`Terminal::join_borders`].
+    fn draw_joined(self, terminal: &Terminal) {
+        let right = self.x + self.width - 1;
+        let bottom = self.y + self.height - 1;
+
+        terminal.render_box_char(self.style.top_left, (self.x, self.y));
+        terminal.render_box_char(self.style.top_right, (right, self.y));
+        terminal.render_box_char(self.style.bottom_right, (right, bottom));
+        terminal.render_box_char(self.style.bottom_left, (self.x, bottom));
+
+        for x in self.x + 1..right {
+            terminal.render_box_char(self.style.top, (x, self.y));
+            terminal.render_box_char(self.style.bottom, (x, bottom));
+        }
+        for y in self.y + 1..bottom {
+            terminal.render_box_char(self.style.left, (self.x, y));
+            terminal.render_box_char(self.style.right, (right, y));
+        }
+    }
+}
+
 /// The characters used to make a [`Border`]. There are several included
 /// styles in this type's associated constants; it may be easier to use those
 /// than to make your own.
@@ -162,3 +360,226 @@ impl BorderStyle {
         left: '║',
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::str};
+
+    /// Draw `shape` and return what was drawn, without escape codes.
+    fn draw_text(shape: impl Shape) -> String {
+        let terminal = Terminal::headless();
+        terminal.draw(shape);
+        let output = str::from_utf8(&terminal.output_buffer).unwrap();
+        ansi::segments(output)
+            .filter_map(|segment| match segment {
+                ansi::AnsiSegment::Text(text) => Some(text),
+                ansi::AnsiSegment::Escape(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sparkline_glyphs() {
+        let sparkline = |data: &[f64], width| {
+            draw_text(Sparkline {
+                x: 0,
+                y: 0,
+                width,
+                data,
+            })
+        };
+
+        assert_eq!(sparkline(&[0., 1., 2., 3., 4., 5., 6., 7.], 8), "▁▂▃▄▅▆▇█");
+        // Values are scaled between the min and max, even when negative
+        assert_eq!(sparkline(&[-10., 0., 10.], 8), "▁▅█");
+        // Constant data is a flat line in the middle
+        assert_eq!(sparkline(&[5., 5., 5.], 8), "▄▄▄");
+        assert_eq!(sparkline(&[], 8), "");
+        // Too much data gets averaged: (0, 0), (3, 4), (10, 4)
+        assert_eq!(sparkline(&[0., 0., 3., 4., 10., 4.], 3), "▁▅█");
+    }
+
+    #[test]
+    fn bar_chart() {
+        let terminal = Terminal::headless();
+        terminal.draw(BarChart {
+            x: 1,
+            y: 0,
+            width: 7,
+            height: 3,
+            data: &[("a", 4.), ("bb", 1.), ("c", 0.5), ("hidden", 1.)],
+        });
+        let output = str::from_utf8(&terminal.output_buffer).unwrap();
+
+        // The labels are 2 wide, plus a space, so bars get 4 cells
+        assert!(output.starts_with("\x1B[1;2Ha\x1B[1;5H████"));
+        assert!(output.contains("\x1B[2;2Hbb\x1B[2;5H█"));
+        assert!(output.contains("\x1B[3;2Hc\x1B[3;5H▌"));
+        assert!(!output.contains("hidden"));
+    }
+
+    #[test]
+    fn empty_rect() {
+        let terminal = Terminal::headless();
+        terminal.draw(Rect {
+            x: 2,
+            y: 2,
+            width: 0,
+            height: 10,
+        });
+        terminal.draw(Rect {
+            x: 2,
+            y: 2,
+            width: 10,
+            height: 0,
+        });
+
+        assert!(terminal.output_buffer.is_empty());
+    }
+
+    #[test]
+    fn rect() {
+        let terminal = Terminal::headless();
+        terminal.draw(Rect {
+            x: 1,
+            y: 0,
+            width: 3,
+            height: 2,
+        });
+
+        let output = str::from_utf8(&terminal.output_buffer).unwrap();
+        assert_eq!(output, "\x1B[1;2H   \x1B[2;2H   ");
+        assert_eq!(output.matches(' ').count(), 6);
+    }
+
+    fn draw_border(width: u16, height: u16) -> String {
+        let terminal = Terminal::headless();
+        terminal.draw(Border {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            style: BorderStyle::ASCII,
+        });
+
+        String::from_utf8(terminal.output_buffer.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn tiny_borders() {
+        assert_eq!(draw_border(0, 3), "");
+        assert_eq!(draw_border(3, 0), "");
+        assert_eq!(draw_border(1, 2), "\x1B[1;1H|\x1B[2;1H|");
+        assert_eq!(draw_border(3, 1), "\x1B[1;1H---");
+        assert_eq!(
+            draw_border(2, 2),
+            "\x1B[1;1H*\x1B[1;2H*\x1B[2;2H*\x1B[2;1H*"
+        );
+    }
+
+    #[test]
+    fn border() {
+        assert_eq!(
+            draw_border(3, 3),
+            concat!(
+                "\x1B[1;1H*\x1B[1;2H-\x1B[1;3H*",
+                "\x1B[3;3H*\x1B[3;2H-\x1B[3;1H*",
+                "\x1B[2;1H|\x1B[2;3H|"
+            )
+        );
+    }
+
+    #[test]
+    fn joined_borders() {
+        let mut terminal = Terminal::headless();
+        terminal.join_borders = true;
+        for x in [0, 2] {
+            terminal.draw(Border {
+                x,
+                y: 0,
+                width: 3,
+                height: 3,
+                style: BorderStyle::NORMAL,
+            });
+        }
+
+        let output = str::from_utf8(&terminal.output_buffer).unwrap();
+        assert!(output.ends_with(
+            "\x1B[1;3H┬\x1B[1;5H┐\x1B[3;5H┘\x1B[3;3H┴\x1B[1;4H─\x1B[3;4H─\x1B[2;3H│\x1B[2;5H│"
+        ));
+
+        // Joining only lasts for one frame
+        terminal.update();
+        terminal.draw(Border {
+            x: 0,
+            y: 0,
+            width: 3,
+            height: 3,
+            style: BorderStyle::NORMAL,
+        });
+        let output = str::from_utf8(&terminal.output_buffer).unwrap();
+        assert!(output.contains("\x1B[1;3H┐"));
+    }
+
+    #[test]
+    fn image() {
+        let terminal = Terminal::headless();
+        terminal.draw(Image {
+            x: 0,
+            y: 0,
+            width: 2,
+            height: 2,
+            pixels: &[Colour::RED, Colour::GREEN, Colour::BLUE, Colour::WHITE],
+        });
+
+        assert_eq!(
+            str::from_utf8(&terminal.output_buffer).unwrap(),
+            concat!(
+                "\x1B[1;1H",
+                "\x1B[38;2;255;0;0m\x1B[48;2;0;0;255m▀",
+                "\x1B[38;2;0;255;0m\x1B[48;2;255;255;255m▀",
+                "\x1B[39m\x1B[49m",
+            )
+        );
+    }
+
+    #[test]
+    fn image_odd_height() {
+        let terminal = Terminal::headless();
+        terminal.draw(Image {
+            x: 1,
+            y: 2,
+            width: 1,
+            height: 1,
+            pixels: &[Colour::RED],
+        });
+
+        assert_eq!(
+            str::from_utf8(&terminal.output_buffer).unwrap(),
+            "\x1B[3;2H\x1B[38;2;255;0;0m\x1B[49m▀\x1B[39m\x1B[49m",
+        );
+    }
+
+    #[test]
+    fn image_missing_pixels() {
+        let terminal = Terminal::headless();
+        terminal.draw(Image {
+            x: 0,
+            y: 0,
+            width: 2,
+            height: 4,
+            pixels: &[Colour::RED, Colour::GREEN, Colour::BLUE],
+        });
+
+        // Only the first row has all its pixels
+        assert_eq!(
+            str::from_utf8(&terminal.output_buffer).unwrap(),
+            concat!(
+                "\x1B[1;1H",
+                "\x1B[38;2;255;0;0m\x1B[49m▀",
+                "\x1B[38;2;0;255;0m\x1B[49m▀",
+                "\x1B[39m\x1B[49m",
+            )
+        );
+    }
+}
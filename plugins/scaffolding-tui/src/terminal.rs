@@ -1,19 +1,31 @@
 use {
     crate::{
+        ansi::{self, AnsiSegment, Sgr},
+        box_drawing,
+        capabilities::{self, ColourMode},
+        clipboard,
         input::*,
         os::{Os, OsTrait as _},
         shapes::Shape,
+        widgets::Frame,
         Colour,
     },
     scaffolding::{datatypes::ArenaVec, utils::MemoryAmount},
     std::{
-        cell::Cell,
-        collections::HashSet,
-        fmt::Write as _,
-        io::{stdout, Write as _},
+        cell::{Cell, RefCell},
+        collections::{BTreeMap, HashMap, HashSet},
+        fmt::{self, Write as _},
+        io::{stdout, Write},
+        mem,
+        panic::{self, PanicHookInfo},
         str,
-        sync::atomic::{AtomicBool, Ordering},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+        time::Instant,
     },
+    unicode_segmentation::UnicodeSegmentation,
 };
 
 /// Tracks if a [`Terminal`] was already dropped. When dropped, the [`Terminal`]
@@ -23,6 +35,64 @@ use {
 /// this boolean to make sure the drop code is only run once.
 static TERMINAL_DROPPED: AtomicBool = AtomicBool::new(false);
 
+/// A panic hook, as returned by [`std::panic::take_hook`].
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+/// If the panic hook that resets the terminal is installed. Every
+/// [`Terminal`] tries to install it, so this makes sure it's only installed
+/// once, instead of being chained once per terminal.
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+/// The panic hook that was set before ours. Our hook calls it after resetting
+/// the terminal, and [`Terminal::uninstall_panic_hook`] restores it.
+static PREVIOUS_PANIC_HOOK: Mutex<Option<PanicHook>> = Mutex::new(None);
+
+/// Commands sent to the terminal emulator by [`Terminal::on_drop`]. These
+/// disable all of the things we enabled in [`Terminal::default`].
+const FINAL_COMMANDS: &str = concat!(
+    // show the cursor
+    "\x1B[?25h",
+    // reset the cursor style
+    "\x1B[0 q",
+    // leave the alternate buffer
+    "\x1B[?1049l",
+    // disable mouse location reporting
+    "\x1B[?1003l",
+    // disable SGR extended mouse location reporting
+    "\x1B[?1006l",
+    // disable focus reporting
+    "\x1B[?1004l",
+    // leave the Kitty keyboard protocol, if it was enabled; this does nothing
+    // if it wasn't
+    "\x1B[<u",
+);
+
+/// The shape of the terminal's cursor. See [`Terminal::set_cursor_style`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum CursorStyle {
+    /// Whatever the user's terminal emulator uses by default.
+    #[default]
+    Default,
+    BlinkingBlock,
+    SteadyBlock,
+    BlinkingUnderline,
+    SteadyUnderline,
+    BlinkingBar,
+    SteadyBar,
+}
+impl CursorStyle {
+    /// The escape sequence (DECSCUSR) that sets this cursor style.
+    pub fn escape(self) -> &'static str {
+        match self {
+            Self::Default => "\x1B[0 q",
+            Self::BlinkingBlock => "\x1B[1 q",
+            Self::SteadyBlock => "\x1B[2 q",
+            Self::BlinkingUnderline => "\x1B[3 q",
+            Self::SteadyUnderline => "\x1B[4 q",
+            Self::BlinkingBar => "\x1B[5 q",
+            Self::SteadyBar => "\x1B[6 q",
+        }
+    }
+}
+
 /// Handles communicating with the terminal using ANSI escape sequences to
 /// query input and render the TUI.
 pub struct Terminal {
@@ -30,21 +100,24 @@ pub struct Terminal {
     pub size: (u16, u16),
     /// The current location of the mouse.
     pub mouse_pos: (u16, u16),
-    /// Mouse buttons that have just been clicked.
+    /// Mouse buttons that have just been clicked. See
+    /// [`Terminal::mouse_clicked`].
     ///
     /// Mouse buttons are stored as a u8, but only buttons 0-11 are actually
     /// supported (other buttons aren't always communicated by the terminal).
     /// Mouse buttons are indexed starting at 0 (IE, mouse button 0 is left
     /// click).
     pub clicked_mouse_buttons: HashSet<u8>,
-    /// Mouse buttons that are currently being held.
+    /// Mouse buttons that are currently being held. See
+    /// [`Terminal::mouse_held`].
     ///
     /// Mouse buttons are stored as a u8, but only buttons 0-11 are actually
     /// supported (other buttons aren't always communicated by the terminal).
     /// Mouse buttons are indexed starting at 0 (IE, mouse button 0 is left
     /// click).
     pub held_mouse_buttons: HashSet<u8>,
-    /// Mouse buttons that have just been released.
+    /// Mouse buttons that have just been released. See
+    /// [`Terminal::mouse_released`].
     ///
     /// Mouse buttons are stored as a u8, but only buttons 0-11 are actually
     /// supported (other buttons aren't always communicated by the terminal).
@@ -57,40 +130,167 @@ pub struct Terminal {
     pub modifier_keys: ModifierKeys,
     /// Keys currently held by the user.
     pub pressed_keys: HashSet<Key>,
+    /// The modifier keys that were held with each key in
+    /// [`Terminal::pressed_keys`], for terminals that report them. Keys that
+    /// aren't in here were pressed without modifiers. See
+    /// [`Terminal::key_modifiers`].
+    pub key_modifiers: HashMap<Key, ModifierKeys>,
+    /// The keys in [`Terminal::pressed_keys`] that made it through
+    /// [`Terminal::key_repeat`]. Use this instead of `pressed_keys` when held
+    /// keys shouldn't repeat as fast as the terminal sends them.
+    pub debounced_keys: HashSet<Key>,
+    /// The filter used to compute [`Terminal::debounced_keys`].
+    pub key_repeat: KeyRepeatFilter,
     /// If we should exit the app.
     pub exit: bool,
+    /// If the terminal's input was closed, such as when the terminal emulator
+    /// is closed. No more input will arrive, so [`TuiRunloop`] exits when
+    /// this is set.
+    ///
+    /// [`TuiRunloop`]: crate::runloop::TuiRunloop
+    pub disconnected: bool,
+    /// If the terminal emulator acknowledged the Kitty keyboard protocol
+    /// after [`Terminal::enable_kitty_keyboard`]. Until it does (or if it
+    /// doesn't support the protocol), keys are sent with the legacy encoding,
+    /// where keys like Tab and Ctrl+I can't be told apart.
+    pub kitty_keyboard: bool,
+    /// If the terminal emulator is focused. Terminals that don't report focus
+    /// changes are always considered focused.
+    pub focused: bool,
+    /// If the runloop should start the next frame immediately. See
+    /// [`TuiMsg::RequestRedraw`].
+    ///
+    /// [`TuiMsg::RequestRedraw`]: crate::msg::TuiMsg::RequestRedraw
+    pub(crate) redraw_requested: bool,
+    /// If the last [`Terminal::update_input`] got any input, or the terminal
+    /// was resized, so the UI may need to change.
+    pub(crate) input_changed: bool,
+    /// If the next [`Terminal::update`] should redraw the screen, even if the
+    /// frame is identical to the last one. See [`TuiMsg::ForceFullRedraw`].
+    ///
+    /// [`TuiMsg::ForceFullRedraw`]: crate::msg::TuiMsg::ForceFullRedraw
+    pub(crate) force_full_redraw: bool,
+    /// A new FPS for the runloop, if one was requested. See
+    /// [`TuiMsg::SetTargetFps`].
+    ///
+    /// [`TuiMsg::SetTargetFps`]: crate::msg::TuiMsg::SetTargetFps
+    pub(crate) target_fps: Option<u32>,
+    /// How colours are sent to the terminal. This is detected from the
+    /// environment when the terminal is created (see
+    /// [`capabilities::detect_colour_mode`]), and can be changed afterwards.
+    pub colour_mode: ColourMode,
+    /// If box-drawing characters drawn with [`Terminal::render_box_char`]
+    /// (such as [`Border`]s) should join with ones already drawn in the same
+    /// cell this frame. For example, two [`Border`]s that share an edge will
+    /// meet with `┬` and `┴` instead of overlapping corners.
+    ///
+    /// [`Border`]: crate::shapes::Border
+    pub join_borders: bool,
+    /// The box-drawing characters drawn this frame, when
+    /// [`Terminal::join_borders`] is enabled.
+    box_glyphs: RefCell<HashMap<(u16, u16), char>>,
+    /// The background colour set by the last call to [`Terminal::set_bg`].
+    current_bg: Cell<Option<Colour>>,
+    /// The background colour of every cell drawn with a background colour
+    /// this frame. This is what [`Terminal::set_bg_blended`] blends with.
+    /// Cells on higher layers hide the ones below them, so this also stores
+    /// which layer each colour is on.
+    bg_colours: RefCell<HashMap<(u16, u16), (u8, Colour)>>,
     /// The location to move the cursor to, if one was set.
     pub target_cursor_location: Cell<Option<(u16, u16)>>,
+    /// The cursor style for this frame. See [`Terminal::set_cursor_style`].
+    target_cursor_style: Cell<CursorStyle>,
+    /// The cursor style the terminal emulator is currently using.
+    cursor_style: CursorStyle,
     /// The buffer for writing to stdout.
     pub(crate) output_buffer: ArenaVec<u8>,
+    /// The layer that's currently being drawn to. See
+    /// [`Terminal::draw_on_layer`].
+    layer: Cell<u8>,
+    /// Everything drawn on layers above 0 this frame. These are drawn on top
+    /// of [`Terminal::output_buffer`], in order, when the frame ends.
+    layers: RefCell<BTreeMap<u8, ArenaVec<u8>>>,
+    /// Everything that was written to stdout in the last frame. If a frame is
+    /// identical to the last one, we don't redraw it.
+    pub(crate) last_frame: ArenaVec<u8>,
+    /// Where frames are written. This is stdout unless the terminal was made
+    /// with [`Terminal::with_output`].
+    output: RefCell<Box<dyn Write>>,
+    /// How much of [`Terminal::output_buffer`] was already written by
+    /// [`Terminal::flush`] this frame.
+    flushed: Cell<usize>,
     /// OS APIs.
     pub(crate) os: Os,
+    /// If this terminal is attached to a real terminal emulator. Terminals
+    /// that aren't attached are only used for tests; they never read user
+    /// input or change the terminal's settings, and write their frames to
+    /// [`std::io::sink`].
+    pub(crate) attached: bool,
 }
 impl Terminal {
     pub fn set_fg(&self, fg: Option<Colour>) {
-        let mut buffer = &self.output_buffer;
-
-        if let Some(fg) = fg {
-            // Custom RGB colour
-            // TODO: Support older colour formats for terminals that don't
-            // support RGB
-            write!(buffer, "\x1B[38;2;{};{};{}m", fg.r, fg.g, fg.b).unwrap();
-        } else {
-            // Default fg colour
-            buffer.extend_from_slice(b"\x1B[39m");
-        }
+        self.set_colour(fg, 38, 30);
     }
     pub fn set_bg(&self, bg: Option<Colour>) {
-        let mut buffer = &self.output_buffer;
-
-        if let Some(bg) = bg {
-            // Custom RGB colour
-            // TODO: Support older colour formats for terminals that don't
-            // support RGB
-            write!(buffer, "\x1B[48;2;{};{};{}m", bg.r, bg.g, bg.b).unwrap();
-        } else {
-            // Default bg colour
-            buffer.extend_from_slice(b"\x1B[49m");
+        self.current_bg.set(bg);
+        self.set_colour(bg, 48, 40);
+    }
+    /// Set the background colour to `bg` drawn with the given opacity over
+    /// whatever was already drawn at `position` this frame (see
+    /// [`Colour::over_cell`]). Terminals don't tell us their default
+    /// background colour, so if nothing has a background at `position`, `bg`
+    /// is used as-is.
+    pub fn set_bg_blended(&self, bg: Colour, alpha: f32, position: (u16, u16)) {
+        let colour = match self.bg_at(position) {
+            Some(existing) => bg.over_cell(existing, alpha),
+            None => bg,
+        };
+        self.set_bg(Some(colour));
+    }
+    /// The background colour drawn at `position` this frame, if it has one.
+    pub fn bg_at(&self, position: (u16, u16)) -> Option<Colour> {
+        self.bg_colours
+            .borrow()
+            .get(&position)
+            .map(|(_, colour)| *colour)
+    }
+    /// Sets the fg or bg colour, in the format for [`Terminal::colour_mode`].
+    /// `extended` is the SGR code for RGB/256 colours (38 for fg, 48 for
+    /// bg), and `basic` is the code for the first of the 8 basic colours (30
+    /// for fg, 40 for bg).
+    fn set_colour(&self, colour: Option<Colour>, extended: u8, basic: u8) {
+        self.with_buffer(|mut buffer| match (colour, self.colour_mode) {
+            (_, ColourMode::NoColour) => {}
+            // Default colour
+            (None, _) => write!(buffer, "\x1B[{}m", basic + 9).unwrap(),
+            (Some(colour), ColourMode::TrueColour) => write!(
+                buffer,
+                "\x1B[{extended};2;{};{};{}m",
+                colour.r, colour.g, colour.b
+            )
+            .unwrap(),
+            (Some(colour), ColourMode::Ansi256) => {
+                write!(buffer, "\x1B[{extended};5;{}m", colour.to_ansi256()).unwrap()
+            }
+            (Some(colour), ColourMode::Ansi16) => {
+                let colour = colour.to_ansi16();
+                // Bright colours are 60 after the normal ones
+                let code = if colour < 8 {
+                    basic + colour
+                } else {
+                    basic + 60 + colour - 8
+                };
+                write!(buffer, "\x1B[{code}m").unwrap()
+            }
+        })
+    }
+    /// Run `f` with the buffer for the layer that's currently being drawn to.
+    fn with_buffer<R>(&self, f: impl FnOnce(&ArenaVec<u8>) -> R) -> R {
+        match self.layer.get() {
+            0 => f(&self.output_buffer),
+            layer => f(self.layers.borrow_mut().entry(layer).or_insert_with(|| {
+                ArenaVec::with_reserved_memory(MemoryAmount::Megabytes(1).into_bytes())
+            })),
         }
     }
 
@@ -98,55 +298,365 @@ impl Terminal {
     pub fn draw<E: Shape>(&self, element: E) -> E::Output {
         element.draw(self)
     }
+    /// Draw `element` on the given layer. Layers are drawn in order when the
+    /// frame ends, so anything on a higher layer is drawn on top of lower
+    /// layers, no matter when it was drawn this frame. Everything drawn with
+    /// [`Terminal::draw`] is on layer 0; overlays like popups and dropdowns
+    /// should use a higher layer.
+    ///
+    /// The background colour set with [`Terminal::set_bg`] carries over to
+    /// the layer, and colours set while drawing `element` only apply to the
+    /// layer. Layers above 0 aren't written by [`Terminal::flush`]; they only
+    /// get drawn when the frame ends.
+    pub fn draw_on_layer<E: Shape>(&self, layer: u8, element: E) -> E::Output {
+        let previous = self.layer.replace(layer);
+        let bg = self.current_bg.get();
+        if layer != previous {
+            self.set_bg(bg);
+        }
+
+        let output = element.draw(self);
+
+        self.layer.set(previous);
+        self.current_bg.set(bg);
+        output
+    }
+
+    /// Blank the whole screen, erasing everything drawn before this point in
+    /// the frame, and move the cursor to the top-left corner. Unlike
+    /// [`Terminal::update`], this doesn't end the frame or read any input.
+    pub fn clear(&self) {
+        self.write_raw(b"\x1B[2J\x1B[H");
+        self.box_glyphs.borrow_mut().clear();
+        self.bg_colours.borrow_mut().clear();
+    }
+
+    /// Dim everything drawn in `frame` so far this frame, on the current
+    /// layer and the layers below it, by redrawing it on the current layer at
+    /// half brightness. This is meant for de-emphasizing whatever's behind a
+    /// modal or popup.
+    ///
+    /// Text drawn in the terminal's default colour uses the terminal's faint
+    /// style instead, since its actual colour isn't known. Other styles, like
+    /// bold, aren't kept. Cells that weren't drawn this frame aren't changed.
+    pub fn dim_region(&self, frame: Frame) {
+        const BRIGHTNESS: f32 = 0.5;
+
+        let layer = self.layer.get();
+        let mut cells = BTreeMap::new();
+        let mut colours = replay_cells(&self.output_buffer, frame, &mut cells);
+        if layer != 0 {
+            // Layers start with their colours reset
+            colours = (None, None);
+        }
+        for (buffer_layer, buffer) in self.layers.borrow().range(1..=layer) {
+            let layer_colours = replay_cells(buffer, frame, &mut cells);
+            if *buffer_layer == layer {
+                colours = layer_colours;
+            }
+        }
+
+        let mut last_colours = None;
+        for ((y, x), (text, fg, bg)) in cells {
+            if last_colours != Some((fg, bg)) {
+                last_colours = Some((fg, bg));
+                match fg {
+                    Some(fg) => {
+                        self.write_raw(b"\x1B[22m");
+                        self.set_fg(Some(fg.scaled(BRIGHTNESS)));
+                    }
+                    None => {
+                        self.set_fg(None);
+                        self.write_raw(b"\x1B[2m");
+                    }
+                }
+                self.set_bg(bg.map(|bg| bg.scaled(BRIGHTNESS)));
+            }
+            self.render_string(&text, (x, y));
+        }
+
+        // Put the colours back the way they were before
+        self.write_raw(b"\x1B[22m");
+        self.set_fg(colours.0);
+        self.set_bg(colours.1);
+    }
 
     pub fn render_bytes(&self, bytes: &[u8], position: (u16, u16)) {
-        let mut buffer = &self.output_buffer;
+        self.render_with(position, |buffer| buffer.extend_from_slice(bytes));
+    }
+    /// Render formatted text, such as from [`format_args!`]. This formats
+    /// straight into the output buffer, so unlike rendering the output of
+    /// [`format!`], it doesn't allocate a [`String`] every frame.
+    pub fn render_fmt(&self, args: fmt::Arguments, position: (u16, u16)) {
+        self.render_with(position, |mut buffer| buffer.write_fmt(args).unwrap());
+    }
+    /// Move the cursor to `position`, then render whatever `render` writes to
+    /// the output buffer.
+    fn render_with(&self, position: (u16, u16), render: impl FnOnce(&ArenaVec<u8>)) {
+        let width = self.with_buffer(|mut buffer| {
+            // Move cursor
+            write!(buffer, "\x1B[{};{}H", position.1 + 1, position.0 + 1).unwrap();
+            // Print bytes
+            let start = buffer.len();
+            render(buffer);
+
+            let bytes = &buffer[start..];
+            match str::from_utf8(bytes) {
+                Ok(string) => ansi::visible_width(string),
+                Err(_) => bytes.len(),
+            }
+        });
 
-        // Move cursor
-        write!(buffer, "\x1B[{};{}H", position.1 + 1, position.0 + 1).unwrap();
-        // Print bytes
-        buffer.extend_from_slice(bytes);
+        // Remember the background of the cells we drew over
+        let layer = self.layer.get();
+        let mut bg_colours = self.bg_colours.borrow_mut();
+        for x in position.0..position.0.saturating_add(width as u16) {
+            let cell = (x, position.1);
+            if bg_colours
+                .get(&cell)
+                .is_some_and(|(above, _)| *above > layer)
+            {
+                continue;
+            }
+            match self.current_bg.get() {
+                Some(bg) => bg_colours.insert(cell, (layer, bg)),
+                None => bg_colours.remove(&cell),
+            };
+        }
     }
     pub fn render_char(&self, figure: char, position: (u16, u16)) {
         let mut buf = [0; 4];
         let string = figure.encode_utf8(&mut buf);
         self.render_bytes(string.as_bytes(), position)
     }
+    /// Render a box-drawing character. If [`Terminal::join_borders`] is
+    /// enabled, this joins it with any box-drawing character that was already
+    /// drawn in the same cell this frame.
+    pub fn render_box_char(&self, glyph: char, position: (u16, u16)) {
+        if !self.join_borders {
+            return self.render_char(glyph, position);
+        }
+
+        let mut box_glyphs = self.box_glyphs.borrow_mut();
+        let glyph = match box_glyphs.get(&position) {
+            Some(below) => box_drawing::join(*below, glyph),
+            None => glyph,
+        };
+        box_glyphs.insert(position, glyph);
+        self.render_char(glyph, position);
+    }
     pub fn render_string(&self, string: &str, position: (u16, u16)) {
         self.render_bytes(string.as_bytes(), position)
     }
     pub fn render_string_unpositioned(&self, string: &str) {
-        self.output_buffer.extend_from_slice(string.as_bytes());
+        self.write_raw(string.as_bytes());
+    }
+    /// Like [`Terminal::render_fmt`], but renders wherever the cursor is.
+    pub fn render_fmt_unpositioned(&self, args: fmt::Arguments) {
+        self.with_buffer(|mut buffer| buffer.write_fmt(args).unwrap());
+    }
+    /// Write `bytes` to the current layer's output buffer exactly as they
+    /// are. This is for escape sequences the terminal doesn't support itself,
+    /// like the Kitty graphics protocol or uncommon DECSET modes.
+    ///
+    /// Nothing is checked: the bytes don't need to be UTF-8, and the terminal
+    /// doesn't know what they do. If they move the cursor, change colours, or
+    /// switch modes, the rest of the frame may be drawn wrong unless the
+    /// sequence undoes those changes itself.
+    pub fn write_raw(&self, bytes: &[u8]) {
+        self.with_buffer(|buffer| buffer.extend_from_slice(bytes));
+    }
+
+    /// If the frame in the output buffer needs to be drawn. This is false if
+    /// the frame is identical to the last one, unless a full redraw was
+    /// forced.
+    ///
+    /// Frames that were partially drawn with [`Terminal::flush`] always need
+    /// to be finished.
+    pub fn should_redraw(&self) -> bool {
+        self.force_full_redraw
+            || self.flushed.get() > 0
+            || self.output_buffer.as_slice() != self.last_frame.as_slice()
+    }
+
+    /// Immediately draw everything rendered so far this frame, without
+    /// waiting for [`Terminal::update`]. This doesn't read input or change any
+    /// other state, so it can be used to show progress in the middle of a long
+    /// frame.
+    pub fn flush(&self) {
+        let mut output = self.output.borrow_mut();
+        self.write_unflushed(&mut **output);
+        output.flush().unwrap();
+    }
+    /// Write the part of the output buffer that hasn't been flushed yet. The
+    /// first write of each frame clears the screen first.
+    fn write_unflushed(&self, output: &mut dyn Write) {
+        let flushed = self.flushed.get();
+        if flushed == 0 {
+            output.write_all(b"\x1B[0m\x1B[2J\x1B[H").unwrap();
+        }
+        output.write_all(&self.output_buffer[flushed..]).unwrap();
+        self.flushed.set(self.output_buffer.len());
+    }
+
+    /// Copy `text` to the terminal emulator's clipboard, with an OSC 52
+    /// escape sequence. Since this goes through the terminal, it works over
+    /// SSH and without a clipboard daemon; terminals that don't support it
+    /// ignore it. See also [`Clipboard::osc52`].
+    ///
+    /// [`Clipboard::osc52`]: crate::clipboard::Clipboard::osc52
+    pub fn set_clipboard(&self, text: &str) {
+        self.render_fmt_unpositioned(format_args!(
+            "\x1B]52;c;{}\x07",
+            clipboard::base64(text.as_bytes())
+        ));
+    }
+
+    /// Ask the terminal emulator to send keys with the [Kitty keyboard
+    /// protocol], so keys that are normally ambiguous (like Tab and Ctrl+I, or
+    /// Enter and Ctrl+M) are reported as different keys with modifiers.
+    ///
+    /// Terminals that don't support the protocol ignore this and keep using
+    /// the legacy encoding. Once the terminal acknowledges it,
+    /// [`Terminal::kitty_keyboard`] is set.
+    ///
+    /// [Kitty keyboard protocol]: https://sw.kovidgoyal.net/kitty/keyboard-protocol/
+    pub fn enable_kitty_keyboard(&self) {
+        if !self.attached {
+            return;
+        }
+
+        // Push the "disambiguate escape codes" flag, then query the current
+        // flags; only terminals that support the protocol answer the query
+        stdout().write_all(b"\x1B[>1u\x1B[?u").unwrap();
+        stdout().flush().unwrap();
+    }
+
+    /// Set the cursor's shape for this frame. Like
+    /// [`Terminal::target_cursor_location`], this has to be set every frame;
+    /// otherwise the cursor goes back to [`CursorStyle::Default`].
+    pub fn set_cursor_style(&self, style: CursorStyle) {
+        self.target_cursor_style.set(style);
+    }
+
+    /// The modifier keys that were held when `key` was pressed.
+    pub fn key_modifiers(&self, key: Key) -> ModifierKeys {
+        self.key_modifiers.get(&key).copied().unwrap_or_default()
+    }
+    /// If `key` was pressed this frame with exactly the given modifier keys.
+    pub fn key_pressed_with(&self, key: Key, modifiers: ModifierKeys) -> bool {
+        self.pressed_keys.contains(&key) && self.key_modifiers(key) == modifiers
+    }
+
+    /// If `button` was pressed this frame. This is only true for the first
+    /// frame of a press; after that, the button is held.
+    pub fn mouse_clicked(&self, button: u8) -> bool {
+        self.clicked_mouse_buttons.contains(&button)
+    }
+    /// If `button` has been held down since an earlier frame.
+    pub fn mouse_held(&self, button: u8) -> bool {
+        self.held_mouse_buttons.contains(&button)
+    }
+    /// If `button` was released this frame.
+    pub fn mouse_released(&self, button: u8) -> bool {
+        self.released_mouse_buttons.contains(&button)
     }
 
     pub fn update(&mut self) {
-        print!("\x1B[0m\x1B[2J\x1B[H");
-        if let Some((x, y)) = self.target_cursor_location.take() {
-            // Move cursor
-            write!(&self.output_buffer, "\x1B[{};{}H", y + 1, x + 1).unwrap();
-            // Show cursor
-            write!(&self.output_buffer, "\x1B[?25h").unwrap();
+        let cursor_location = self.target_cursor_location.take();
+        let cursor_style = self.target_cursor_style.take();
+
+        // Draw the layers on top of everything else, resetting the colours
+        // left over from the layer below
+        for layer in self.layers.get_mut().values_mut() {
+            if !layer.is_empty() {
+                self.output_buffer.extend_from_slice(b"\x1B[0m");
+                self.output_buffer.extend_from_arena_copy(layer);
+                layer.clear();
+            }
+        }
+
+        let redraw = self.should_redraw();
+        let mut output = self.output.borrow_mut();
+        if redraw {
+            self.write_unflushed(&mut **output);
+        }
+        if cursor_style != self.cursor_style {
+            output.write_all(cursor_style.escape().as_bytes()).unwrap();
+        }
+        if let Some((x, y)) = cursor_location {
+            // Move cursor, then show cursor
+            write!(output, "\x1B[{};{}H\x1B[?25h", y + 1, x + 1).unwrap();
         } else {
             // Hide cursor
-            write!(&self.output_buffer, "\x1B[?25l").unwrap();
+            output.write_all(b"\x1B[?25l").unwrap();
         }
-        stdout().write_all(&self.output_buffer).unwrap();
-        stdout().flush().unwrap();
+        output.flush().unwrap();
+        drop(output);
+        self.flushed.set(0);
+        self.cursor_style = cursor_style;
+        self.force_full_redraw = false;
+        mem::swap(&mut self.output_buffer, &mut self.last_frame);
         self.output_buffer.clear();
+        self.box_glyphs.get_mut().clear();
+        self.bg_colours.get_mut().clear();
+        self.current_bg.set(None);
 
-        // Get terminal size
-        self.size = self.os.terminal_size();
-
-        // Clear old user input
-        self.pressed_keys.clear();
-
+        self.update_input();
+    }
+    /// Read the user's input and the terminal's size, without drawing a
+    /// frame. [`Terminal::update`] does this after drawing; [`TuiRunloop`]
+    /// calls it on its own when it skips a frame.
+    ///
+    /// [`TuiRunloop`]: crate::runloop::TuiRunloop
+    pub fn update_input(&mut self) {
         // Progress mouse button states
         for btn in self.clicked_mouse_buttons.drain() {
             self.held_mouse_buttons.insert(btn);
         }
         self.released_mouse_buttons.clear();
 
+        if !self.attached {
+            // Unattached terminals don't read input, but tests can set it
+            // directly
+            self.input_changed = self.has_input();
+            return;
+        }
+        let previous = (self.size, self.mouse_pos, self.focused);
+
+        // Get terminal size
+        // If it changed, the terminal may have garbled the last frame, so we
+        // have to redraw it
+        if self.os.take_resized() {
+            let size = self.os.terminal_size();
+            if size != self.size {
+                self.size = size;
+                self.force_full_redraw = true;
+            }
+        }
+
+        // Clear old user input
+        self.pressed_keys.clear();
+        self.key_modifiers.clear();
+        self.modifier_keys = ModifierKeys::default();
+        self.scroll_direction = None;
+
         Os::update(self);
+
+        self.key_repeat
+            .filter(&self.pressed_keys, Instant::now(), &mut self.debounced_keys);
+        self.input_changed = self.force_full_redraw
+            || self.disconnected
+            || previous != (self.size, self.mouse_pos, self.focused)
+            || self.has_input();
+    }
+    /// If any keys were pressed, mouse buttons were clicked or released, or
+    /// the mouse was scrolled since the last update.
+    fn has_input(&self) -> bool {
+        !self.pressed_keys.is_empty()
+            || !self.clicked_mouse_buttons.is_empty()
+            || !self.released_mouse_buttons.is_empty()
+            || self.scroll_direction.is_some()
     }
 
     /// Called when the [`Terminal`] is dropped, or when the program panics, to
@@ -157,25 +667,28 @@ impl Terminal {
             return;
         }
 
-        // disable all of the things we enabled in [`INITIAL_COMMANDS`]
-        const FINAL_COMMANDS: &str = concat!(
-            // show the cursor
-            "\x1B[?25h",
-            // leave the alternate buffer
-            "\x1B[?1049l",
-            // disable mouse location reporting
-            "\x1B[?1003l",
-            // disable SGR extended mouse location reporting
-            "\x1B[?1006l",
-        );
-        stdout().write_all(FINAL_COMMANDS.as_bytes()).unwrap();
-        stdout().flush().unwrap();
+        // If the terminal disconnected, these fail; there's nothing left to
+        // reset, so that's fine
+        let _ = stdout().write_all(FINAL_COMMANDS.as_bytes());
+        let _ = stdout().flush();
 
         os.set_raw_mode(false);
+        os.restore();
     }
 }
 impl Default for Terminal {
     fn default() -> Self {
+        Self::with_output(stdout())
+    }
+}
+impl Terminal {
+    /// Create a [`Terminal`] that writes its frames to `output` instead of
+    /// stdout. This is useful for capturing frames, like for logging or
+    /// golden-file tests.
+    ///
+    /// The terminal is otherwise set up normally: input is still read from,
+    /// and raw mode is still set on, the real terminal.
+    pub fn with_output(output: impl Write + 'static) -> Self {
         let os = Os::default();
 
         os.set_raw_mode(true);
@@ -192,27 +705,73 @@ impl Default for Terminal {
             // this is an alternate screen that doesn't scrollback, so we can
             // just draw to it and won't be deleting terminal history
             "\x1B[?1049h",
+            // enable focus reporting, so we know when the terminal gains or
+            // loses focus
+            "\x1B[?1004h",
+        );
+        const MOUSE_COMMANDS: &str = concat!(
             // enable mouse location reporting
             "\x1B[?1003h",
             // enable SGR extended mouse location reporting
             // without this, mouse x/y coords are each limited between 0 and 223
             "\x1B[?1006h",
         );
+        let env = |name: &str| std::env::var(name).ok();
         stdout().write_all(INITIAL_COMMANDS.as_bytes()).unwrap();
+        if capabilities::detect_mouse_support(env) {
+            stdout().write_all(MOUSE_COMMANDS.as_bytes()).unwrap();
+        }
         stdout().flush().unwrap();
 
-        // Set a panic handler to leave the alternate buffer before printing
-        // the panic message
-        // Otherwise the message will be printed inside the alternate buffer,
-        // and then we leave the alternate buffer when Terminal is dropped,
-        // so the message can't be seen.
-        let normal_panic_handler = std::panic::take_hook();
-        let os2 = os.clone();
-        std::panic::set_hook(Box::new(move |panic_info| {
-            Terminal::on_drop(&os2);
-            normal_panic_handler(panic_info);
+        // This terminal needs to be reset when it's dropped, even if an
+        // earlier terminal was already reset
+        TERMINAL_DROPPED.store(false, Ordering::Release);
+        Self::install_panic_hook(&os);
+
+        let mut this = Self::with_os(os, Box::new(output), true);
+        this.colour_mode = capabilities::detect_colour_mode(env);
+        this
+    }
+
+    /// Set a panic handler to leave the alternate buffer before printing the
+    /// panic message. Otherwise the message will be printed inside the
+    /// alternate buffer, and then we leave the alternate buffer when Terminal
+    /// is dropped, so the message can't be seen.
+    ///
+    /// The hook is only installed once, no matter how many terminals are
+    /// created. Returns false if it was already installed.
+    fn install_panic_hook(os: &Os) -> bool {
+        if PANIC_HOOK_INSTALLED.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+
+        *PREVIOUS_PANIC_HOOK.lock().unwrap() = Some(panic::take_hook());
+        let os = os.clone();
+        panic::set_hook(Box::new(move |panic_info| {
+            Terminal::on_drop(&os);
+            if let Ok(previous) = PREVIOUS_PANIC_HOOK.lock() {
+                if let Some(previous) = previous.as_ref() {
+                    previous(panic_info);
+                }
+            }
         }));
 
+        true
+    }
+    /// Remove the panic hook that resets the terminal, and restore the panic
+    /// hook that was set before it. The hook is installed again by the next
+    /// [`Terminal`] that's created.
+    pub fn uninstall_panic_hook() {
+        if !PANIC_HOOK_INSTALLED.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        if let Some(previous) = PREVIOUS_PANIC_HOOK.lock().unwrap().take() {
+            panic::set_hook(previous);
+        }
+    }
+
+    fn with_os(os: Os, output: Box<dyn Write>, attached: bool) -> Self {
         Self {
             size: (0, 0),
             mouse_pos: (0, 0),
@@ -222,15 +781,457 @@ impl Default for Terminal {
             held_mouse_buttons: HashSet::default(),
             released_mouse_buttons: HashSet::default(),
             pressed_keys: HashSet::default(),
+            key_modifiers: HashMap::default(),
+            debounced_keys: HashSet::default(),
+            key_repeat: KeyRepeatFilter::default(),
             exit: false,
+            disconnected: false,
+            kitty_keyboard: false,
+            focused: true,
+            redraw_requested: false,
+            input_changed: false,
+            force_full_redraw: false,
+            target_fps: None,
+            colour_mode: ColourMode::TrueColour,
+            join_borders: false,
+            box_glyphs: RefCell::default(),
+            current_bg: Cell::new(None),
+            bg_colours: RefCell::default(),
             target_cursor_location: Cell::new(None),
+            target_cursor_style: Cell::default(),
+            cursor_style: CursorStyle::Default,
             output_buffer: ArenaVec::with_reserved_memory(MemoryAmount::Megabytes(1).into_bytes()),
+            layer: Cell::new(0),
+            layers: RefCell::default(),
+            last_frame: ArenaVec::with_reserved_memory(MemoryAmount::Megabytes(1).into_bytes()),
+            output: RefCell::new(output),
+            flushed: Cell::new(0),
             os,
+            attached,
         }
     }
+
+    /// A [`Terminal`] that isn't attached to a real terminal emulator, for
+    /// tests. Its frames are discarded.
+    #[cfg(test)]
+    pub(crate) fn headless() -> Self {
+        Self::with_os(Os::headless(), Box::new(std::io::sink()), false)
+    }
 }
 impl Drop for Terminal {
     fn drop(&mut self) {
-        Self::on_drop(&self.os);
+        if self.attached {
+            Self::on_drop(&self.os);
+        }
+    }
+}
+
+/// The text, text colour, and background colour of a cell.
+type ReplayedCell = (String, Option<Colour>, Option<Colour>);
+
+/// Replay the escapes and text in an output buffer, and store every cell
+/// drawn inside `frame` in `cells`, keyed by row then column so they're in
+/// drawing order. Returns the text and background colours that are set at the
+/// end of the buffer.
+fn replay_cells(
+    buffer: &[u8],
+    frame: Frame,
+    cells: &mut BTreeMap<(u16, u16), ReplayedCell>,
+) -> (Option<Colour>, Option<Colour>) {
+    let buffer = String::from_utf8_lossy(buffer);
+    let (mut fg, mut bg) = (None, None);
+    let mut cursor = (0, 0);
+
+    for segment in ansi::segments(&buffer) {
+        match segment {
+            AnsiSegment::Escape(escape) => {
+                if let Some(position) = ansi::parse_cursor_position(escape) {
+                    cursor = position;
+                } else if escape == "\x1B[2J" {
+                    // The screen was cleared
+                    cells.clear();
+                }
+                for command in ansi::parse_sgr(escape).unwrap_or_default() {
+                    match command {
+                        Sgr::Reset => (fg, bg) = (None, None),
+                        Sgr::Foreground(colour) => fg = colour,
+                        Sgr::Background(colour) => bg = colour,
+                        Sgr::Attribute(_) => {}
+                    }
+                }
+            }
+            AnsiSegment::Text(text) => {
+                for grapheme in text.graphemes(true) {
+                    if frame.contains(cursor) {
+                        cells.insert((cursor.1, cursor.0), (grapheme.to_string(), fg, bg));
+                    }
+                    cursor.0 = cursor.0.saturating_add(1);
+                }
+            }
+        }
+    }
+
+    (fg, bg)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{CursorStyle, Terminal, FINAL_COMMANDS, PANIC_HOOK_INSTALLED, TERMINAL_DROPPED},
+        crate::{
+            capabilities::ColourMode,
+            input::Key,
+            msg::{tui_msg_handler, TuiMsg},
+            os::Os,
+            shapes::Rect,
+            widgets::Frame,
+            Colour,
+        },
+        scaffolding::world::World,
+        std::{
+            cell::RefCell,
+            collections::BTreeSet,
+            io, panic,
+            rc::Rc,
+            str,
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+        },
+    };
+
+    /// An output sink that can still be read after it's given to a terminal.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+    impl io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn frames_go_to_output() {
+        let buffer = SharedBuffer::default();
+        let mut terminal = Terminal::headless();
+        *terminal.output.get_mut() = Box::new(buffer.clone());
+
+        terminal.render_string("hi", (1, 0));
+        terminal.update();
+        assert_eq!(
+            str::from_utf8(&buffer.0.borrow()).unwrap(),
+            "\x1B[0m\x1B[2J\x1B[H\x1B[1;2Hhi\x1B[?25l"
+        );
+    }
+
+    #[test]
+    fn flush() {
+        let buffer = SharedBuffer::default();
+        let mut terminal = Terminal::headless();
+        *terminal.output.get_mut() = Box::new(buffer.clone());
+        terminal.pressed_keys.insert(Key::Text('a'));
+
+        terminal.render_string("hi", (0, 0));
+        terminal.flush();
+        assert_eq!(
+            str::from_utf8(&buffer.0.borrow()).unwrap(),
+            "\x1B[0m\x1B[2J\x1B[H\x1B[1;1Hhi"
+        );
+        assert!(terminal.pressed_keys.contains(&Key::Text('a')));
+
+        // Flushing again only writes what's new, and finishing the frame
+        // doesn't write what was already flushed
+        terminal.render_string("!", (2, 0));
+        terminal.flush();
+        terminal.update();
+        assert_eq!(
+            str::from_utf8(&buffer.0.borrow()).unwrap(),
+            "\x1B[0m\x1B[2J\x1B[H\x1B[1;1Hhi\x1B[1;3H!\x1B[?25l"
+        );
+    }
+
+    #[test]
+    fn layers() {
+        let buffer = SharedBuffer::default();
+        let mut terminal = Terminal::headless();
+        *terminal.output.get_mut() = Box::new(buffer.clone());
+        let rect = |x| Rect {
+            x,
+            y: 0,
+            width: 2,
+            height: 1,
+        };
+
+        // The higher layer is drawn first, but still ends up on top
+        terminal.set_bg(Some(Colour::RED));
+        terminal.draw_on_layer(1, rect(1));
+        terminal.set_bg(Some(Colour::BLUE));
+        terminal.draw(rect(0));
+        assert_eq!(terminal.bg_at((2, 0)), Some(Colour::RED));
+        terminal.update();
+        assert_eq!(
+            str::from_utf8(&buffer.0.borrow()).unwrap(),
+            concat!(
+                "\x1B[0m\x1B[2J\x1B[H",
+                "\x1B[48;2;255;0;0m\x1B[48;2;0;0;255m\x1B[1;1H  ",
+                "\x1B[0m\x1B[48;2;255;0;0m\x1B[1;2H  \x1B[?25l"
+            )
+        );
+    }
+
+    #[test]
+    fn render_fmt() {
+        let frames = 42;
+        let formatted = Terminal::headless();
+        formatted.set_bg(Some(Colour::RED));
+        formatted.render_fmt(format_args!("Frames: {frames}"), (2, 1));
+        formatted.render_fmt_unpositioned(format_args!("{}", '!'));
+
+        let rendered = Terminal::headless();
+        rendered.set_bg(Some(Colour::RED));
+        rendered.render_string(&format!("Frames: {frames}"), (2, 1));
+        rendered.render_string_unpositioned("!");
+
+        assert_eq!(formatted.output_buffer, rendered.output_buffer);
+        assert_eq!(formatted.bg_at((11, 1)), Some(Colour::RED));
+        assert_eq!(formatted.bg_at((12, 1)), None);
+    }
+
+    #[test]
+    fn write_raw() {
+        let terminal = Terminal::headless();
+        let bytes = b"\x1B_Gf=24,s=1,v=1;AAAA\x1B\\\xFF\x00";
+        terminal.write_raw(bytes);
+        assert_eq!(terminal.output_buffer.as_slice(), bytes);
+    }
+
+    #[test]
+    fn dim_region() {
+        let terminal = Terminal::headless();
+        let bg = Colour::new(200, 100, 50);
+        terminal.set_bg(Some(bg));
+        terminal.set_fg(Some(Colour::new(100, 100, 100)));
+        terminal.render_string("abcd", (0, 0));
+        terminal.set_fg(None);
+        terminal.render_string("xyz", (0, 1));
+        let drawn = terminal.output_buffer.len();
+
+        terminal.dim_region(Frame {
+            x: 1,
+            y: 0,
+            width: 2,
+            height: 2,
+        });
+        let dimmed = str::from_utf8(&terminal.output_buffer[drawn..]).unwrap();
+        assert_eq!(
+            dimmed,
+            concat!(
+                "\x1B[22m\x1B[38;2;50;50;50m\x1B[48;2;100;50;25m",
+                "\x1B[1;2Hb\x1B[1;3Hc",
+                // Text in the default colour is made faint instead
+                "\x1B[39m\x1B[2m\x1B[48;2;100;50;25m",
+                "\x1B[2;2Hy\x1B[2;3Hz",
+                "\x1B[22m\x1B[39m\x1B[48;2;200;100;50m",
+            )
+        );
+        assert_eq!(terminal.bg_at((1, 0)), Some(Colour::new(100, 50, 25)));
+        assert_eq!(terminal.bg_at((3, 0)), Some(bg));
+        assert_eq!(terminal.bg_at((0, 1)), Some(bg));
+    }
+
+    #[test]
+    fn colour_modes() {
+        let mut terminal = Terminal::headless();
+        let mut set_colours = |mode| {
+            terminal.colour_mode = mode;
+            terminal.output_buffer.clear();
+            terminal.set_fg(Some(Colour::RED));
+            terminal.set_bg(Some(Colour::GREY));
+            terminal.set_fg(None);
+            str::from_utf8(&terminal.output_buffer).unwrap().to_string()
+        };
+
+        assert_eq!(
+            set_colours(ColourMode::TrueColour),
+            "\x1B[38;2;255;0;0m\x1B[48;2;127;127;127m\x1B[39m"
+        );
+        assert_eq!(
+            set_colours(ColourMode::Ansi256),
+            "\x1B[38;5;196m\x1B[48;5;243m\x1B[39m"
+        );
+        assert_eq!(set_colours(ColourMode::Ansi16), "\x1B[91m\x1B[47m\x1B[39m");
+        assert_eq!(set_colours(ColourMode::NoColour), "");
+    }
+
+    #[test]
+    fn clear() {
+        let terminal = Terminal::headless();
+        terminal.set_bg(Some(Colour::BLUE));
+        terminal.render_string("before", (0, 0));
+        terminal.clear();
+        let cleared = terminal.output_buffer.len();
+        assert!(terminal.output_buffer[..cleared].ends_with(b"\x1B[2J\x1B[H"));
+        assert_eq!(terminal.bg_at((0, 0)), None);
+
+        // Only what's drawn after clearing is left on the screen
+        terminal.render_string("x", (1, 0));
+        terminal.dim_region(Frame {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 1,
+        });
+        let dimmed = str::from_utf8(&terminal.output_buffer[cleared..]).unwrap();
+        assert_eq!(
+            dimmed,
+            concat!(
+                "\x1B[1;2Hx",
+                "\x1B[39m\x1B[2m\x1B[48;2;0;0;128m\x1B[1;2Hx",
+                "\x1B[22m\x1B[39m\x1B[48;2;0;0;255m",
+            )
+        );
+    }
+
+    #[test]
+    fn colour_ordering() {
+        let mut colours = vec![
+            Colour::WHITE,
+            Colour::new(0, 0, 1),
+            Colour::RED,
+            Colour::BLACK,
+            Colour::new(0, 1, 0),
+            Colour::RED,
+        ];
+        colours.sort();
+        colours.dedup();
+        assert_eq!(
+            colours,
+            [
+                Colour::BLACK,
+                Colour::new(0, 0, 1),
+                Colour::new(0, 1, 0),
+                Colour::RED,
+                Colour::WHITE,
+            ]
+        );
+
+        let palette: BTreeSet<Colour> = (0..=255).map(Colour::from_ansi256).collect();
+        assert!(palette.contains(&Colour::new(95, 135, 175)));
+        assert!(!palette.contains(&Colour::new(1, 2, 3)));
+        assert_eq!(palette.first(), Some(&Colour::BLACK));
+        assert_eq!(palette.last(), Some(&Colour::WHITE));
+    }
+
+    #[test]
+    fn blending() {
+        let purple = Colour::new(128, 0, 128);
+        assert_eq!(Colour::RED.over_cell(Colour::BLUE, 0.5), purple);
+        assert_eq!(Colour::RED.over_cell(Colour::BLUE, 1.), Colour::RED);
+        assert_eq!(Colour::RED.over_cell(Colour::BLUE, 0.), Colour::BLUE);
+
+        let terminal = Terminal::headless();
+        terminal.set_bg(Some(Colour::BLUE));
+        terminal.render_string("  ", (1, 0));
+        terminal.set_bg(None);
+        assert_eq!(terminal.bg_at((2, 0)), Some(Colour::BLUE));
+        assert_eq!(terminal.bg_at((3, 0)), None);
+
+        terminal.set_bg_blended(Colour::RED, 0.5, (2, 0));
+        terminal.render_char(' ', (2, 0));
+        assert_eq!(terminal.bg_at((2, 0)), Some(purple));
+
+        // Cells without a background aren't blended
+        terminal.set_bg_blended(Colour::RED, 0.5, (3, 0));
+        assert_eq!(terminal.bg_at((1, 0)), Some(Colour::BLUE));
+        assert!(str::from_utf8(&terminal.output_buffer)
+            .unwrap()
+            .ends_with("\x1B[48;2;255;0;0m"));
+    }
+
+    #[test]
+    fn panic_hook_installs_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls2 = calls.clone();
+        panic::set_hook(Box::new(move |_| {
+            calls2.fetch_add(1, Ordering::Relaxed);
+        }));
+        // Stop the hook from resetting the real terminal
+        TERMINAL_DROPPED.store(true, Ordering::Release);
+
+        assert!(Terminal::install_panic_hook(&Os::headless()));
+        assert!(!Terminal::install_panic_hook(&Os::headless()));
+        assert!(!Terminal::install_panic_hook(&Os::headless()));
+        let _ = panic::catch_unwind(|| panic!("first panic"));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        // Uninstalling restores the original hook
+        Terminal::uninstall_panic_hook();
+        assert!(!PANIC_HOOK_INSTALLED.load(Ordering::Acquire));
+        let _ = panic::catch_unwind(|| panic!("second panic"));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        assert!(Terminal::install_panic_hook(&Os::headless()));
+        Terminal::uninstall_panic_hook();
+        drop(panic::take_hook());
+    }
+
+    #[test]
+    fn cursor_styles() {
+        let styles = [
+            (CursorStyle::Default, "\x1B[0 q"),
+            (CursorStyle::BlinkingBlock, "\x1B[1 q"),
+            (CursorStyle::SteadyBlock, "\x1B[2 q"),
+            (CursorStyle::BlinkingUnderline, "\x1B[3 q"),
+            (CursorStyle::SteadyUnderline, "\x1B[4 q"),
+            (CursorStyle::BlinkingBar, "\x1B[5 q"),
+            (CursorStyle::SteadyBar, "\x1B[6 q"),
+        ];
+        for (style, escape) in styles {
+            assert_eq!(style.escape(), escape);
+        }
+
+        // Dropping the terminal resets the cursor style
+        assert!(FINAL_COMMANDS.contains(CursorStyle::Default.escape()));
+
+        // Cursor styles only last one frame
+        let mut terminal = Terminal::headless();
+        terminal.set_cursor_style(CursorStyle::SteadyBar);
+        terminal.update();
+        assert_eq!(terminal.cursor_style, CursorStyle::SteadyBar);
+        terminal.update();
+        assert_eq!(terminal.cursor_style, CursorStyle::Default);
+    }
+
+    #[test]
+    fn identical_frames_are_skipped() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        world.add_msg_handler(tui_msg_handler);
+
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.render_string("hi", (0, 0));
+        assert!(terminal.should_redraw());
+        terminal.update();
+
+        terminal.render_string("hi", (0, 0));
+        assert!(!terminal.should_redraw());
+        terminal.update();
+
+        // Forcing a redraw only lasts for one frame
+        world.send_msg_now(TuiMsg::ForceFullRedraw);
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.render_string("hi", (0, 0));
+        assert!(terminal.should_redraw());
+        terminal.update();
+
+        terminal.render_string("hi", (0, 0));
+        assert!(!terminal.should_redraw());
+        terminal.update();
+
+        terminal.render_string("hello", (0, 0));
+        assert!(terminal.should_redraw());
     }
 }
@@ -0,0 +1,172 @@
+//! Short messages that pop up over the app for a while, then fade away.
+
+use {
+    crate::{ansi, shapes::Shape, terminal::Terminal, Colour},
+    core::time::Duration,
+    std::cell::RefCell,
+};
+
+/// The draw layer toasts are drawn on, so they cover everything else,
+/// including popups. See [`Terminal::draw_on_layer`].
+const TOAST_LAYER: u8 = 200;
+/// How long toasts take to fade out at the end of their duration.
+const FADE: Duration = Duration::from_millis(300);
+/// The toasts' colours.
+const TEXT: Colour = Colour::WHITE;
+const BACKGROUND: Colour = Colour::new(50, 50, 50);
+
+struct Toast {
+    message: String,
+    duration: Duration,
+    /// When the toast was first drawn, as [`Time::elapsed`]. Toasts don't
+    /// start counting down until they're drawn.
+    ///
+    /// [`Time::elapsed`]: scaffolding::world::Time::elapsed
+    shown_at: Option<Duration>,
+}
+
+/// Messages that show up in the bottom right corner of the terminal for a
+/// while, then disappear, without blocking the rest of the app. This is a
+/// singleton added by [`TuiPlugin`](crate::TuiPlugin); push toasts to it
+/// from anywhere, then call [`App::draw_toasts`](crate::App::draw_toasts)
+/// every frame to show them.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: RefCell<Vec<Toast>>,
+}
+impl Toasts {
+    /// Show `message` for `duration`. The duration starts the first time the
+    /// toast is drawn.
+    pub fn push(&self, message: impl Into<String>, duration: Duration) {
+        self.toasts.borrow_mut().push(Toast {
+            message: message.into(),
+            duration,
+            shown_at: None,
+        });
+    }
+    /// The messages of the toasts that haven't expired yet, oldest first.
+    pub fn messages(&self) -> Vec<String> {
+        self.toasts
+            .borrow()
+            .iter()
+            .map(|toast| toast.message.clone())
+            .collect()
+    }
+    pub fn len(&self) -> usize {
+        self.toasts.borrow().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.toasts.borrow().is_empty()
+    }
+    /// Remove every toast, even if it hasn't expired.
+    pub fn clear(&self) {
+        self.toasts.borrow_mut().clear();
+    }
+
+    /// Remove expired toasts and draw the rest, stacked upwards from the
+    /// bottom right corner with the newest at the bottom. `now` is
+    /// [`Time::elapsed`](scaffolding::world::Time::elapsed).
+    pub(crate) fn draw(&self, terminal: &Terminal, now: Duration) {
+        let mut toasts = self.toasts.borrow_mut();
+        toasts.retain_mut(|toast| *toast.shown_at.get_or_insert(now) + toast.duration > now);
+
+        let (columns, rows) = terminal.size;
+        for (idx, toast) in toasts.iter().rev().enumerate() {
+            // Leave a row between the toasts and the bottom of the terminal
+            let Some(y) = rows.checked_sub(2 + idx as u16) else {
+                break;
+            };
+            let width = ansi::visible_width(&toast.message) as u16 + 2;
+            let remaining = toast.shown_at.unwrap() + toast.duration - now;
+
+            terminal.draw_on_layer(
+                TOAST_LAYER,
+                ToastShape {
+                    x: columns.saturating_sub(width + 1),
+                    y,
+                    message: &toast.message,
+                    alpha: remaining.as_secs_f32() / FADE.as_secs_f32(),
+                },
+            );
+        }
+    }
+}
+
+/// Draws a single toast.
+struct ToastShape<'a> {
+    x: u16,
+    y: u16,
+    message: &'a str,
+    /// How faded in the toast is, from 0 to 1.
+    alpha: f32,
+}
+impl Shape for ToastShape<'_> {
+    type Output = ();
+
+    fn draw(self, terminal: &Terminal) -> Self::Output {
+        terminal.set_bg_blended(BACKGROUND, self.alpha, (self.x, self.y));
+        terminal.set_fg(Some(TEXT.over_cell(BACKGROUND, self.alpha)));
+        terminal.render_fmt(format_args!(" {} ", self.message), (self.x, self.y));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::App,
+        scaffolding::world::{Time, World},
+        std::time::Instant,
+    };
+
+    #[test]
+    fn toasts_expire() {
+        let start = Instant::now();
+        let mut world = World::new();
+        world
+            .add_singleton(Terminal::headless())
+            .add_singleton(Toasts::default())
+            .add_singleton(Time::starting_at(start));
+
+        let toasts: &Toasts = world.get_singleton();
+        toasts.push("short", Duration::from_secs(1));
+        toasts.push("long", Duration::from_secs(5));
+
+        world.execute(|app: &App| app.draw_toasts());
+        assert_eq!(
+            world.get_singleton::<Toasts>().messages(),
+            ["short", "long"]
+        );
+
+        world
+            .get_singleton_mut::<Time>()
+            .tick_at(start + Duration::from_secs(2));
+        world.execute(|app: &App| app.draw_toasts());
+        assert_eq!(world.get_singleton::<Toasts>().messages(), ["long"]);
+
+        world
+            .get_singleton_mut::<Time>()
+            .tick_at(start + Duration::from_secs(5));
+        world.execute(|app: &App| app.draw_toasts());
+        assert!(world.get_singleton::<Toasts>().is_empty());
+    }
+
+    #[test]
+    fn countdown_starts_when_drawn() {
+        let start = Instant::now();
+        let mut world = World::new();
+        world
+            .add_singleton(Terminal::headless())
+            .add_singleton(Toasts::default())
+            .add_singleton(Time::starting_at(start));
+
+        world
+            .get_singleton_mut::<Time>()
+            .tick_at(start + Duration::from_secs(10));
+        world
+            .get_singleton::<Toasts>()
+            .push("late", Duration::from_secs(1));
+        world.execute(|app: &App| app.draw_toasts());
+        assert_eq!(world.get_singleton::<Toasts>().len(), 1);
+    }
+}
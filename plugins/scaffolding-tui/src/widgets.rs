@@ -7,6 +7,13 @@ pub trait Widget<'a> {
 
     /// Build the draw function, which is an executable.
     fn build_draw_fn(self) -> impl TypeErasedExecutable<'a, Output = Self::Output>;
+    /// How many columns and rows this widget would like to take up, if it
+    /// has `available` columns and rows to fit in. Layouts can use this to
+    /// size widgets before positioning them. By default, widgets take up all
+    /// the space they're given.
+    fn measure(&self, available: (u16, u16)) -> (u16, u16) {
+        available
+    }
 }
 impl<'a, E: TypeErasedExecutable<'a>> Widget<'a> for E {
     type Output = E::Output;
@@ -18,7 +25,7 @@ impl<'a, E: TypeErasedExecutable<'a>> Widget<'a> for E {
 
 /// A rectangular area in the terminal. This is generally used for widget
 /// positioning.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Frame {
     pub x: u16,
     pub y: u16,
@@ -34,29 +41,21 @@ impl Frame {
 }
 
 /// Vertical alignment values.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum VAlign {
     Top,
+    #[default]
     Center,
     Bottom,
 }
-impl Default for VAlign {
-    fn default() -> Self {
-        Self::Center
-    }
-}
 /// Horizontal alignment values.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum HAlign {
     Left,
+    #[default]
     Center,
     Right,
 }
-impl Default for HAlign {
-    fn default() -> Self {
-        Self::Center
-    }
-}
 
 /// Adds common methods to widgets with a frame, such as:
 /// .x
@@ -142,7 +141,7 @@ macro_rules! impl_frame_methods {
         #[allow(unused_parens)]
         impl $ty {
             pub fn clicked(&self, terminal: &Terminal) -> bool {
-                terminal.clicked_mouse_buttons.contains(&0) && self.frame.contains(terminal.mouse_pos)
+                terminal.mouse_clicked(0) && self.frame.contains(terminal.mouse_pos)
             }
         }
     };
@@ -204,3 +203,13 @@ mod text;
 pub use text::*;
 mod checkbox;
 pub use checkbox::*;
+mod dropdown;
+pub use dropdown::*;
+mod grid;
+pub use grid::*;
+mod canvas;
+pub use canvas::*;
+mod logpane;
+pub use logpane::*;
+mod keyhints;
+pub use keyhints::*;
@@ -1,8 +1,19 @@
 use {
     super::*,
     crate::{prelude::Terminal, shapes::*, Colour},
+    scaffolding::datatypes::uniq::UniqKey,
+    std::borrow::Cow,
+    unicode_segmentation::UnicodeSegmentation,
 };
 
+/// Button state that persists across frames.
+#[derive(Default)]
+struct ButtonCache {
+    /// If the mouse was pressed on the button and hasn't been released yet.
+    held: bool,
+}
+
+/// The result of drawing a [`Button`]. This is recomputed every frame.
 #[derive(Debug)]
 pub struct ButtonOut {
     /// The current state of the button.
@@ -10,27 +21,26 @@ pub struct ButtonOut {
     /// The mouse cursor is over the button.
     pub hovered: bool,
 }
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
 pub enum ButtonState {
     /// The mouse has just clicked on the button.
     Pressed,
-    /// The mouse has been clicking the button for multiple frames.
+    /// The mouse has been clicking the button for multiple frames. This
+    /// stays set if the mouse moves off the button while it's held.
     Held,
-    /// The mouse just released the button.
+    /// The mouse just released the button. This is only set if the mouse
+    /// was pressed on the button, and then released over it.
     Released,
     /// The mouse is hovering over the button, but hasn't interacted with it.
     Hovered,
     /// The mouse isn't interacting with the button.
+    #[default]
     Inactive,
 }
-impl Default for ButtonState {
-    fn default() -> Self {
-        Self::Inactive
-    }
-}
 
 pub struct Button<'a> {
     label: &'a str,
+    cache_key: Option<UniqKey>,
     border_style: Option<BorderStyle>,
     background_colour: Option<Colour>,
     border_colour: Option<Colour>,
@@ -38,9 +48,10 @@ pub struct Button<'a> {
     frame: Frame,
 }
 impl<'a> Button<'a> {
-    pub fn new(label: &'a str) -> Self {
+    pub fn new(label: &'a str, cache_key: UniqKey) -> Self {
         Self {
             label,
+            cache_key: Some(cache_key),
             border_style: Some(BorderStyle::ROUND),
             border_colour: None,
             text_colour: None,
@@ -63,9 +74,18 @@ impl<'a> Button<'a> {
         self
     }
 
-    fn draw(mut self, terminal: &Singleton<Terminal>) -> ButtonOut {
+    fn draw(mut self, uniqs: &Uniqs, terminal: &Singleton<Terminal>) -> ButtonOut {
+        let cache: &mut ButtonCache = uniqs.get(self.cache_key.take().unwrap());
+
         terminal.set_bg(self.background_colour);
 
+        // The label goes inside the border, if there is one
+        let (inner_x, inner_width) = if self.border_style.is_some() {
+            (self.frame.x + 1, self.frame.width.saturating_sub(2))
+        } else {
+            (self.frame.x, self.frame.width)
+        };
+
         if let Some(style) = self.border_style.take() {
             terminal.set_fg(self.border_colour);
             terminal.draw(Border {
@@ -77,28 +97,30 @@ impl<'a> Button<'a> {
             });
         }
 
-        terminal.draw(
-            Text::new(self.label)
-                // TODO: Implement wrapping text
-                .horizontal_overflow(HorizontalOverflowStyle::Clip)
-                .frame(self.frame)
-                .text_colour(self.text_colour)
-                .background_colour(self.background_colour)
-                .horizontal_anchor(HAlign::Center),
-        );
+        let (label, label_width) = fit_label(self.label, inner_width as usize);
+        terminal.set_fg(self.text_colour);
+        terminal.draw(RawString {
+            x: inner_x + (inner_width - label_width as u16) / 2,
+            y: self.frame.y + self.frame.height / 2,
+            text: label,
+        });
 
         let hovered = self.hovered(terminal);
 
-        let state = if hovered {
-            if terminal.clicked_mouse_buttons.contains(&0) {
-                ButtonState::Pressed
-            } else if terminal.held_mouse_buttons.contains(&0) {
-                ButtonState::Held
-            } else if terminal.released_mouse_buttons.contains(&0) {
+        let state = if hovered && terminal.mouse_clicked(0) {
+            cache.held = true;
+            ButtonState::Pressed
+        } else if cache.held && terminal.mouse_held(0) {
+            ButtonState::Held
+        } else if cache.held && terminal.mouse_released(0) {
+            cache.held = false;
+            if hovered {
                 ButtonState::Released
             } else {
-                ButtonState::Hovered
+                ButtonState::Inactive
             }
+        } else if hovered {
+            ButtonState::Hovered
         } else {
             ButtonState::Inactive
         };
@@ -106,12 +128,132 @@ impl<'a> Button<'a> {
         ButtonOut { state, hovered }
     }
 }
+/// Shortens `label` so it fits in `width` columns, ending it with `…` if any of
+/// it had to be cut off. Also returns how many columns the label takes up.
+pub(super) fn fit_label(label: &str, width: usize) -> (Cow<'_, str>, usize) {
+    let len = label.graphemes(true).count();
+    if len <= width {
+        return (Cow::Borrowed(label), len);
+    }
+    if width == 0 {
+        return (Cow::Borrowed(""), 0);
+    }
+
+    let mut truncated: String = label.graphemes(true).take(width - 1).collect();
+    truncated.push('…');
+    (Cow::Owned(truncated), width)
+}
+
 impl<'a> Widget<'a> for Button<'a> {
     type Output = ButtonOut;
 
     fn build_draw_fn(self) -> impl TypeErasedExecutable<'a, Output = Self::Output> {
         Self::draw.with_state(self).type_erase()
     }
+    /// The label's width, plus room for the border if there is one.
+    fn measure(&self, available: (u16, u16)) -> (u16, u16) {
+        let label_width = self.label.graphemes(true).count() as u16;
+        let (width, height) = match self.border_style {
+            Some(_) => (label_width.saturating_add(2), 3),
+            None => (label_width, 1),
+        };
+
+        (width.min(available.0), height.min(available.1))
+    }
 }
 impl_frame_methods!(Button<'_>);
 impl_colour_methods!(Button<'_>, text_colour, border_colour, background_colour);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::App,
+        scaffolding::{uniq_key, world::World},
+    };
+
+    fn draw_label(label: &str) -> String {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        world.execute(|app: &App| app.draw(Button::new(label, uniq_key!()).width(8).height(3)));
+
+        let terminal: &Terminal = world.get_singleton();
+        String::from_utf8(terminal.output_buffer.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn measure() {
+        let button = Button::new("Submit", uniq_key!());
+        assert_eq!(button.measure((20, 20)), (8, 3));
+        assert_eq!(button.measure((5, 2)), (5, 2));
+
+        let button = Button::new("Submit", uniq_key!()).border(None);
+        assert_eq!(button.measure((20, 20)), (6, 1));
+    }
+
+    #[test]
+    fn fitting_label_is_centered() {
+        assert!(draw_label("Hi").contains("\x1B[2;4HHi"));
+    }
+
+    #[test]
+    fn overlong_label_is_ellipsized() {
+        let output = draw_label("Save and Continue");
+        assert!(output.contains("\x1B[2;2HSave …"));
+        assert!(!output.contains("Save a"));
+    }
+
+    fn draw_button(world: &mut World) -> ButtonOut {
+        world.execute(|app: &App| app.draw(Button::new("Hi", uniq_key!()).width(8).height(3)))
+    }
+
+    #[test]
+    fn held_persists_across_frames() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+
+        // Press on the button
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.mouse_pos = (1, 1);
+        terminal.clicked_mouse_buttons.insert(0);
+        let out = draw_button(&mut world);
+        assert_eq!(out.state, ButtonState::Pressed);
+        assert!(out.hovered);
+
+        // Drag off the button while holding the mouse
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.clicked_mouse_buttons.clear();
+        terminal.held_mouse_buttons.insert(0);
+        terminal.mouse_pos = (20, 20);
+        let out = draw_button(&mut world);
+        assert_eq!(out.state, ButtonState::Held);
+        assert!(!out.hovered);
+
+        // Release back over the button
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.held_mouse_buttons.clear();
+        terminal.released_mouse_buttons.insert(0);
+        terminal.mouse_pos = (2, 1);
+        let out = draw_button(&mut world);
+        assert_eq!(out.state, ButtonState::Released);
+        assert!(out.hovered);
+
+        // The press ended, so the button goes back to being hovered
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.released_mouse_buttons.clear();
+        let out = draw_button(&mut world);
+        assert_eq!(out.state, ButtonState::Hovered);
+    }
+
+    #[test]
+    fn held_needs_a_press_on_the_button() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.mouse_pos = (1, 1);
+        terminal.held_mouse_buttons.insert(0);
+        let out = draw_button(&mut world);
+        assert_eq!(out.state, ButtonState::Hovered);
+    }
+}
@@ -0,0 +1,152 @@
+use {
+    super::{Frame, Widget},
+    crate::{shapes::Shape, terminal::Terminal, Colour},
+    scaffolding::world::{Executable, ExecutableWithState, Singleton, TypeErasedExecutable},
+};
+
+/// An area that's drawn one cell at a time, for custom visualisations like
+/// charts. The closure passed to [`Canvas::new`] draws the canvas with a
+/// [`CanvasCtx`].
+pub struct Canvas<F: FnOnce(&mut CanvasCtx)> {
+    frame: Frame,
+    draw_fn: F,
+}
+impl<F: FnOnce(&mut CanvasCtx)> Canvas<F> {
+    pub fn new(draw_fn: F) -> Self {
+        Self {
+            frame: Frame {
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 10,
+            },
+            draw_fn,
+        }
+    }
+
+    // `impl_frame_methods!` doesn't support generics
+    pub fn x(mut self, x: u16) -> Self {
+        self.frame.x = x;
+        self
+    }
+    pub fn y(mut self, y: u16) -> Self {
+        self.frame.y = y;
+        self
+    }
+    pub fn width(mut self, width: u16) -> Self {
+        self.frame.width = width;
+        self
+    }
+    pub fn height(mut self, height: u16) -> Self {
+        self.frame.height = height;
+        self
+    }
+    pub fn frame(mut self, frame: Frame) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    fn draw(self, terminal: &Singleton<Terminal>) {
+        let mut ctx = CanvasCtx {
+            terminal,
+            frame: self.frame,
+        };
+        (self.draw_fn)(&mut ctx);
+    }
+}
+
+/// Draws cells in a [`Canvas`].
+pub struct CanvasCtx<'a> {
+    terminal: &'a Terminal,
+    frame: Frame,
+}
+impl CanvasCtx<'_> {
+    /// Draw `char` at `(x, y)`, relative to the top left of the canvas. Cells
+    /// outside of the canvas are ignored.
+    pub fn set(&mut self, x: u16, y: u16, char: char, fg: Option<Colour>, bg: Option<Colour>) {
+        if x >= self.frame.width || y >= self.frame.height {
+            return;
+        }
+
+        self.terminal.set_fg(fg);
+        self.terminal.set_bg(bg);
+        self.terminal
+            .render_char(char, (self.frame.x + x, self.frame.y + y));
+    }
+
+    /// How wide the canvas is.
+    pub fn width(&self) -> u16 {
+        self.frame.width
+    }
+    /// How tall the canvas is.
+    pub fn height(&self) -> u16 {
+        self.frame.height
+    }
+}
+
+impl<'a, F: FnOnce(&mut CanvasCtx) + 'a> Widget<'a> for Canvas<F> {
+    type Output = ();
+
+    fn build_draw_fn(self) -> impl TypeErasedExecutable<'a, Output = Self::Output> {
+        Self::draw.with_state(self).type_erase()
+    }
+}
+impl<F: FnOnce(&mut CanvasCtx)> Shape for Canvas<F> {
+    type Output = ();
+
+    fn draw(self, terminal: &Terminal) -> Self::Output {
+        self.draw(&Singleton::new(terminal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::App, scaffolding::world::World, std::str};
+
+    #[test]
+    fn cells_are_clipped() {
+        let terminal = Terminal::headless();
+        terminal.draw(
+            Canvas::new(|ctx| {
+                for idx in 0..5 {
+                    ctx.set(idx, idx, '*', None, Some(Colour::RED));
+                }
+            })
+            .x(2)
+            .y(1)
+            .width(3)
+            .height(3),
+        );
+
+        for idx in 0..3 {
+            assert_eq!(terminal.bg_at((2 + idx, 1 + idx)), Some(Colour::RED));
+            assert_eq!(terminal.bg_at((3 + idx, 1 + idx)), None);
+        }
+        assert_eq!(terminal.bg_at((5, 4)), None);
+
+        let output = str::from_utf8(&terminal.output_buffer).unwrap();
+        assert!(output.contains("\x1B[2;3H*"));
+        assert!(output.contains("\x1B[4;5H*"));
+        assert!(!output.contains("\x1B[5;6H"));
+    }
+
+    #[test]
+    fn draw_as_widget() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        world.execute(|app: &App| {
+            app.draw(
+                Canvas::new(|ctx| {
+                    assert_eq!((ctx.width(), ctx.height()), (4, 2));
+                    ctx.set(0, 0, '#', Some(Colour::GREEN), None);
+                })
+                .width(4)
+                .height(2),
+            )
+        });
+
+        let terminal: &Terminal = world.get_singleton();
+        let output = str::from_utf8(&terminal.output_buffer).unwrap();
+        assert!(output.ends_with("\x1B[1;1H#"));
+    }
+}
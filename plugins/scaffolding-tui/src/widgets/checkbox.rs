@@ -1,5 +1,5 @@
 use {
-    super::{Frame, HAlign, HorizontalOverflowStyle, Text, Widget},
+    super::{Frame, HorizontalOverflowStyle, Text, Widget},
     crate::{prelude::Terminal, Colour},
     scaffolding::{
         datatypes::uniq::UniqKey,
@@ -10,7 +10,6 @@ use {
 #[derive(Default)]
 struct CheckboxCache {
     checked: bool,
-    mouse_held: bool,
 }
 
 pub struct Checkbox<'a> {
@@ -61,13 +60,9 @@ impl<'a> Checkbox<'a> {
         let clicked = self.clicked(terminal);
         let cache: &mut CheckboxCache = uniqs.get(self.cache_key);
 
+        // Clicks only last one frame, so this toggles once per click
         if clicked {
-            if !cache.mouse_held {
-                cache.checked = !cache.checked;
-                cache.mouse_held = true;
-            }
-        } else {
-            cache.mouse_held = false;
+            cache.checked = !cache.checked;
         }
 
         if self.frame.width > 0 {
@@ -109,3 +104,38 @@ impl<'a> Widget<'a> for Checkbox<'a> {
         Self::draw.with_state(self).type_erase()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::App,
+        scaffolding::{uniq_key, world::World},
+    };
+
+    fn draw_checkbox(world: &mut World) -> bool {
+        world
+            .execute(|app: &App| app.draw(Checkbox::new("Check", uniq_key!())))
+            .checked
+    }
+
+    #[test]
+    fn click_toggles_once() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.mouse_pos = (3, 0);
+        terminal.clicked_mouse_buttons.insert(0);
+        assert!(draw_checkbox(&mut world));
+
+        // The click becomes a hold, which shouldn't toggle the checkbox again
+        world.get_singleton_mut::<Terminal>().update();
+        let terminal: &Terminal = world.get_singleton();
+        assert!(!terminal.mouse_clicked(0));
+        assert!(terminal.mouse_held(0));
+        assert!(draw_checkbox(&mut world));
+        world.get_singleton_mut::<Terminal>().update();
+        assert!(draw_checkbox(&mut world));
+    }
+}
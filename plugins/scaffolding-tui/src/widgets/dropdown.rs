@@ -0,0 +1,210 @@
+use {
+    super::{button::fit_label, Frame, Widget},
+    crate::{input::Key, prelude::Terminal, shapes::RawString, Colour},
+    scaffolding::{
+        datatypes::uniq::UniqKey,
+        world::{Executable, ExecutableWithState, Singleton, TypeErasedExecutable, Uniqs},
+    },
+};
+
+/// The draw layer the list of options is drawn on, so it covers widgets
+/// drawn below the dropdown. See [`Terminal::draw_on_layer`].
+const LIST_LAYER: u8 = 1;
+
+/// Dropdown state that persists across frames.
+#[derive(Default)]
+struct DropdownCache {
+    /// If the list of options is showing.
+    open: bool,
+    /// The option that's highlighted in the list.
+    highlighted: usize,
+}
+
+/// The result of drawing a [`Dropdown`]. This is recomputed every frame.
+#[derive(Debug)]
+pub struct DropdownOut {
+    /// The user picked a different option this frame.
+    pub changed: bool,
+    /// The list of options is showing.
+    pub open: bool,
+}
+
+/// Shows the selected option out of a list of options. Clicking it shows
+/// the rest of the options below it, on top of any other widgets; clicking
+/// one of them selects it, and clicking anywhere else closes the list. While
+/// it's open, the arrow keys move the highlight, enter selects the
+/// highlighted option, and escape closes the list.
+pub struct Dropdown<'a> {
+    options: &'a [&'a str],
+    selected: &'a mut usize,
+    cache_key: UniqKey,
+    frame: Frame,
+    text_colour: Option<Colour>,
+    background_colour: Option<Colour>,
+}
+impl<'a> Dropdown<'a> {
+    pub fn new(options: &'a [&'a str], selected: &'a mut usize, cache_key: UniqKey) -> Self {
+        Self {
+            options,
+            selected,
+            cache_key,
+            frame: Frame {
+                x: 0,
+                y: 0,
+                width: 12,
+                height: 1,
+            },
+            text_colour: None,
+            background_colour: None,
+        }
+    }
+
+    /// The area the list of options is drawn in, right below the dropdown.
+    fn list_frame(&self) -> Frame {
+        Frame {
+            x: self.frame.x,
+            y: self.frame.y + 1,
+            width: self.frame.width,
+            height: self.options.len() as u16,
+        }
+    }
+
+    fn draw(self, uniqs: &Uniqs, terminal: &Singleton<Terminal>) -> DropdownOut {
+        let clicked = self.clicked(terminal);
+        let list = self.list_frame();
+        let cache: &mut DropdownCache = uniqs.get(self.cache_key);
+        let previous = *self.selected;
+
+        if cache.open {
+            if list.contains(terminal.mouse_pos) {
+                cache.highlighted = (terminal.mouse_pos.1 - list.y) as usize;
+            }
+            if terminal.pressed_keys.contains(&Key::ArrowUp) {
+                cache.highlighted = cache.highlighted.saturating_sub(1);
+            }
+            if terminal.pressed_keys.contains(&Key::ArrowDown) {
+                cache.highlighted =
+                    (cache.highlighted + 1).min(self.options.len().saturating_sub(1));
+            }
+
+            if terminal.mouse_clicked(0) {
+                if list.contains(terminal.mouse_pos) {
+                    *self.selected = cache.highlighted;
+                }
+                cache.open = false;
+            } else if terminal.pressed_keys.contains(&Key::Text('\r'))
+                || terminal.pressed_keys.contains(&Key::Text('\n'))
+            {
+                *self.selected = cache.highlighted;
+                cache.open = false;
+            } else if terminal.pressed_keys.contains(&Key::Escape) {
+                cache.open = false;
+            }
+        } else if clicked && !self.options.is_empty() {
+            cache.open = true;
+            cache.highlighted = (*self.selected).min(self.options.len() - 1);
+        }
+
+        if self.frame.width > 0 {
+            terminal.set_bg(self.background_colour);
+            terminal.set_fg(self.text_colour);
+
+            // The selected option, then the indicator in the last column
+            let label = self.options.get(*self.selected).copied().unwrap_or("");
+            let label_space = self.frame.width as usize - 1;
+            let (label, label_width) = fit_label(label, label_space);
+            terminal.draw(RawString {
+                x: self.frame.x,
+                y: self.frame.y,
+                text: format!("{label}{}▾", " ".repeat(label_space - label_width)),
+            });
+
+            if cache.open {
+                for (idx, option) in self.options.iter().enumerate() {
+                    let (option, option_width) = fit_label(option, self.frame.width as usize);
+                    let padding = " ".repeat(self.frame.width as usize - option_width);
+                    let text = if idx == cache.highlighted {
+                        format!("\x1B[7m{option}{padding}\x1B[27m")
+                    } else {
+                        format!("{option}{padding}")
+                    };
+
+                    terminal.draw_on_layer(
+                        LIST_LAYER,
+                        RawString {
+                            x: list.x,
+                            y: list.y + idx as u16,
+                            text,
+                        },
+                    );
+                }
+            }
+        }
+
+        DropdownOut {
+            changed: *self.selected != previous,
+            open: cache.open,
+        }
+    }
+}
+impl_frame_methods!(Dropdown<'_>, x, y, width, clicked, hovered);
+impl_colour_methods!(Dropdown<'_>, text_colour, background_colour);
+
+impl<'a> Widget<'a> for Dropdown<'a> {
+    type Output = DropdownOut;
+
+    fn build_draw_fn(self) -> impl TypeErasedExecutable<'a, Output = Self::Output> {
+        Self::draw.with_state(self).type_erase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::App,
+        scaffolding::{uniq_key, world::World},
+    };
+
+    const OPTIONS: &[&str] = &["Red", "Green", "Blue"];
+
+    /// Click at `pos`, then draw the dropdown.
+    fn click(world: &mut World, selected: &mut usize, pos: (u16, u16)) -> DropdownOut {
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.update();
+        terminal.mouse_pos = pos;
+        terminal.clicked_mouse_buttons.insert(0);
+
+        world.execute(|app: &App| app.draw(Dropdown::new(OPTIONS, selected, uniq_key!()).x(2).y(1)))
+    }
+
+    #[test]
+    fn select_option() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        let mut selected = 0;
+
+        let out = click(&mut world, &mut selected, (3, 1));
+        assert!(out.open);
+        assert!(!out.changed);
+
+        // "Blue" is the third row of the list
+        let out = click(&mut world, &mut selected, (3, 4));
+        assert_eq!(selected, 2);
+        assert!(out.changed);
+        assert!(!out.open);
+    }
+
+    #[test]
+    fn click_outside_closes() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        let mut selected = 1;
+
+        assert!(click(&mut world, &mut selected, (3, 1)).open);
+        let out = click(&mut world, &mut selected, (30, 10));
+        assert!(!out.open);
+        assert!(!out.changed);
+        assert_eq!(selected, 1);
+    }
+}
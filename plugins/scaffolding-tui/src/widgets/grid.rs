@@ -0,0 +1,126 @@
+use super::Frame;
+
+/// Splits a frame into evenly-sized rows and columns, for laying out widgets
+/// in a grid. This doesn't draw anything; use [`Grid::cell`] or
+/// [`Grid::cell_span`] to get the frame for each widget.
+///
+/// When the frame doesn't divide evenly, the leftover rows and columns are
+/// spread out between the cells, so the cells always cover the whole frame
+/// without gaps or overlaps.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Grid {
+    rows: u16,
+    cols: u16,
+    frame: Frame,
+}
+impl Grid {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self {
+            rows,
+            cols,
+            frame: Frame {
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 10,
+            },
+        }
+    }
+
+    /// The frame of the cell at row `row` and column `col`.
+    pub fn cell(&self, row: u16, col: u16) -> Frame {
+        self.cell_span(row, col, 1, 1)
+    }
+    /// The frame of several merged cells, starting at row `row` and column
+    /// `col` and spanning `row_span` rows and `col_span` columns. Spans are
+    /// cut off at the edge of the grid.
+    pub fn cell_span(&self, row: u16, col: u16, row_span: u16, col_span: u16) -> Frame {
+        let (y, height) = span(self.frame.y, self.frame.height, self.rows, row, row_span);
+        let (x, width) = span(self.frame.x, self.frame.width, self.cols, col, col_span);
+
+        Frame {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+impl_frame_methods!(Grid, x, y, width, height, frame);
+
+/// The start and length of `count` tracks (rows or columns) starting at
+/// track `first`, when `length` is divided into `tracks` tracks starting at
+/// `start`.
+fn span(start: u16, length: u16, tracks: u16, first: u16, count: u16) -> (u16, u16) {
+    // Where each track starts. Rounding down here spreads the remainder
+    // across the tracks.
+    let edge = |track: u16| {
+        let track = track.min(tracks) as u32;
+        (length as u32 * track / (tracks as u32).max(1)) as u16
+    };
+
+    let from = edge(first);
+    let to = edge(first.saturating_add(count));
+    (start + from, to - from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts every cell in the grid together covers its frame exactly once.
+    fn assert_tiles(grid: Grid, rows: u16, cols: u16, frame: Frame) {
+        let mut covered = vec![0; frame.width as usize * frame.height as usize];
+        for row in 0..rows {
+            for col in 0..cols {
+                let cell = grid.cell(row, col);
+                for y in cell.y..cell.y + cell.height {
+                    for x in cell.x..cell.x + cell.width {
+                        assert!(frame.contains((x, y)));
+                        covered[(y - frame.y) as usize * frame.width as usize
+                            + (x - frame.x) as usize] += 1;
+                    }
+                }
+            }
+        }
+        assert!(covered.iter().all(|count| *count == 1));
+    }
+
+    #[test]
+    fn cells_tile_the_grid() {
+        let frame = Frame {
+            x: 3,
+            y: 2,
+            width: 10,
+            height: 7,
+        };
+        let grid = Grid::new(3, 4).frame(frame);
+        assert_tiles(grid, 3, 4, frame);
+
+        // 10 columns split into 4 is 2, 3, 2, 3
+        let widths: Vec<_> = (0..4).map(|col| grid.cell(0, col).width).collect();
+        assert_eq!(widths, [2, 3, 2, 3]);
+        // 7 rows split into 3 is 2, 2, 3
+        let heights: Vec<_> = (0..3).map(|row| grid.cell(row, 0).height).collect();
+        assert_eq!(heights, [2, 2, 3]);
+
+        let grid = Grid::new(2, 5).frame(frame);
+        assert_tiles(grid, 2, 5, frame);
+    }
+
+    #[test]
+    fn spans() {
+        let grid = Grid::new(3, 3).width(9).height(9);
+        assert_eq!(
+            grid.cell_span(0, 1, 2, 2),
+            Frame {
+                x: 3,
+                y: 0,
+                width: 6,
+                height: 6,
+            }
+        );
+        // Spans stop at the edge of the grid
+        assert_eq!(grid.cell_span(2, 2, 5, 5), grid.cell(2, 2));
+    }
+}
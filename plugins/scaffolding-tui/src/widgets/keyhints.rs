@@ -0,0 +1,198 @@
+use {
+    super::{button::fit_label, Frame, Widget},
+    crate::{prelude::Terminal, Colour},
+    scaffolding::world::{Executable, ExecutableWithState, Singleton, TypeErasedExecutable},
+    std::mem,
+    unicode_segmentation::UnicodeSegmentation,
+};
+
+/// How many spaces go between hints.
+const SPACING: usize = 2;
+
+/// A footer that lists keyboard shortcuts, like `q: quit  ↑↓: move`. Each
+/// hint is a key and a label for what it does; keys are drawn inverted so
+/// they stand out.
+///
+/// Hints are drawn along the bottom of the widget's frame. If they don't fit
+/// on one row, they wrap onto the rows above it; if they don't fit in the
+/// whole frame, the last hint that fits is cut off with `…`.
+pub struct KeyHints<'a> {
+    hints: &'a [(&'a str, &'a str)],
+    frame: Frame,
+    text_colour: Option<Colour>,
+    background_colour: Option<Colour>,
+}
+impl<'a> KeyHints<'a> {
+    pub fn new(hints: &'a [(&'a str, &'a str)]) -> Self {
+        Self {
+            hints,
+            frame: Frame {
+                x: 0,
+                y: 0,
+                width: 80,
+                height: 1,
+            },
+            text_colour: None,
+            background_colour: None,
+        }
+    }
+
+    pub fn hints(mut self, hints: &'a [(&'a str, &'a str)]) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    /// Lay the hints out into rows that fit in the frame, with the escapes to
+    /// style their keys.
+    fn rows(&self) -> Vec<String> {
+        let width = self.frame.width as usize;
+        let mut rows = Vec::new();
+        let mut row = String::new();
+        let mut row_width = 0;
+
+        for (key, label) in self.hints {
+            let hint_width = key.graphemes(true).count() + 2 + label.graphemes(true).count();
+            let mut gap = if row_width == 0 { 0 } else { SPACING };
+
+            if row_width + gap + hint_width > width {
+                if row_width > 0 && rows.len() + 1 < self.frame.height as usize {
+                    rows.push(mem::take(&mut row));
+                    row_width = 0;
+                    gap = 0;
+                }
+
+                if hint_width > width || row_width > 0 {
+                    // Out of room
+                    let room = width.saturating_sub(row_width + gap);
+                    if room > 0 {
+                        row.extend([" "; SPACING].into_iter().take(gap));
+                        push_hint(&mut row, key, label, room);
+                    }
+                    break;
+                }
+            }
+
+            row.extend([" "; SPACING].into_iter().take(gap));
+            push_hint(&mut row, key, label, hint_width);
+            row_width += gap + hint_width;
+        }
+
+        if !row.is_empty() {
+            rows.push(row);
+        }
+        rows
+    }
+
+    fn draw(self, terminal: &Singleton<Terminal>) {
+        if self.frame.width == 0 || self.frame.height == 0 {
+            return;
+        }
+
+        let rows = self.rows();
+        let top = self.frame.y + self.frame.height - rows.len() as u16;
+        terminal.set_fg(self.text_colour);
+        terminal.set_bg(self.background_colour);
+        for (idx, row) in rows.iter().enumerate() {
+            terminal.render_string(row, (self.frame.x, top + idx as u16));
+        }
+    }
+}
+impl_frame_methods!(KeyHints<'_>);
+impl_colour_methods!(KeyHints<'_>, text_colour, background_colour);
+
+/// Add a hint to `row`, cut off with `…` if it's wider than `width`.
+fn push_hint(row: &mut String, key: &str, label: &str, width: usize) {
+    if width == 0 {
+        return;
+    }
+
+    let key_width = key.graphemes(true).count();
+    row.push_str("\x1B[7m");
+    if width <= key_width {
+        // Not even the key fits
+        row.extend(key.graphemes(true).take(width - 1));
+        row.push('…');
+        row.push_str("\x1B[27m");
+        return;
+    }
+    row.push_str(key);
+    row.push_str("\x1B[27m");
+    row.push_str(&fit_label(&format!(": {label}"), width - key_width).0);
+}
+
+impl<'a> Widget<'a> for KeyHints<'a> {
+    type Output = ();
+
+    fn build_draw_fn(self) -> impl TypeErasedExecutable<'a, Output = Self::Output> {
+        Self::draw.with_state(self).type_erase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::App, scaffolding::world::World};
+
+    const HINTS: &[(&str, &str)] = &[("q", "quit"), ("↑↓", "move"), ("enter", "select")];
+
+    fn draw_hints(hints: KeyHints<'static>) -> String {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        world.execute(|app: &App| app.draw(hints));
+
+        str::from_utf8(&world.get_singleton::<Terminal>().output_buffer)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn hints_that_fit() {
+        assert_eq!(
+            draw_hints(KeyHints::new(HINTS).y(5).height(2)),
+            concat!(
+                "\x1B[39m\x1B[49m\x1B[7;1H",
+                "\x1B[7mq\x1B[27m: quit  \x1B[7m↑↓\x1B[27m: move  \x1B[7menter\x1B[27m: select",
+            )
+        );
+
+        // Hints wrap onto the rows above when there's room
+        assert_eq!(
+            draw_hints(KeyHints::new(HINTS).width(20).height(2)),
+            concat!(
+                "\x1B[39m\x1B[49m",
+                "\x1B[1;1H\x1B[7mq\x1B[27m: quit  \x1B[7m↑↓\x1B[27m: move",
+                "\x1B[2;1H\x1B[7menter\x1B[27m: select",
+            )
+        );
+    }
+
+    #[test]
+    fn hints_that_overflow() {
+        assert_eq!(
+            draw_hints(KeyHints::new(HINTS).width(28)),
+            concat!(
+                "\x1B[39m\x1B[49m\x1B[1;1H",
+                "\x1B[7mq\x1B[27m: quit  \x1B[7m↑↓\x1B[27m: move  \x1B[7menter\x1B[27m: s…",
+            )
+        );
+        assert_eq!(
+            draw_hints(KeyHints::new(HINTS).width(21)),
+            concat!(
+                "\x1B[39m\x1B[49m\x1B[1;1H",
+                "\x1B[7mq\x1B[27m: quit  \x1B[7m↑↓\x1B[27m: move  \x1B[7me…\x1B[27m",
+            )
+        );
+        assert_eq!(
+            draw_hints(KeyHints::new(HINTS).width(5)),
+            "\x1B[39m\x1B[49m\x1B[1;1H\x1B[7mq\x1B[27m: q…"
+        );
+
+        // There's no room left for the next hint, not even the space before it
+        assert_eq!(
+            draw_hints(KeyHints::new(HINTS).width(18)),
+            concat!(
+                "\x1B[39m\x1B[49m\x1B[1;1H",
+                "\x1B[7mq\x1B[27m: quit  \x1B[7m↑↓\x1B[27m: move",
+            )
+        );
+    }
+}
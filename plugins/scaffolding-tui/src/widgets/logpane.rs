@@ -0,0 +1,103 @@
+use {
+    super::{button::fit_label, Frame, Widget},
+    crate::{logging::LogBuffer, prelude::Terminal, shapes::RawString},
+    scaffolding::world::{Executable, ExecutableWithState, Singleton, TypeErasedExecutable},
+};
+
+/// Shows the newest messages in the [`LogBuffer`] singleton, with the newest
+/// at the bottom, coloured by their level. The pane always scrolls to the
+/// newest messages; lines that are too wide are cut off.
+pub struct LogPane {
+    frame: Frame,
+}
+impl Default for LogPane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl LogPane {
+    pub fn new() -> Self {
+        Self {
+            frame: Frame {
+                x: 0,
+                y: 0,
+                width: 40,
+                height: 10,
+            },
+        }
+    }
+
+    fn draw(self, terminal: &Singleton<Terminal>, logs: &Singleton<LogBuffer>) {
+        let records = logs.newest(self.frame.height as usize);
+        // Stick to the bottom of the pane when it isn't full yet
+        let top = self.frame.y + self.frame.height - records.len() as u16;
+
+        let mut line = String::new();
+        for (idx, record) in records.iter().enumerate() {
+            line.clear();
+            line.push_str(record.level.label());
+            line.push(' ');
+            line.extend(record.message.chars().map(|char| match char {
+                '\n' => ' ',
+                char => char,
+            }));
+
+            terminal.set_fg(Some(record.level.colour()));
+            terminal.draw(RawString {
+                x: self.frame.x,
+                y: top + idx as u16,
+                text: fit_label(&line, self.frame.width as usize).0,
+            });
+        }
+    }
+}
+impl_frame_methods!(LogPane);
+
+impl<'a> Widget<'a> for LogPane {
+    type Output = ();
+
+    fn build_draw_fn(self) -> impl TypeErasedExecutable<'a, Output = Self::Output> {
+        Self::draw.with_state(self).type_erase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{logging::LogLevel, App},
+        scaffolding::world::World,
+    };
+
+    #[test]
+    fn levels_are_coloured() {
+        let mut world = World::new();
+        world
+            .add_singleton(Terminal::headless())
+            .add_singleton(LogBuffer::with_capacity(4));
+
+        let logs: &LogBuffer = world.get_singleton();
+        logs.push(LogLevel::Error, "boom");
+        logs.push(LogLevel::Warn, "hmm");
+        logs.push(LogLevel::Info, "hi");
+        logs.push(LogLevel::Debug, "x = 1");
+        logs.push(LogLevel::Trace, "multi\nline");
+
+        world.execute(|app: &App| app.draw(LogPane::new().width(12).height(5)));
+        let output = str::from_utf8(&world.get_singleton::<Terminal>().output_buffer)
+            .unwrap()
+            .to_string();
+
+        // Only 4 messages fit in the buffer, so the error was dropped, and
+        // they're drawn at the bottom of the pane
+        assert_eq!(
+            output,
+            concat!(
+                "\x1B[38;2;255;255;0m\x1B[2;1HWARN  hmm",
+                "\x1B[38;2;255;255;255m\x1B[3;1HINFO  hi",
+                "\x1B[38;2;0;170;255m\x1B[4;1HDEBUG x = 1",
+                "\x1B[38;2;127;127;127m\x1B[5;1HTRACE multi…",
+            )
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use {
     super::{HAlign, VAlign, Widget},
     crate::{
+        ansi::{self, AnsiSegment, Sgr},
         shapes::{RawString, Shape},
         terminal::Terminal,
         widgets::Frame,
@@ -10,6 +11,8 @@ use {
         bitflags,
         world::{Executable, ExecutableWithState, Singleton, TypeErasedExecutable},
     },
+    std::fmt::Write,
+    unicode_segmentation::UnicodeSegmentation,
 };
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -59,6 +62,9 @@ pub struct Text<'a> {
     horizontal_anchor: HAlign,
     horizontal_overflow: HorizontalOverflowStyle,
     style: TextStyleFlags,
+    tab_width: usize,
+    line_numbers: Option<usize>,
+    highlighted_line: Option<usize>,
 }
 impl<'a> Text<'a> {
     pub fn new(text: &'a str) -> Self {
@@ -77,6 +83,9 @@ impl<'a> Text<'a> {
             horizontal_anchor: HAlign::Left,
             horizontal_overflow: HorizontalOverflowStyle::Wrap,
             style: TextStyleFlags::default(),
+            tab_width: 4,
+            line_numbers: None,
+            highlighted_line: None,
         }
     }
 
@@ -100,34 +109,36 @@ impl<'a> Text<'a> {
         self.style.merge(style.into());
         self
     }
+    /// How many columns apart tab stops are. Tabs are expanded to spaces,
+    /// up to the next tab stop.
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
+    }
+    /// Prefix each line with its line number, starting at `start`. Numbers
+    /// are right-aligned in a dim gutter that's as wide as the largest line
+    /// number. When lines wrap, only the first row gets a number.
+    pub fn line_numbers(mut self, start: usize) -> Self {
+        self.line_numbers = Some(start);
+        self
+    }
+    /// Draw a line in inverse video. `line` is the index of the line in the
+    /// text, starting at 0, regardless of [`Text::line_numbers`].
+    pub fn highlight_line(mut self, line: usize) -> Self {
+        self.highlighted_line = Some(line);
+        self
+    }
 
     fn draw(self, terminal: &Singleton<Terminal>) {
-        if self.style & TextStyle::Bold {
-            terminal.render_string_unpositioned("\x1B[1m");
-        }
-        if self.style & TextStyle::Dim {
-            terminal.render_string_unpositioned("\x1B[2m");
-        }
-        if self.style & TextStyle::Italic {
-            terminal.render_string_unpositioned("\x1B[3m");
-        }
-        if self.style & TextStyle::Underline {
-            terminal.render_string_unpositioned("\x1B[4m");
-        }
-        if self.style & TextStyle::Blinking {
-            terminal.render_string_unpositioned("\x1B[5m");
-        }
-        if self.style & TextStyle::Inverse {
-            terminal.render_string_unpositioned("\x1B[7m");
-        }
-        if self.style & TextStyle::Hidden {
-            terminal.render_string_unpositioned("\x1B[8m");
-        }
-        if self.style & TextStyle::Strikethrough {
-            terminal.render_string_unpositioned("\x1B[9m");
-        }
+        self.apply_style(terminal);
+
+        let multiline = self.horizontal_overflow == HorizontalOverflowStyle::Wrap
+            || self.line_numbers.is_some()
+            || self.highlighted_line.is_some()
+            || self.text.contains(['\n', '\t', '\x1B']);
 
         match self.horizontal_overflow {
+            _ if multiline => self.draw_lines(terminal),
             HorizontalOverflowStyle::Overflow => {
                 let horizontal_diff = self.frame.width.saturating_sub(self.text.len() as u16);
                 let x = if horizontal_diff > 0 {
@@ -223,16 +234,257 @@ impl<'a> Text<'a> {
                     });
                 }
             }
-            HorizontalOverflowStyle::Wrap => {
-                todo!()
-            }
+            HorizontalOverflowStyle::Wrap => unreachable!(),
         }
 
-        if self.style != TextStyleFlags::default() {
+        if self.style != TextStyleFlags::default()
+            || self.text_colour.is_some()
+            || self.background_colour.is_some()
+            || self.text.contains('\x1B')
+        {
             // Reset custom styles & colours
             terminal.render_string_unpositioned("\x1B[0m");
         }
     }
+
+    /// Sets the text's colours and styles.
+    fn apply_style(&self, terminal: &Terminal) {
+        if self.text_colour.is_some() {
+            terminal.set_fg(self.text_colour);
+        }
+        if self.background_colour.is_some() {
+            terminal.set_bg(self.background_colour);
+        }
+        if self.style & TextStyle::Bold {
+            terminal.render_string_unpositioned("\x1B[1m");
+        }
+        if self.style & TextStyle::Dim {
+            terminal.render_string_unpositioned("\x1B[2m");
+        }
+        if self.style & TextStyle::Italic {
+            terminal.render_string_unpositioned("\x1B[3m");
+        }
+        if self.style & TextStyle::Underline {
+            terminal.render_string_unpositioned("\x1B[4m");
+        }
+        if self.style & TextStyle::Blinking {
+            terminal.render_string_unpositioned("\x1B[5m");
+        }
+        if self.style & TextStyle::Inverse {
+            terminal.render_string_unpositioned("\x1B[7m");
+        }
+        if self.style & TextStyle::Hidden {
+            terminal.render_string_unpositioned("\x1B[8m");
+        }
+        if self.style & TextStyle::Strikethrough {
+            terminal.render_string_unpositioned("\x1B[9m");
+        }
+    }
+
+    /// Applies an escape embedded in the text. SGR escapes are translated for
+    /// the terminal's colour mode; a reset, or a reset to the default colour,
+    /// goes back to this widget's own colours and styles. Other escapes are
+    /// dropped, since they could move the cursor and break the layout.
+    fn apply_escape(&self, terminal: &Terminal, escape: &str, highlighted: bool) {
+        let Some(commands) = ansi::parse_sgr(escape) else {
+            return;
+        };
+
+        for command in commands {
+            match command {
+                Sgr::Reset => {
+                    terminal.render_string_unpositioned("\x1B[0m");
+                    self.apply_style(terminal);
+                    if highlighted {
+                        terminal.render_string_unpositioned("\x1B[7m");
+                    }
+                }
+                Sgr::Foreground(colour) => terminal.set_fg(colour.or(self.text_colour)),
+                Sgr::Background(colour) => terminal.set_bg(colour.or(self.background_colour)),
+                Sgr::Attribute(code) => {
+                    terminal.render_fmt_unpositioned(format_args!("\x1B[{code}m"))
+                }
+            }
+        }
+    }
+
+    /// Splits the text into the rows it takes up on screen, if it's drawn
+    /// `frame_width` columns wide. Each row is the index of the line it's
+    /// from, if it's the first row of that line, and its text. Also returns
+    /// the width of the line number gutter.
+    fn rows(&self, frame_width: u16) -> (Vec<(usize, bool, String)>, usize) {
+        let lines: Vec<String> = self
+            .text
+            .split('\n')
+            .map(|line| expand_tabs(line, self.tab_width))
+            .collect();
+
+        // The gutter is the widest line number, plus a space
+        let gutter_width = self
+            .line_numbers
+            .map(|start| (start + lines.len() - 1).to_string().len() + 1)
+            .unwrap_or(0);
+        let width = (frame_width as usize).saturating_sub(gutter_width);
+
+        // Each row on screen, as the index of the line it's from, if it's the
+        // first row of that line, and its text
+        let mut rows: Vec<(usize, bool, String)> = Vec::new();
+        for (idx, line) in lines.iter().enumerate() {
+            let cells = cells(line);
+            let line_width = ansi::visible_width(line);
+
+            match self.horizontal_overflow {
+                HorizontalOverflowStyle::Wrap if width > 0 && line_width > width => {
+                    let mut row = String::new();
+                    let mut row_width = 0;
+                    let mut first_row = true;
+                    for cell in cells {
+                        if !is_escape(cell) {
+                            if row_width == width {
+                                rows.push((idx, first_row, row));
+                                row = String::new();
+                                row_width = 0;
+                                first_row = false;
+                            }
+                            row_width += 1;
+                        }
+                        row.push_str(cell);
+                    }
+                    rows.push((idx, first_row, row));
+                }
+                HorizontalOverflowStyle::Clip | HorizontalOverflowStyle::Wrap => {
+                    rows.push((idx, true, clip(&cells, width)));
+                }
+                HorizontalOverflowStyle::ClipWithChar(char) if line_width > width => {
+                    let mut row = clip(&cells, width.saturating_sub(1));
+                    if width > 0 {
+                        row.push(char);
+                    }
+                    rows.push((idx, true, row));
+                }
+                HorizontalOverflowStyle::ClipWithChar(_) | HorizontalOverflowStyle::Overflow => {
+                    rows.push((idx, true, line.clone()));
+                }
+            }
+        }
+
+        (rows, gutter_width)
+    }
+
+    /// Draws text line-by-line. This handles newlines, tabs, wrapping, line
+    /// numbers, line highlighting and ANSI escapes in the text.
+    fn draw_lines(&self, terminal: &Terminal) {
+        let (mut rows, gutter_width) = self.rows(self.frame.width);
+        let width = (self.frame.width as usize).saturating_sub(gutter_width);
+
+        if self.vertical_overflow != VerticalOverflowStyle::Overflow {
+            rows.truncate(self.frame.height as usize);
+        }
+        let vertical_diff = self.frame.height.saturating_sub(rows.len() as u16);
+        let y = match self.vertical_anchor {
+            VAlign::Top => self.frame.y,
+            VAlign::Center => self.frame.y + vertical_diff / 2,
+            VAlign::Bottom => self.frame.y + vertical_diff,
+        };
+
+        let mut buffer = String::new();
+        for (row_idx, (line_idx, first_row, text)) in rows.iter().enumerate() {
+            let highlighted = self.highlighted_line == Some(*line_idx);
+            buffer.clear();
+
+            if let Some(start) = self.line_numbers {
+                // Dim the gutter, then reapply the text's own intensity
+                buffer.push_str("\x1B[2m");
+                if *first_row {
+                    write!(buffer, "{:>1$} ", start + line_idx, gutter_width - 1).unwrap();
+                } else {
+                    buffer.extend((0..gutter_width).map(|_| ' '));
+                }
+                buffer.push_str("\x1B[22m");
+                if self.style & TextStyle::Bold {
+                    buffer.push_str("\x1B[1m");
+                }
+                if self.style & TextStyle::Dim {
+                    buffer.push_str("\x1B[2m");
+                }
+            }
+
+            let horizontal_diff = width.saturating_sub(ansi::visible_width(text));
+            let padding = match self.horizontal_anchor {
+                HAlign::Left => 0,
+                HAlign::Center => horizontal_diff / 2,
+                HAlign::Right => horizontal_diff,
+            };
+            buffer.extend((0..padding).map(|_| ' '));
+
+            if highlighted {
+                buffer.push_str("\x1B[7m");
+            }
+            terminal.render_string(&buffer, (self.frame.x, y + row_idx as u16));
+
+            for segment in ansi::segments(text) {
+                match segment {
+                    AnsiSegment::Text(text) => terminal.render_string_unpositioned(text),
+                    AnsiSegment::Escape(escape) => self.apply_escape(terminal, escape, highlighted),
+                }
+            }
+            if highlighted {
+                terminal.render_string_unpositioned("\x1B[27m");
+            }
+        }
+    }
+}
+
+/// Splits a line into graphemes and escape sequences.
+fn cells(line: &str) -> Vec<&str> {
+    ansi::segments(line)
+        .flat_map(|segment| match segment {
+            AnsiSegment::Text(text) => text.graphemes(true).collect(),
+            AnsiSegment::Escape(escape) => vec![escape],
+        })
+        .collect()
+}
+fn is_escape(cell: &str) -> bool {
+    cell.starts_with('\x1B')
+}
+/// Joins the cells from [`cells`] back together, keeping at most `width`
+/// graphemes. Escapes are always kept, so styles still end where they should.
+fn clip(cells: &[&str], width: usize) -> String {
+    let mut row = String::new();
+    let mut row_width = 0;
+
+    for cell in cells {
+        if is_escape(cell) {
+            row.push_str(cell);
+        } else if row_width < width {
+            row.push_str(cell);
+            row_width += 1;
+        }
+    }
+
+    row
+}
+
+/// Replaces tabs in `line` with spaces, up to the next multiple of
+/// `tab_width` columns.
+fn expand_tabs(line: &str, tab_width: usize) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for grapheme in cells(line) {
+        if is_escape(grapheme) {
+            expanded.push_str(grapheme);
+        } else if grapheme == "\t" {
+            let spaces = tab_width - (column % tab_width.max(1));
+            expanded.extend((0..spaces).map(|_| ' '));
+            column += spaces;
+        } else {
+            expanded.push_str(grapheme);
+            column += 1;
+        }
+    }
+
+    expanded
 }
 impl<'a> Widget<'a> for Text<'a> {
     type Output = ();
@@ -240,6 +492,21 @@ impl<'a> Widget<'a> for Text<'a> {
     fn build_draw_fn(self) -> impl TypeErasedExecutable<'a, Output = Self::Output> {
         Self::draw.with_state(self).type_erase()
     }
+    /// The widest row and the number of rows, after wrapping the text to
+    /// `available` columns.
+    fn measure(&self, available: (u16, u16)) -> (u16, u16) {
+        let (rows, gutter_width) = self.rows(available.0);
+        let width = rows
+            .iter()
+            .map(|(_, _, text)| gutter_width + ansi::visible_width(text))
+            .max()
+            .unwrap_or(0);
+
+        (
+            (width as u16).min(available.0),
+            (rows.len() as u16).min(available.1),
+        )
+    }
 }
 impl Shape for Text<'_> {
     type Output = ();
@@ -250,3 +517,105 @@ impl Shape for Text<'_> {
 }
 impl_frame_methods!(Text<'_>);
 impl_colour_methods!(Text<'_>, text_colour, background_colour);
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::str};
+
+    fn draw(text: Text) -> String {
+        let terminal = Terminal::headless();
+        terminal.draw(text);
+        str::from_utf8(&terminal.output_buffer).unwrap().to_string()
+    }
+
+    #[test]
+    fn tabs() {
+        assert_eq!(expand_tabs("\tab\tc", 4), "    ab  c");
+        assert_eq!(expand_tabs("\tx", 2), "  x");
+    }
+
+    #[test]
+    fn gutter_width_scales() {
+        let text = "a\nb\nc";
+        let frame = |text: Text<'static>| text.width(10).height(3).vertical_anchor(VAlign::Top);
+
+        let output = draw(frame(Text::new(text).line_numbers(1)));
+        assert!(output.starts_with("\x1B[1;1H\x1B[2m1 \x1B[22ma"));
+
+        // 8, 9, 10: the gutter is two digits wide
+        let output = draw(frame(Text::new(text).line_numbers(8)));
+        assert!(output.starts_with("\x1B[1;1H\x1B[2m 8 \x1B[22ma"));
+        assert!(output.ends_with("\x1B[3;1H\x1B[2m10 \x1B[22mc"));
+    }
+
+    #[test]
+    fn measure() {
+        let text = Text::new("abcdefgh");
+        assert_eq!(text.measure((3, 10)), (3, 3));
+        assert_eq!(text.measure((20, 10)), (8, 1));
+        // Never bigger than the space that's available
+        assert_eq!(text.measure((3, 2)), (3, 2));
+
+        let text = Text::new("a\n\tbc").line_numbers(9);
+        assert_eq!(text.measure((20, 10)), (9, 2));
+    }
+
+    #[test]
+    fn wrapped_lines() {
+        let output = draw(
+            Text::new("abcdef\ng")
+                .line_numbers(1)
+                .width(5)
+                .height(3)
+                .vertical_anchor(VAlign::Top),
+        );
+
+        assert_eq!(
+            output,
+            concat!(
+                "\x1B[1;1H\x1B[2m1 \x1B[22mabc",
+                "\x1B[2;1H\x1B[2m  \x1B[22mdef",
+                "\x1B[3;1H\x1B[2m2 \x1B[22mg",
+            )
+        );
+    }
+
+    #[test]
+    fn highlighted_line() {
+        let output = draw(
+            Text::new("a\nb")
+                .highlight_line(1)
+                .width(5)
+                .height(2)
+                .vertical_anchor(VAlign::Top),
+        );
+
+        assert_eq!(output, "\x1B[1;1Ha\x1B[2;1H\x1B[7mb\x1B[27m");
+    }
+
+    #[test]
+    fn ansi_escapes() {
+        let text = "\x1B[31mred\x1B[0m ok";
+
+        // The escapes don't count towards the text's width
+        let output = draw(
+            Text::new(text)
+                .width(10)
+                .height(1)
+                .horizontal_anchor(HAlign::Right),
+        );
+        assert_eq!(
+            output,
+            "\x1B[1;1H    \x1B[38;2;170;0;0mred\x1B[0m ok\x1B[0m"
+        );
+
+        // ...or to clipping
+        let output = draw(
+            Text::new(text)
+                .width(4)
+                .height(1)
+                .horizontal_overflow(HorizontalOverflowStyle::Clip),
+        );
+        assert_eq!(output, "\x1B[1;1H\x1B[38;2;170;0;0mred\x1B[0m \x1B[0m");
+    }
+}
@@ -1,9 +1,10 @@
 use {
-    super::{HAlign, HorizontalOverflowStyle, Text, TextStyleFlags, VAlign},
+    super::{HorizontalOverflowStyle, Text, TextStyleFlags, VAlign},
     crate::{
-        input::Key,
-        prelude::Terminal,
+        clipboard::Clipboard,
+        input::{Key, ModifierKeys},
         shapes::*,
+        terminal::{CursorStyle, Terminal},
         widgets::{Frame, Widget},
         Colour,
     },
@@ -11,6 +12,7 @@ use {
         datatypes::uniq::UniqKey,
         world::{Executable, ExecutableWithState, Singleton, TypeErasedExecutable, Uniqs},
     },
+    std::hash::{DefaultHasher, Hash, Hasher},
     unicode_segmentation::UnicodeSegmentation,
 };
 
@@ -26,10 +28,29 @@ struct TextInputCache {
     /// user has scrolled over to a part of the string that's past the text
     /// input's length.
     render_offset: usize,
+    /// Where the selection started, if text is selected. The selection spans
+    /// from here to the cursor. This is in graphemes, not bytes.
+    selection_anchor: Option<usize>,
+    /// A hash of the buffer at the end of the last frame, so we can tell if
+    /// it changed. This is `None` before the first frame.
+    buffer_hash: Option<u64>,
+}
+impl TextInputCache {
+    /// The selected range of graphemes, if any text is selected.
+    fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.selection_anchor?;
+        (anchor != self.cursor_pos)
+            .then(|| (anchor.min(self.cursor_pos), anchor.max(self.cursor_pos)))
+    }
 }
 
+/// The result of drawing a [`TextInput`]. This is recomputed every frame.
 pub struct TextInputOut {
     pub focused: bool,
+    /// The text in the buffer changed since the last frame.
+    pub changed: bool,
+    /// Enter was pressed while the text input was focused.
+    pub submitted: bool,
 }
 
 pub struct TextInput<'a> {
@@ -78,7 +99,12 @@ impl<'a> TextInput<'a> {
         self
     }
 
-    fn draw(mut self, uniqs: &Uniqs, terminal: &Singleton<Terminal>) -> TextInputOut {
+    fn draw(
+        mut self,
+        uniqs: &Uniqs,
+        terminal: &Singleton<Terminal>,
+        clipboard: &Singleton<Clipboard>,
+    ) -> TextInputOut {
         let cache: &mut TextInputCache = uniqs.get(self.cache_key.take().unwrap());
 
         let text_offset = if self.border_style.is_some() { 1 } else { 0 };
@@ -86,13 +112,20 @@ impl<'a> TextInput<'a> {
 
         // On a mouse press, if the press was inside the text input, focus it
         // Otherwise, unfocus it
-        if terminal.clicked_mouse_buttons.contains(&0) {
+        if terminal.mouse_clicked(0) {
             cache.focused = self.frame.contains(terminal.mouse_pos);
         }
 
+        let mut submitted = false;
         if cache.focused {
             for key in terminal.pressed_keys.iter() {
-                self.handle_keypress(cache, *key);
+                if matches!(key, Key::Text('\r' | '\n')) {
+                    submitted = true;
+                    continue;
+                }
+
+                let modifiers = terminal.key_modifiers(*key);
+                self.handle_keypress(cache, *key, modifiers, clipboard, terminal);
             }
 
             let target_cursor_x = self.frame.x + (cache.cursor_pos - cache.render_offset) as u16;
@@ -100,30 +133,47 @@ impl<'a> TextInput<'a> {
                 target_cursor_x + text_offset,
                 self.frame.y + text_offset,
             )));
+            terminal.set_cursor_style(CursorStyle::BlinkingBar);
         }
 
         terminal.set_bg(self.background_colour);
 
-        let string = if !self.buffer.is_empty() {
+        let (string, selection) = if !self.buffer.is_empty() {
             terminal.set_fg(self.text_colour);
-            self.buffer as &'a str
+            (self.buffer as &'a str, cache.selection())
         } else {
             terminal.set_fg(self.placeholder_colour);
-            self.placeholder.unwrap_or_default()
+            (self.placeholder.unwrap_or_default(), None)
         };
 
-        let mut string_graphemes = string.grapheme_indices(true);
-        let string_render_start_idx = string_graphemes
-            .nth(cache.render_offset)
-            .map(|(idx, _val)| idx)
-            .unwrap_or(0);
-        let string_render_end_idx = string_graphemes
-            .nth(self.max_renderable_graphemes() as usize)
-            .map(|(idx, _val)| idx)
-            .unwrap_or(string.len());
+        let byte_idx = |grapheme: usize| {
+            string
+                .grapheme_indices(true)
+                .nth(grapheme)
+                .map(|(idx, _val)| idx)
+                .unwrap_or(string.len())
+        };
+        let render_end = cache.render_offset + self.max_renderable_graphemes() as usize;
+        let string_render_start_idx = byte_idx(cache.render_offset);
+        let string_render_end_idx = byte_idx(render_end);
+
+        // Draw the selected part of the string in inverse video
+        let visible = match selection {
+            Some((start, end)) => {
+                let start = byte_idx(start.clamp(cache.render_offset, render_end));
+                let end = byte_idx(end.clamp(cache.render_offset, render_end));
+                format!(
+                    "{}\x1B[7m{}\x1B[27m{}",
+                    &string[string_render_start_idx..start],
+                    &string[start..end],
+                    &string[end..string_render_end_idx]
+                )
+            }
+            None => string[string_render_start_idx..string_render_end_idx].to_string(),
+        };
 
         terminal.draw(
-            Text::new(&string[string_render_start_idx..string_render_end_idx])
+            Text::new(&visible)
                 .frame(self.frame)
                 .x(self.frame.x + text_offset)
                 .y(self.frame.y + text_offset)
@@ -144,14 +194,69 @@ impl<'a> TextInput<'a> {
             });
         }
 
+        let mut hasher = DefaultHasher::new();
+        self.buffer.hash(&mut hasher);
+        let hash = hasher.finish();
+        let changed = cache.buffer_hash.is_some_and(|previous| previous != hash);
+        cache.buffer_hash = Some(hash);
+
         TextInputOut {
             focused: cache.focused,
+            changed,
+            submitted,
         }
     }
 
-    fn handle_keypress(&mut self, cache: &mut TextInputCache, key: Key) {
+    fn handle_keypress(
+        &mut self,
+        cache: &mut TextInputCache,
+        key: Key,
+        modifiers: ModifierKeys,
+        clipboard: &Clipboard,
+        terminal: &Terminal,
+    ) {
+        // Shift + arrow keys extend the selection; any other arrow key
+        // movement clears it
+        if matches!(key, Key::ArrowLeft | Key::ArrowRight) {
+            if !modifiers.shift {
+                cache.selection_anchor = None;
+            } else if cache.selection_anchor.is_none() {
+                cache.selection_anchor = Some(cache.cursor_pos);
+            }
+        }
+
+        // Terminals send control + a letter as an ASCII control character
+        let control_char = match key {
+            Key::Text(char) if modifiers.control => Some(char.to_ascii_lowercase()),
+            Key::Text(char @ '\x01'..='\x1A') => Some((char as u8 - 1 + b'a') as char),
+            _ => None,
+        };
+        match control_char {
+            Some('c') => {
+                if let Some(selected) = self.selected_text(cache) {
+                    clipboard.copy(selected, terminal);
+                }
+                return;
+            }
+            Some('x') => {
+                if let Some(selected) = self.delete_selection(cache) {
+                    clipboard.copy(selected, terminal);
+                }
+                return;
+            }
+            Some('v') => {
+                self.delete_selection(cache);
+                self.insert_str(cache, &clipboard.get());
+                return;
+            }
+            Some(_) => return,
+            None => {}
+        }
+
         match key {
             Key::Text(char) => {
+                self.delete_selection(cache);
+
                 // Insert the character at the correct byte in our buffer,
                 // based on the cursor's location
                 let cursor_byte_idx = self
@@ -198,6 +303,7 @@ impl<'a> TextInput<'a> {
                 // Move the cursor forwards one
                 cache.cursor_pos = cache.cursor_pos.saturating_add(1);
             }
+            Key::Backspace | Key::Delete if self.delete_selection(cache).is_some() => {}
             Key::Backspace => {
                 if let Some(grapheme) = cache.cursor_pos.checked_sub(1) {
                     let idx = self.buffer.grapheme_indices(true).nth(grapheme).unwrap().0;
@@ -206,21 +312,58 @@ impl<'a> TextInput<'a> {
                     cache.render_offset = cache.render_offset.saturating_sub(1);
                 }
             }
-            Key::Delete => {
-                if cache.cursor_pos < self.buffer.graphemes(true).count() {
-                    let idx = self
-                        .buffer
-                        .grapheme_indices(true)
-                        .nth(cache.cursor_pos)
-                        .unwrap()
-                        .0;
-                    self.buffer.remove(idx);
-                    cache.render_offset = cache.render_offset.saturating_sub(1);
-                }
+            Key::Delete if cache.cursor_pos < self.buffer.graphemes(true).count() => {
+                let idx = self
+                    .buffer
+                    .grapheme_indices(true)
+                    .nth(cache.cursor_pos)
+                    .unwrap()
+                    .0;
+                self.buffer.remove(idx);
+                cache.render_offset = cache.render_offset.saturating_sub(1);
             }
             _ => {}
         }
     }
+    /// The byte index of the given grapheme in the buffer.
+    fn byte_idx(&self, grapheme: usize) -> usize {
+        self.buffer
+            .grapheme_indices(true)
+            .nth(grapheme)
+            .map(|(idx, _)| idx)
+            .unwrap_or(self.buffer.len())
+    }
+    fn selected_text(&self, cache: &TextInputCache) -> Option<String> {
+        let (start, end) = cache.selection()?;
+        Some(self.buffer[self.byte_idx(start)..self.byte_idx(end)].to_string())
+    }
+    /// Remove the selected text from the buffer and return it, moving the
+    /// cursor to where the selection was.
+    fn delete_selection(&mut self, cache: &mut TextInputCache) -> Option<String> {
+        let (start, end) = cache.selection()?;
+        let selected = self
+            .buffer
+            .drain(self.byte_idx(start)..self.byte_idx(end))
+            .collect();
+
+        cache.selection_anchor = None;
+        cache.cursor_pos = start;
+        cache.render_offset = cache.render_offset.min(start);
+
+        Some(selected)
+    }
+    /// Insert a string at the cursor, and move the cursor to the end of it.
+    fn insert_str(&mut self, cache: &mut TextInputCache, string: &str) {
+        let idx = self.byte_idx(cache.cursor_pos);
+        self.buffer.insert_str(idx, string);
+
+        cache.cursor_pos += string.graphemes(true).count();
+        // Scroll so the cursor's still visible
+        let max = self.max_renderable_graphemes() as usize;
+        if cache.cursor_pos - cache.render_offset > max {
+            cache.render_offset = cache.cursor_pos - max;
+        }
+    }
     fn max_renderable_graphemes(&self) -> u16 {
         if self.border_style.is_some() {
             self.frame.width.saturating_sub(2)
@@ -238,3 +381,114 @@ impl<'a> Widget<'a> for TextInput<'a> {
 }
 impl_frame_methods!(TextInput<'_>, x, y, width, hovered, clicked);
 impl_colour_methods!(TextInput<'_>, text_colour, border_colour, background_colour);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::App,
+        scaffolding::{uniq_key, world::World},
+    };
+
+    fn draw_input(world: &mut World, buffer: &mut String, keys: &[Key], shift: bool) {
+        for key in keys {
+            let terminal: &mut Terminal = world.get_singleton_mut();
+            terminal.pressed_keys.clear();
+            terminal.pressed_keys.insert(*key);
+            terminal.key_modifiers.insert(
+                *key,
+                ModifierKeys {
+                    shift,
+                    ..Default::default()
+                },
+            );
+            world.execute(|app: &App| app.draw(TextInput::new(buffer, uniq_key!()).width(20)));
+        }
+    }
+
+    /// Draws the text input for one frame with the given keys pressed.
+    fn draw_frame(world: &mut World, buffer: &mut String, keys: &[Key]) -> TextInputOut {
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.pressed_keys.clear();
+        terminal.pressed_keys.extend(keys);
+        world.execute(|app: &App| app.draw(TextInput::new(buffer, uniq_key!()).width(20)))
+    }
+
+    #[test]
+    fn change_and_submit() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        world.add_singleton(Clipboard::default());
+        let mut buffer = String::new();
+
+        // Focus the text input
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.mouse_pos = (1, 0);
+        terminal.clicked_mouse_buttons.insert(0);
+        let out = draw_frame(&mut world, &mut buffer, &[]);
+        assert!(out.focused && !out.changed && !out.submitted);
+        world
+            .get_singleton_mut::<Terminal>()
+            .clicked_mouse_buttons
+            .clear();
+
+        let out = draw_frame(&mut world, &mut buffer, &[Key::Text('a')]);
+        assert!(out.changed && !out.submitted);
+        assert_eq!(buffer, "a");
+
+        let out = draw_frame(&mut world, &mut buffer, &[]);
+        assert!(!out.changed && !out.submitted);
+
+        let out = draw_frame(&mut world, &mut buffer, &[Key::Text('\r')]);
+        assert!(out.submitted && !out.changed);
+        assert_eq!(buffer, "a");
+
+        // Changes from outside the text input count too
+        buffer.push('b');
+        let out = draw_frame(&mut world, &mut buffer, &[]);
+        assert!(out.changed && !out.submitted);
+    }
+
+    #[test]
+    fn cut_and_paste() {
+        let mut world = World::new();
+        world.add_singleton(Terminal::headless());
+        world.add_singleton(Clipboard::default());
+        let mut buffer = String::new();
+
+        // Focus the text input and type into it
+        let terminal: &mut Terminal = world.get_singleton_mut();
+        terminal.mouse_pos = (1, 1);
+        terminal.clicked_mouse_buttons.insert(0);
+        let text = "hello".chars().map(Key::Text).collect::<Vec<_>>();
+        draw_input(&mut world, &mut buffer, &text, false);
+        world
+            .get_singleton_mut::<Terminal>()
+            .clicked_mouse_buttons
+            .clear();
+        assert_eq!(buffer, "hello");
+
+        // Select "ell"
+        let left = [Key::ArrowLeft; 4];
+        draw_input(&mut world, &mut buffer, &left, false);
+        let right = [Key::ArrowRight; 3];
+        draw_input(&mut world, &mut buffer, &right, true);
+        assert!(
+            str::from_utf8(&world.get_singleton::<Terminal>().output_buffer)
+                .unwrap()
+                .contains("h\x1B[7mell\x1B[27mo")
+        );
+
+        // Cut it
+        draw_input(&mut world, &mut buffer, &[Key::Text('\x18')], false);
+        assert_eq!(buffer, "ho");
+        assert_eq!(world.get_singleton::<Clipboard>().get(), "ell");
+
+        // Paste it back, at the end of the string
+        draw_input(&mut world, &mut buffer, &[Key::ArrowRight], false);
+        draw_input(&mut world, &mut buffer, &[Key::Text('\x16')], false);
+        assert_eq!(buffer, "hoell");
+        draw_input(&mut world, &mut buffer, &[Key::Text('!')], false);
+        assert_eq!(buffer, "hoell!");
+    }
+}
@@ -1,15 +1,19 @@
 //! Custom data structures that simplify the borrow checker and other tasks.
 
+pub mod arenastring;
 pub mod arenavec;
 pub mod stackvec;
+pub mod sync_arenavec;
 pub mod typemap;
 pub mod uniq;
 pub mod warehouse;
 
 #[doc(inline)]
 pub use {
+    arenastring::ArenaString,
     arenavec::ArenaVec,
     stackvec::StackVec,
+    sync_arenavec::SyncArenaVec,
     typemap::TypeMap,
     uniq::{uniq_key, Uniq},
     warehouse::Warehouse,
@@ -0,0 +1,204 @@
+//! Module for [`ArenaString`].
+
+use {
+    super::{arenavec::Result, ArenaVec},
+    core::{
+        fmt::{self, Debug, Display, Write},
+        hash::{Hash, Hasher},
+        ops::Deref,
+        str::{self, Utf8Error},
+    },
+};
+
+/// A UTF-8 string backed by an [`ArenaVec<u8>`]. Like an arenavec, it never
+/// reallocates, so it can be appended to through a shared reference.
+///
+/// Every way of adding to an arenastring checks that the result is valid
+/// UTF-8, so [`ArenaString::as_str`] is always safe.
+#[derive(Default, PartialEq, Eq)]
+pub struct ArenaString {
+    bytes: ArenaVec<u8>,
+}
+impl ArenaString {
+    /// Creates an empty [`ArenaString`]. See [`ArenaVec::new`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates an empty [`ArenaString`] that reserves `reserved_memory` bytes.
+    /// See [`ArenaVec::with_reserved_memory`].
+    pub fn with_reserved_memory(reserved_memory: usize) -> Self {
+        Self {
+            bytes: ArenaVec::with_reserved_memory(reserved_memory),
+        }
+    }
+    /// Converts an arenavec of bytes to an [`ArenaString`], if the bytes are
+    /// valid UTF-8.
+    pub fn from_utf8(bytes: ArenaVec<u8>) -> core::result::Result<Self, Utf8Error> {
+        str::from_utf8(&bytes)?;
+        Ok(Self { bytes })
+    }
+
+    /// Appends a string, panicking if the arenastring is out of reserved
+    /// memory.
+    pub fn push_str(&self, string: &str) {
+        self.bytes.extend_from_slice(string.as_bytes());
+    }
+    /// Appends a string, or returns an error if the arenastring is out of
+    /// reserved memory.
+    pub fn try_push_str(&self, string: &str) -> Result<()> {
+        self.bytes.try_extend_from_slice(string.as_bytes())
+    }
+    /// Appends a character.
+    pub fn push(&self, char: char) {
+        self.push_str(char.encode_utf8(&mut [0; 4]));
+    }
+    /// Appends raw bytes, if they're valid UTF-8. Nothing is appended if
+    /// they aren't.
+    pub fn push_bytes(&self, bytes: &[u8]) -> core::result::Result<(), Utf8Error> {
+        self.push_str(str::from_utf8(bytes)?);
+        Ok(())
+    }
+
+    /// Removes and returns the last character.
+    pub fn pop(&mut self) -> Option<char> {
+        let char = self.as_str().chars().next_back()?;
+        self.bytes.truncate(self.len() - char.len_utf8());
+        Some(char)
+    }
+    /// Shortens the string to `new_len` bytes. Does nothing if the string is
+    /// already shorter than that.
+    ///
+    /// # Panics
+    /// Panics if `new_len` isn't on a character boundary.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.len() {
+            assert!(
+                self.as_str().is_char_boundary(new_len),
+                "ArenaString::truncate: new_len isn't on a char boundary"
+            );
+            self.bytes.truncate(new_len);
+        }
+    }
+    /// Removes everything in the string. This doesn't decommit any memory.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    /// The length of the string, in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn as_str(&self) -> &str {
+        // SAFETY: Every method that adds bytes checks they're valid UTF-8
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+    pub fn into_bytes(self) -> ArenaVec<u8> {
+        self.bytes
+    }
+}
+
+impl Clone for ArenaString {
+    fn clone(&self) -> Self {
+        let string = Self::with_reserved_memory(self.bytes.reserved_memory());
+        string.push_str(self);
+        string
+    }
+}
+impl Hash for ArenaString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+impl Deref for ArenaString {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+impl AsRef<str> for ArenaString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+impl Display for ArenaString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+impl Debug for ArenaString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+impl Write for &ArenaString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.try_push_str(s).map_err(|_| fmt::Error)
+    }
+}
+impl Write for ArenaString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        <&Self>::write_str(&mut &*self, s)
+    }
+}
+impl From<&str> for ArenaString {
+    fn from(value: &str) -> Self {
+        let string = Self::new();
+        string.push_str(value);
+        string
+    }
+}
+impl PartialEq<str> for ArenaString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl PartialEq<&str> for ArenaString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::ArenaString, core::fmt::Write};
+
+    #[test]
+    fn push_and_read() {
+        let mut string = ArenaString::new();
+        string.push_str("héllo");
+        string.push(',');
+        string.push('🦀');
+        write!(string, " {}", 42).unwrap();
+
+        assert_eq!(string.as_str(), "héllo,🦀 42");
+        assert_eq!(string.len(), "héllo,🦀 42".len());
+        assert_eq!(format!("{string}"), "héllo,🦀 42");
+
+        assert_eq!(string.pop(), Some('2'));
+        string.truncate(3);
+        assert_eq!(string, "hé");
+
+        string.clear();
+        assert!(string.is_empty());
+        assert_eq!(string, "");
+        string.push_str("again");
+        assert_eq!(string, "again");
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let string = ArenaString::from("ok");
+        assert!(string.push_bytes(&[0xFF, b'a']).is_err());
+        assert!(string.push_bytes("é".as_bytes()).is_ok());
+        assert_eq!(string, "oké");
+        assert_eq!(string.clone(), "oké");
+    }
+}
@@ -3,13 +3,15 @@
 use {
     crate::{
         os::{Os, OsTrait},
-        utils::MemoryAmount,
+        utils::{self, MemoryAmount},
     },
     core::{
         borrow::{Borrow, BorrowMut},
         cell::Cell,
         cmp::Ordering,
-        fmt::Write,
+        fmt::{self, Debug, Write},
+        hash::{Hash, Hasher},
+        marker::PhantomData,
         mem::{self, MaybeUninit},
         ops::{Bound, Deref, DerefMut, Index, IndexMut, RangeBounds},
         ptr::{self, NonNull},
@@ -18,7 +20,7 @@ use {
 };
 
 /// Represents possible errors that vec functions can return
-#[derive(Debug)]
+#[derive(PartialEq, Eq, Debug)]
 pub enum Error {
     OutOfMemoryAddresses,
     IndexOutOfBounds,
@@ -64,11 +66,20 @@ pub type Result<T> = core::result::Result<T, Error>;
 /// needs to grow, it won't be able to and will have to panic. However, reserving a large amount
 /// of the virtual address space has little (if any) overhead, so it's fairly easy to just reserve
 /// an unreasonable amount of addresses and then leave it alone.
+///
+/// Note that arenavecs aren't [`Sync`], so only one thread can push to an arenavec at a time. Use
+/// a [`SyncArenaVec`] to push from several threads at once.
+///
+/// [`SyncArenaVec`]: super::SyncArenaVec
+///
+/// Zero-sized types never need memory, so arenavecs storing them never commit any and can grow
+/// until their length overflows.
 pub struct ArenaVec<T> {
     /// The total amount of memory the arenavec reserved when it was created.
     reserved_memory: usize,
-    /// The amount of memory the arenavec has allocated and can be used to store data.
-    capacity: Cell<usize>,
+    /// The amount of memory, in bytes, the arenavec has allocated and can be used to
+    /// store data.
+    committed_memory: Cell<usize>,
     /// The number of entries in the arenavec.
     len: Cell<usize>,
     /// A pointer to the base of the memory buffer storing all the arenavec's
@@ -76,6 +87,9 @@ pub struct ArenaVec<T> {
     // TODO: Swap this pointer out for a `NonNull` once `nonnull_convenience`
     // is stabilised
     buffer: *mut T,
+    /// If the arenavec's memory is backed by huge pages. See
+    /// [`ArenaVec::with_reserved_memory_hugepages`].
+    huge_pages: bool,
 }
 impl<T> ArenaVec<T> {
     /// This is the default amount of memory an arenavec will reserve when it's
@@ -91,7 +105,20 @@ impl<T> ArenaVec<T> {
     /// Thus 10GiB seems like a buffer size that will probably never be filled
     /// and also doesn't take a large portion of the virtual address space
     /// (thousands of arenavecs could still be created without filling it).
-    pub const DEFAULT_RESERVED_MEMORY: usize = MemoryAmount::Gibibytes(10).into_bytes();
+    ///
+    /// If the OS can't reserve memory without allocating it (see
+    /// [`OsTrait::LAZY_RESERVE`]), this is only 1MiB instead, since all of it
+    /// gets allocated up front. That's also the most these arenavecs can
+    /// hold, since they can't move their buffer to grow it.
+    pub const DEFAULT_RESERVED_MEMORY: usize = if Os::LAZY_RESERVE {
+        MemoryAmount::Gibibytes(10).into_bytes()
+    } else {
+        MemoryAmount::Mebibytes(1).into_bytes()
+    };
+
+    /// If `T` is a zero-sized type. Arenavecs storing ZSTs never commit any
+    /// memory; their elements all live at the (page-aligned) buffer pointer.
+    const IS_ZST: bool = mem::size_of::<T>() == 0;
 
     /// Creates a default [`ArenaVec`]. This will reserve virtual addresses, but does not allocate.
     pub fn new() -> Self {
@@ -103,35 +130,87 @@ impl<T> ArenaVec<T> {
         Self::with_reserved_memory_and_capacity(reserved_memory, 0)
     }
 
-    /// Create an [`ArenaVec`] with the specified allocation size. This will either reserve the default amount of reserved
-    /// memory (see [`ArenaVec::DEFAULT_RESERVED_MEMORY`]) or the allocation size, whichever is larger.
+    /// Create an [`ArenaVec`] with room for `capacity` elements. This will either reserve the default amount of reserved
+    /// memory (see [`ArenaVec::DEFAULT_RESERVED_MEMORY`]) or enough memory for `capacity` elements, whichever is larger.
     pub fn with_capacity(capacity: usize) -> Self {
-        if capacity > Self::DEFAULT_RESERVED_MEMORY {
-            Self::with_reserved_memory_and_capacity(capacity, capacity)
-        } else {
-            Self::with_reserved_memory_and_capacity(Self::DEFAULT_RESERVED_MEMORY, capacity)
-        }
+        let capacity_bytes = capacity
+            .checked_mul(mem::size_of::<T>())
+            .expect("ArenaVec capacity overflowed the address space");
+
+        Self::with_reserved_memory_and_capacity(
+            Self::DEFAULT_RESERVED_MEMORY.max(capacity_bytes),
+            capacity,
+        )
     }
 
     /// Create an [`ArenaVec`] with the specified amount of reserved virtual addresses and allocate enough memory to store
     /// `capacity` elements.
     pub fn with_reserved_memory_and_capacity(reserved_memory: usize, capacity: usize) -> Self {
-        if reserved_memory < capacity {
+        let capacity_bytes = capacity
+            .checked_mul(mem::size_of::<T>())
+            .expect("ArenaVec capacity overflowed the address space");
+        if reserved_memory < capacity_bytes {
             panic!("Attempted to create an ArenaVec with less reserved memory than allocated capacity.");
         }
 
+        let committed_memory = Os::page_align(capacity_bytes);
         let reserved_memory = Os::page_align(reserved_memory);
         let buffer = Os::reserve(reserved_memory).unwrap();
 
         unsafe {
-            Os::commit(buffer, capacity);
+            Os::commit(buffer, committed_memory);
         }
 
         Self {
             reserved_memory,
-            capacity: Cell::new(capacity),
+            committed_memory: Cell::new(committed_memory),
             len: Cell::new(0),
             buffer: buffer.as_ptr().cast(),
+            huge_pages: false,
+        }
+    }
+
+    /// Create an [`ArenaVec`] with the specified amount of reserved virtual
+    /// addresses, backed by huge pages if possible. This is meant for very
+    /// large arenavecs, where normal pages cause TLB pressure.
+    ///
+    /// If the OS doesn't support huge pages, or doesn't have enough of them
+    /// available, this falls back to normal pages; the arenavec works the
+    /// same either way. See [`ArenaVec::uses_huge_pages`] to check which one
+    /// was used.
+    ///
+    /// Note that some OSes allocate huge pages up front, when they're
+    /// reserved, instead of when they're committed.
+    pub fn with_reserved_memory_hugepages(reserved_memory: usize) -> Self {
+        let Some(huge_page_size) = Os::huge_page_size() else {
+            return Self::with_reserved_memory(reserved_memory);
+        };
+        let reserved_memory = utils::align(reserved_memory, huge_page_size);
+
+        match Os::reserve_huge(reserved_memory) {
+            Some(buffer) => Self {
+                reserved_memory,
+                committed_memory: Cell::new(0),
+                len: Cell::new(0),
+                buffer: buffer.as_ptr().cast(),
+                huge_pages: true,
+            },
+            None => Self::with_reserved_memory(reserved_memory),
+        }
+    }
+
+    /// If this arenavec's memory is backed by huge pages. See
+    /// [`ArenaVec::with_reserved_memory_hugepages`].
+    pub fn uses_huge_pages(&self) -> bool {
+        self.huge_pages
+    }
+
+    /// Align an amount of memory to the size of the pages backing this
+    /// arenavec.
+    fn page_align(&self, amount: usize) -> usize {
+        match Os::huge_page_size() {
+            Some(huge_page_size) if self.huge_pages => utils::align(amount, huge_page_size),
+            _ => Os::page_align(amount),
         }
     }
 
@@ -152,31 +231,9 @@ impl<T> ArenaVec<T> {
     // convience function to allocate memory if necessary
     // This function will allocate memory if necessary to ensure that self.capacity is at least equal to the capaciy argument
     fn ensure_capacity(&self, capacity: usize) {
-        let current_capacity = self.capacity();
-        if capacity > current_capacity {
-            let used_memory = mem::size_of::<T>() * self.len();
-
-            // Double in size if possible, else reserve all memory
-            let growth_amount = if used_memory == 0 {
-                mem::size_of::<T>()
-            } else if used_memory * 2 < self.reserved_memory {
-                used_memory
-            } else {
-                self.reserved_memory - used_memory
-            };
-            let growth_amount = Os::page_align(growth_amount);
-
-            if used_memory + growth_amount > self.reserved_memory {
-                // rip bozo
-                panic!("ArenaVec needed to grow, but ran out of reserved memory");
-            }
-
-            let region_to_allocate =
-                unsafe { NonNull::new_unchecked(self.buffer.byte_add(current_capacity)) };
-            unsafe { Os::commit(region_to_allocate.cast(), growth_amount) };
-
-            self.capacity.set(current_capacity + growth_amount);
-            debug_assert!(self.capacity() >= capacity);
+        if self.try_ensure_capacity(capacity).is_err() {
+            // rip bozo
+            panic!("ArenaVec needed to grow, but ran out of reserved memory");
         }
     }
 
@@ -184,30 +241,36 @@ impl<T> ArenaVec<T> {
     // This function will allocate memory if necessary to ensure that self.capacity is at least equal to the capaciy argument
     // If this function can't allocate more room, it will return an error instead of panicking
     fn try_ensure_capacity(&self, capacity: usize) -> Result<()> {
-        let current_capacity = self.capacity();
-        if capacity > current_capacity {
-            let used_memory = mem::size_of::<T>() * self.len.get();
-
-            // Double in size if possible, else reserve all memory
-            let growth_amount = if used_memory == 0 {
-                mem::size_of::<T>()
-            } else if used_memory * 2 < self.reserved_memory {
-                used_memory
-            } else {
-                self.reserved_memory - used_memory
-            };
-            let growth_amount = Os::page_align(growth_amount);
-
-            if used_memory + growth_amount > self.reserved_memory {
-                // rip bozo
+        if capacity > self.capacity() {
+            // `capacity` is in elements, but everything below is in bytes
+            let committed_memory = self.capacity_bytes();
+            let required_memory = capacity
+                .checked_mul(mem::size_of::<T>())
+                .ok_or(Error::OutOfMemoryAddresses)?;
+
+            if required_memory > self.reserved_memory {
                 return Err(Error::OutOfMemoryAddresses);
             }
 
+            // Double in size if possible, else commit as much as we need, up
+            // to all of the reserved memory
+            let new_committed_memory = self
+                .page_align(required_memory.max(committed_memory * 2))
+                .min(self.reserved_memory);
+
             let region_to_allocate =
-                unsafe { NonNull::new_unchecked(self.buffer.byte_add(current_capacity)) };
-            unsafe { Os::commit(region_to_allocate.cast(), growth_amount) };
+                unsafe { NonNull::new_unchecked(self.buffer.byte_add(committed_memory)) };
+            let amount = new_committed_memory - committed_memory;
+            unsafe {
+                if self.huge_pages {
+                    Os::commit_huge(region_to_allocate.cast(), amount)
+                } else {
+                    Os::commit(region_to_allocate.cast(), amount)
+                }
+            };
 
-            self.capacity.set(current_capacity + growth_amount);
+            self.committed_memory.set(new_committed_memory);
+            debug_assert!(self.capacity() >= capacity);
         }
         Ok(())
     }
@@ -220,6 +283,14 @@ impl<T> ArenaVec<T> {
         self.try_ensure_capacity(self.len() + additional)
     }
 
+    /// Like [`ArenaVec::reserve`], but only needs a shared reference, just
+    /// like [`ArenaVec::push`]. This lets code that only has `&self` commit
+    /// the memory for a batch of pushes all at once, instead of a page at a
+    /// time.
+    pub fn reserve_shared(&self, additional: usize) {
+        self.ensure_capacity(self.len() + additional);
+    }
+
     pub fn push(&self, val: T) {
         let len = self.len();
         self.ensure_capacity(len + 1);
@@ -237,8 +308,8 @@ impl<T> ArenaVec<T> {
         if self.is_empty() {
             None
         } else {
-            let len = self.len();
-            self.len.set(len - 1);
+            let len = self.len() - 1;
+            self.len.set(len);
             Some(unsafe { self.as_mut_ptr().add(len).read() })
         }
     }
@@ -299,6 +370,17 @@ impl<T> ArenaVec<T> {
             None
         }
     }
+    /// Like [`ArenaVec::get`], but returns [`Error::IndexOutOfBounds`] instead
+    /// of [`None`], so it can be used with `?` alongside methods like
+    /// [`ArenaVec::try_push`].
+    pub fn try_get(&self, idx: usize) -> Result<&T> {
+        self.get(idx).ok_or(Error::IndexOutOfBounds)
+    }
+    /// Like [`ArenaVec::get_mut`], but returns [`Error::IndexOutOfBounds`]
+    /// instead of [`None`].
+    pub fn try_get_mut(&mut self, idx: usize) -> Result<&mut T> {
+        self.get_mut(idx).ok_or(Error::IndexOutOfBounds)
+    }
 
     /// Removes an item from the vector, moving all items after it down a slot.
     pub fn remove(&mut self, idx: usize) -> Option<T> {
@@ -319,36 +401,21 @@ impl<T> ArenaVec<T> {
         }
     }
 
+    /// Keeps only the items `f` returns true for, dropping the rest. This
+    /// only moves each item once, so it's O(n).
     pub fn retain<F>(&mut self, mut f: F)
     where
         F: FnMut(&T) -> bool,
     {
-        // there's definitely a faster way to do this lol
-        // sorry
-        let mut idx = 0;
-        while idx < self.len.get() {
-            if f(&self[idx]) {
-                self.remove(idx);
-            } else {
-                idx += 1;
-            }
-        }
+        self.retain_mut(|val| f(val));
     }
 
+    /// Like [`ArenaVec::retain`], but `f` can modify the items.
     pub fn retain_mut<F>(&mut self, mut f: F)
     where
         F: FnMut(&mut T) -> bool,
     {
-        // there's definitely a faster way to do this lol
-        // sorry
-        let mut idx = 0;
-        while idx < self.len.get() {
-            if f(&mut self[idx]) {
-                self.remove(idx);
-            } else {
-                idx += 1;
-            }
-        }
+        self.extract_if(|val| !f(val)).for_each(drop);
     }
 
     /// Returns an iterator over all the items in this arenavec. This iterator will set the arenavec's
@@ -363,6 +430,73 @@ impl<T> ArenaVec<T> {
         }
     }
 
+    /// Returns an iterator that removes and yields every item `pred` returns
+    /// true for, moving the other items down to fill the gaps. This only
+    /// moves each item once, so it's O(n).
+    ///
+    /// If the iterator is dropped before it finishes, the items it hasn't
+    /// checked yet are kept.
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, F>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let len = self.len();
+        // If the iterator is leaked, the items it moved out of would still be
+        // in the vec, so we leak them all instead
+        self.len.set(0);
+
+        ExtractIf {
+            arena_vec: self,
+            pred,
+            idx: 0,
+            extracted: 0,
+            len,
+        }
+    }
+
+    /// Removes the items in `range` and replaces them with the items from
+    /// `replace_with`, like [`Vec::splice`]. The returned iterator yields the
+    /// removed items; the replacement happens when it's dropped.
+    ///
+    /// If `replace_with` has more items than `range`, the items after the
+    /// range are moved up to make room for them, growing the vec if needed.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds, or its start is after its end.
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Splice<'_, T, I::IntoIter>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(i) => *i,
+            Bound::Excluded(i) => *i + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(i) => *i + 1,
+            Bound::Excluded(i) => *i,
+            Bound::Unbounded => len,
+        };
+        assert!(start <= end, "Splice range starts after it ends");
+        assert!(end <= len, "Index out of bounds");
+
+        // If the iterator is leaked, the items after the range are leaked
+        // too, instead of being left in the vec after items that were moved
+        // out of
+        self.len.set(start);
+
+        Splice {
+            arena_vec: self,
+            replace_with: replace_with.into_iter(),
+            idx: start,
+            start,
+            end,
+            len,
+        }
+    }
+
     pub fn clear(&mut self) {
         self.len.set(0);
     }
@@ -384,9 +518,22 @@ impl<T> ArenaVec<T> {
         self.len() == 0
     }
 
+    /// The number of elements the [`ArenaVec`] can hold before it has to
+    /// allocate more memory. Like [`Vec::capacity`], this is measured in
+    /// elements, not bytes; see [`ArenaVec::capacity_bytes`] for the latter.
     #[inline(always)]
     pub fn capacity(&self) -> usize {
-        self.capacity.get()
+        if Self::IS_ZST {
+            usize::MAX
+        } else {
+            self.committed_memory.get() / mem::size_of::<T>()
+        }
+    }
+    /// The amount of memory, in bytes, the [`ArenaVec`] has allocated. This is
+    /// always a multiple of the OS' page size.
+    #[inline(always)]
+    pub fn capacity_bytes(&self) -> usize {
+        self.committed_memory.get()
     }
 
     pub fn reserved_memory(&self) -> usize {
@@ -395,7 +542,11 @@ impl<T> ArenaVec<T> {
 
     /// This function returns the count of Ts that can be pushed before the vector runs out of memory
     pub fn remaining_space(&self) -> usize {
-        self.reserved_memory().div_ceil(mem::size_of::<T>()) - self.len()
+        if Self::IS_ZST {
+            usize::MAX
+        } else {
+            self.reserved_memory().div_ceil(mem::size_of::<T>()) - self.len()
+        }
     }
 
     pub fn as_ptr(&self) -> *const T {
@@ -405,19 +556,47 @@ impl<T> ArenaVec<T> {
         self.buffer
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             arena_vec: self,
             idx: 0,
         }
     }
+    /// Returns an iterator that gives mutable references to every item.
+    ///
+    /// The iterator borrows the arenavec exclusively, even though pushing
+    /// only needs a shared borrow, so the borrow checker won't let anything
+    /// push to the arenavec while the iterator is alive:
+    ///
+    /// ```compile_fail,E0502
+    /// # use scaffolding::datatypes::ArenaVec;
+    /// let mut vec = ArenaVec::from([1, 2, 3]);
+    /// let mut iter = vec.iter_mut();
+    /// vec.push(4);
+    /// iter.next();
+    /// ```
     pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
-            arena_vec: self,
+            ptr: self.as_mut_ptr(),
+            len: self.len(),
+            _arena_vec: PhantomData,
             idx: 0,
         }
     }
 
+    /// The index of the first item that matches `predicate`, if any.
+    pub fn position<P: FnMut(&T) -> bool>(&self, predicate: P) -> Option<usize> {
+        self.as_slice().iter().position(predicate)
+    }
+    /// The index of the last item that matches `predicate`, if any.
+    pub fn rposition<P: FnMut(&T) -> bool>(&self, predicate: P) -> Option<usize> {
+        self.as_slice().iter().rposition(predicate)
+    }
+    /// The first item that matches `predicate`, if any.
+    pub fn find<P: FnMut(&T) -> bool>(&self, mut predicate: P) -> Option<&T> {
+        self.as_slice().iter().find(|val| predicate(val))
+    }
+
     pub fn as_slice(&self) -> &[T] {
         unsafe { slice::from_raw_parts(self.buffer, self.len()) }
     }
@@ -425,22 +604,130 @@ impl<T> ArenaVec<T> {
         unsafe { slice::from_raw_parts_mut(self.buffer, self.len()) }
     }
 
-    // This isn't using the trait because it can fail
+    /// Divides the [`ArenaVec`]'s items into two slices at `mid`. The first
+    /// slice contains the items in `[0, mid)`, and the second contains the
+    /// items in `[mid, len)`.
+    ///
+    /// # Panics
+    /// Panics if `mid > len`.
+    pub fn split_at(&self, mid: usize) -> (&[T], &[T]) {
+        self.as_slice().split_at(mid)
+    }
+    /// The same as [`ArenaVec::split_at`], but returns mutable slices.
+    pub fn split_at_mut(&mut self, mid: usize) -> (&mut [T], &mut [T]) {
+        self.as_mut_slice().split_at_mut(mid)
+    }
+
+    /// Iterates over the [`ArenaVec`]'s items in slices of `chunk_size`
+    /// items. If the length isn't a multiple of `chunk_size`, the last chunk
+    /// will be shorter.
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is 0.
+    pub fn chunks(&self, chunk_size: usize) -> slice::Chunks<'_, T> {
+        self.as_slice().chunks(chunk_size)
+    }
+    /// The same as [`ArenaVec::chunks`], but returns mutable slices.
+    pub fn chunks_mut(&mut self, chunk_size: usize) -> slice::ChunksMut<'_, T> {
+        self.as_mut_slice().chunks_mut(chunk_size)
+    }
+    /// Runs `f` on every chunk from [`ArenaVec::chunks`] in parallel, and
+    /// returns what each call returned, in the same order as the chunks.
+    ///
+    /// The chunks are split into contiguous runs, and each run is handled by
+    /// its own thread, so this never uses more threads than
+    /// [`std::thread::available_parallelism`].
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is 0, or if `f` panics.
+    #[cfg(feature = "std")]
+    pub fn par_chunks<F, R>(&self, chunk_size: usize, f: F) -> alloc::vec::Vec<R>
+    where
+        T: Sync,
+        F: Fn(&[T]) -> R + Sync,
+        R: Send,
+    {
+        let run_len = self.par_run_len(chunk_size);
+        let f = &f;
+        std::thread::scope(|scope| {
+            let threads: alloc::vec::Vec<_> = self
+                .chunks(run_len)
+                .map(|run| {
+                    scope.spawn(move || {
+                        run.chunks(chunk_size)
+                            .map(f)
+                            .collect::<alloc::vec::Vec<_>>()
+                    })
+                })
+                .collect();
+            threads
+                .into_iter()
+                .flat_map(|thread| thread.join().unwrap())
+                .collect()
+        })
+    }
+    /// The same as [`ArenaVec::par_chunks`], but gives `f` mutable slices.
+    #[cfg(feature = "std")]
+    pub fn par_chunks_mut<F, R>(&mut self, chunk_size: usize, f: F) -> alloc::vec::Vec<R>
+    where
+        T: Send,
+        F: Fn(&mut [T]) -> R + Sync,
+        R: Send,
+    {
+        let run_len = self.par_run_len(chunk_size);
+        let f = &f;
+        std::thread::scope(|scope| {
+            let threads: alloc::vec::Vec<_> = self
+                .chunks_mut(run_len)
+                .map(|run| {
+                    scope.spawn(move || {
+                        run.chunks_mut(chunk_size)
+                            .map(f)
+                            .collect::<alloc::vec::Vec<_>>()
+                    })
+                })
+                .collect();
+            threads
+                .into_iter()
+                .flat_map(|thread| thread.join().unwrap())
+                .collect()
+        })
+    }
+    /// How many items each thread in [`ArenaVec::par_chunks`] handles: a
+    /// whole number of chunks, so there's about one run of chunks per
+    /// available thread.
+    #[cfg(feature = "std")]
+    fn par_run_len(&self, chunk_size: usize) -> usize {
+        assert_ne!(chunk_size, 0, "chunk size must be non-zero");
+        let chunks = self.len().div_ceil(chunk_size);
+        let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+
+        chunk_size.saturating_mul(chunks.div_ceil(threads).max(1))
+    }
+
+    /// Push every item from `iter`, or return an error if the arenavec runs
+    /// out of reserved memory. This isn't using the trait because it can fail.
+    ///
+    /// This is all-or-nothing: if it errors, every item it pushed is removed
+    /// again, so the arenavec is left exactly as it was before the call.
     pub fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<()> {
         let iter = iter.into_iter();
-        // There's not enough space to fit the whole iterator in
-        if iter.size_hint().0 > self.remaining_space() {
-            Err(Error::OutOfMemoryAddresses)
-        }
-        // If this function errors out due to not enough memory addresses, it will have filled up its entire capacity
-        // We may want to have it stay the same as it was before if it errors out
-        // But we can change that later
-        else {
-            for val in iter {
-                self.try_push(val)?;
+        let start_len = self.len();
+
+        // Commit the memory for as much of the iterator as we know about up
+        // front, so we only grow once
+        self.try_reserve(iter.size_hint().0)?;
+
+        for val in iter {
+            if let Err(err) = self.try_push(val) {
+                while self.len() > start_len {
+                    self.pop();
+                }
+                return Err(err);
             }
-            Ok(())
         }
+
+        Ok(())
     }
 
     pub fn try_append(&mut self, other: &mut ArenaVec<T>) -> Result<()> {
@@ -458,152 +745,113 @@ impl<T> ArenaVec<T> {
         other.clear();
     }
 
+    /// Removes consecutive duplicate items, like [`Vec::dedup`]. Duplicates
+    /// that aren't next to each other are kept, so `[1, 1, 2, 1]` becomes
+    /// `[1, 2, 1]`; sort the vec first, or use [`ArenaVec::dedup_all`], to
+    /// remove every duplicate.
     pub fn dedup(&mut self)
     where
         T: PartialEq,
     {
-        // First, tag all duplicates
-        // Arenavec?
-        let duplicate_idxs = ArenaVec::with_capacity(self.capacity());
-        for idx in 0..(self.len() - 1) {
-            if self.get(idx).unwrap().eq(self.get(idx + 1).unwrap()) {
-                duplicate_idxs.push(idx + 1);
-            }
-        }
-        // memcpy contiguous memory regions down the vec
-        let mut bgn = 0;
-        let mut region_iter = duplicate_idxs.iter().map(|idx| {
-            let region = bgn..*idx;
-            bgn = idx + 1;
-            region
-        });
-        // There is guaranteed to be at least one contiguous region
-        let mut base_range = region_iter.next().unwrap();
-        region_iter.for_each(|r| {
-            unsafe {
-                ptr::copy(
-                    self.get(r.start).unwrap(),
-                    self.get_mut(base_range.end).unwrap(),
-                    r.len(),
-                );
-            }
-            base_range = r;
-        });
+        self.dedup_by(|a, b| a == b);
     }
 
+    /// Removes consecutive items that `same_bucket` says are duplicates, like
+    /// [`Vec::dedup_by`]. `same_bucket` is called with each item and the last
+    /// item that was kept before it, in that order; if it returns true, the
+    /// item is removed.
     pub fn dedup_by<F>(&mut self, mut same_bucket: F)
     where
         F: FnMut(&mut T, &mut T) -> bool,
     {
-        // First, tag all duplicates
-        let duplicate_idxs = ArenaVec::with_capacity(self.capacity());
-        // the iteration here has to be a little weird, because guarantees we make about eq types are no longer relavent here
-        // if we have elements a, b, and c, and a ~= b, and a ~= c, this doesn't necessarily mean that b ~= c
-        // Because the spec says that we compare two items with the func and remove the second one (although it's first in the function call),
-        // We have to keep calling with the last element that flagged the function
-        // e.g, we have to call f(b, a), and if that returns true, we then have to call f(c, a)
-        // This means we have to keep track of indices
-        // :(
-        let mut base_idx = 0;
-        for idx in 1..self.len() {
-            // This is safe since idx is guaranteed to not equal base_idx
-            let a = self.get_mut(idx).unwrap() as *mut T;
-            let b = self.get_mut(base_idx).unwrap() as *mut T;
-            if same_bucket(unsafe { a.as_mut().unwrap() }, unsafe {
-                b.as_mut().unwrap()
-            }) {
-                duplicate_idxs.push(idx);
-            } else {
-                base_idx = idx;
-            }
+        let len = self.len();
+        if len <= 1 {
+            return;
         }
-        // memcpy contiguous memory regions down the vec
-        let mut bgn = 0;
-        let mut region_iter = duplicate_idxs.iter().map(|idx| {
-            let region = bgn..*idx;
-            bgn = idx + 1;
-            region
-        });
-        // There is guaranteed to be at least one contiguous region
-        let mut base_range = region_iter.next().unwrap();
-        region_iter.for_each(|r| {
+
+        // If `same_bucket` panics, leak the items instead of dropping the
+        // removed ones twice
+        self.len.set(0);
+
+        // Every item before `kept` has been kept, and the items from `kept` up
+        // to the item being checked are gaps left by removed items
+        let mut kept = 1;
+        for idx in 1..len {
             unsafe {
-                ptr::copy(
-                    self.get(r.start).unwrap(),
-                    self.get_mut(base_range.end).unwrap(),
-                    r.len(),
-                );
+                let item = self.buffer.add(idx);
+                if same_bucket(&mut *item, &mut *self.buffer.add(kept - 1)) {
+                    ptr::drop_in_place(item);
+                } else {
+                    if idx != kept {
+                        ptr::copy_nonoverlapping(item, self.buffer.add(kept), 1);
+                    }
+                    kept += 1;
+                }
             }
-            base_range = r;
-        });
+        }
+
+        self.len.set(kept);
     }
 
+    /// Removes consecutive items that have the same key, like
+    /// [`Vec::dedup_by_key`].
     pub fn dedup_by_key<F, K>(&mut self, mut key: F)
     where
         F: FnMut(&mut T) -> K,
         K: PartialEq,
     {
-        // First, tag all duplicates
-        let duplicate_idxs = ArenaVec::with_capacity(self.capacity());
-        // the iteration here has to be a little weird, because guarantees we make about eq types are no longer relavent here
-        // if we have elements a, b, and c, and a ~= b, and a ~= c, this doesn't necessarily mean that b ~= c
-        // Because the spec says that we compare two items with the func and remove the second one (although it's first in the function call),
-        // We have to keep calling with the last element that flagged the function
-        // e.g, we have to call f(b, a), and if that returns true, we then have to call f(c, a)
-        // This means we have to keep track of indices
-        // :(
-        let mut base_idx = 0;
-        for idx in 1..self.len() {
-            // This is safe since idx is guaranteed to not equal base_idx
-            let a = self.get_mut(idx).unwrap() as *mut T;
-            let b = self.get_mut(base_idx).unwrap() as *mut T;
-            if key(unsafe { a.as_mut().unwrap() }) == key(unsafe { b.as_mut().unwrap() }) {
-                duplicate_idxs.push(idx);
-            } else {
-                base_idx = idx;
-            }
-        }
-        // memcpy contiguous memory regions down the vec
-        let mut bgn = 0;
-        let mut region_iter = duplicate_idxs.iter().map(|idx| {
-            let region = bgn..*idx;
-            bgn = idx + 1;
-            region
-        });
-        // There is guaranteed to be at least one contiguous region
-        let mut base_range = region_iter.next().unwrap();
-        region_iter.for_each(|r| {
-            unsafe {
-                ptr::copy(
-                    self.get(r.start).unwrap(),
-                    self.get_mut(base_range.end).unwrap(),
-                    r.len(),
-                );
-            }
-            base_range = r;
-        });
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes every duplicate item, keeping the first of each, no matter
+    /// where they are in the vec; so `[1, 1, 2, 1]` becomes `[1, 2]`. Unlike
+    /// [`ArenaVec::dedup`], this doesn't need the vec to be sorted, but it
+    /// needs to hash every item.
+    #[cfg(feature = "std")]
+    pub fn dedup_all(&mut self)
+    where
+        T: Eq + Hash,
+    {
+        let mut seen = std::collections::HashSet::with_capacity(self.len());
+        let keep: alloc::vec::Vec<bool> = self.iter().map(|item| seen.insert(item)).collect();
+        drop(seen);
+
+        let mut keep = keep.into_iter();
+        self.retain(|_| keep.next().unwrap());
     }
 
     pub fn try_split_off(&mut self, at: usize) -> Result<ArenaVec<T>> {
         if at > self.len() {
             return Err(Error::IndexOutOfBounds);
         }
-        let mut other = ArenaVec::with_capacity(self.len() - at);
-        if at == self.len() {
+        let tail_len = self.len() - at;
+        let mut other = ArenaVec::with_capacity(tail_len);
+        if tail_len == 0 {
             return Ok(other);
         }
         unsafe {
-            ptr::copy(self.buffer.add(at), other.as_mut_ptr(), self.len() - at - 1);
+            ptr::copy_nonoverlapping(self.buffer.add(at), other.as_mut_ptr(), tail_len);
+            other.set_len(tail_len);
         }
         self.len.set(at);
         Ok(other)
     }
 
     pub fn truncate(&mut self, new_len: usize) {
-        if new_len > self.len() {
+        let len = self.len();
+        if new_len > len {
             return;
         }
+
+        // Shorten the vec first, so if a destructor panics, we don't drop
+        // the other items twice
         self.len.set(new_len);
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.buffer.add(new_len),
+                len - new_len,
+            ));
+        }
     }
 
     pub fn try_resize_with<F>(&mut self, new_len: usize, mut f: F) -> Result<()>
@@ -617,6 +865,8 @@ impl<T> ArenaVec<T> {
             }
             Ordering::Equal => Ok(()),
             Ordering::Greater => {
+                // Grow once up front, instead of checking every push
+                self.try_ensure_capacity(new_len)?;
                 while self.len() < new_len {
                     self.try_push(f())?;
                 }
@@ -632,6 +882,8 @@ impl<T> ArenaVec<T> {
         if new_len < self.len() {
             self.truncate(new_len);
         } else if new_len != self.len() {
+            // Grow once up front, instead of checking every push
+            self.ensure_capacity(new_len);
             while self.len() < new_len {
                 self.push(f());
             }
@@ -714,6 +966,36 @@ impl<T> ArenaVec<T> {
         self.len.set(new_len);
     }
 
+    /// Clone every item from `other` onto the end of this arenavec. Unlike
+    /// [`ArenaVec::append`], `other` is left as it was. For [`Copy`] types,
+    /// [`ArenaVec::extend_from_arena_copy`] is faster.
+    pub fn extend_from_arena(&mut self, other: &ArenaVec<T>)
+    where
+        T: Clone,
+    {
+        self.reserve(other.len());
+
+        for val in other.iter() {
+            self.push(val.clone());
+        }
+    }
+
+    /// Copy every item from `other` onto the end of this arenavec, leaving
+    /// `other` as it was. This copies all of the items at once, instead of
+    /// cloning them one at a time like [`ArenaVec::extend_from_arena`].
+    pub fn extend_from_arena_copy(&mut self, other: &ArenaVec<T>)
+    where
+        T: Copy,
+    {
+        let len = self.len();
+        self.reserve(other.len());
+
+        unsafe {
+            ptr::copy_nonoverlapping(other.as_ptr(), self.as_mut_ptr().add(len), other.len());
+        }
+        self.len.set(len + other.len());
+    }
+
     pub fn try_extend_from_within<R>(&mut self, src: R) -> Result<()>
     where
         R: RangeBounds<usize>,
@@ -756,10 +1038,24 @@ impl<T> ArenaVec<T> {
         }
     }
 
-    // TODO: actually deallocate pages here lol
+    /// Shrinks the [`ArenaVec`]'s capacity to fit at least `min_capacity`
+    /// elements, releasing any whole pages it no longer needs.
     pub fn shrink_to(&mut self, min_capacity: usize) {
         let new_cap = self.capacity().min(min_capacity.max(self.len()));
-        self.capacity.set(new_cap);
+        let committed_memory = self.capacity_bytes();
+        let new_committed_memory = self.page_align(new_cap * mem::size_of::<T>());
+
+        if new_committed_memory < committed_memory {
+            unsafe {
+                let region_to_release =
+                    NonNull::new_unchecked(self.buffer.byte_add(new_committed_memory));
+                Os::decommit(
+                    region_to_release.cast(),
+                    committed_memory - new_committed_memory,
+                );
+            }
+            self.committed_memory.set(new_committed_memory);
+        }
     }
     pub fn shrink_to_fit(&mut self) {
         self.shrink_to(self.len());
@@ -782,7 +1078,8 @@ impl<T> ArenaVec<T> {
             Ordering::Less => {
                 let mut v = ArenaVec::with_capacity(len - at);
                 unsafe {
-                    ptr::copy(self.buffer.add(at), v.as_mut_ptr(), len - at - 1);
+                    ptr::copy_nonoverlapping(self.buffer.add(at), v.as_mut_ptr(), len - at);
+                    v.set_len(len - at);
                 }
                 self.len.set(at);
                 v
@@ -805,16 +1102,24 @@ impl<T> Drop for ArenaVec<T> {
 
         unsafe {
             let buffer = NonNull::new_unchecked(self.buffer);
-            Os::decommit(buffer.cast(), self.capacity());
+            Os::decommit(buffer.cast(), self.capacity_bytes());
             Os::dereserve(buffer.cast(), self.reserved_memory);
         }
     }
 }
 impl<T: Clone> Clone for ArenaVec<T> {
     fn clone(&self) -> Self {
-        let new_vec: ArenaVec<T> =
-            ArenaVec::with_reserved_memory_and_capacity(self.reserved_memory, self.capacity());
-        unsafe { new_vec.buffer.copy_from(self.buffer, self.len()) };
+        let new_vec: ArenaVec<T> = if self.huge_pages {
+            ArenaVec::with_reserved_memory_hugepages(self.reserved_memory)
+        } else {
+            ArenaVec::with_reserved_memory(self.reserved_memory)
+        };
+        new_vec.reserve_shared(self.capacity());
+        // Items have to be cloned one at a time; copying their bytes would
+        // leave both vecs owning the same heap data
+        for item in self.iter() {
+            new_vec.push(item.clone());
+        }
 
         new_vec
     }
@@ -850,6 +1155,44 @@ impl<T> DerefMut for ArenaVec<T> {
         self.as_mut_slice()
     }
 }
+impl<T: Debug> Debug for ArenaVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+impl<T: PartialEq<U>, U> PartialEq<ArenaVec<U>> for ArenaVec<T> {
+    fn eq(&self, other: &ArenaVec<U>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+impl<T: PartialEq<U>, U> PartialEq<[U]> for ArenaVec<T> {
+    fn eq(&self, other: &[U]) -> bool {
+        self.as_slice() == other
+    }
+}
+impl<T: PartialEq<U>, U> PartialEq<&[U]> for ArenaVec<T> {
+    fn eq(&self, other: &&[U]) -> bool {
+        self.as_slice() == *other
+    }
+}
+impl<T: PartialEq<U>, U, const N: usize> PartialEq<[U; N]> for ArenaVec<T> {
+    fn eq(&self, other: &[U; N]) -> bool {
+        self.as_slice() == other
+    }
+}
+#[cfg(any(feature = "std", test))]
+impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for ArenaVec<T> {
+    fn eq(&self, other: &Vec<U>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+impl<T: Eq> Eq for ArenaVec<T> {}
+impl<T: Hash> Hash for ArenaVec<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
 impl<T: Clone> From<&[T]> for ArenaVec<T> {
     fn from(value: &[T]) -> Self {
         let mut v = Self::with_capacity(value.len());
@@ -971,6 +1314,146 @@ impl<'a, T> Drop for Drain<'a, T> {
     }
 }
 
+/// See [`ArenaVec::extract_if`].
+pub struct ExtractIf<'a, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    arena_vec: &'a mut ArenaVec<T>,
+    pred: F,
+    /// The next item to check.
+    idx: usize,
+    /// How many items have been removed so far. Items that are kept get
+    /// moved down by this much.
+    extracted: usize,
+    /// The length of the vec before extracting.
+    len: usize,
+}
+impl<T, F> Iterator for ExtractIf<'_, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.idx < self.len {
+            let ptr = unsafe { self.arena_vec.buffer.add(self.idx) };
+            let extract = (self.pred)(unsafe { &mut *ptr });
+            self.idx += 1;
+
+            if extract {
+                self.extracted += 1;
+                return Some(unsafe { ptr.read() });
+            } else if self.extracted > 0 {
+                unsafe { ptr.copy_to_nonoverlapping(ptr.sub(self.extracted), 1) };
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.len - self.idx))
+    }
+}
+impl<T, F> Drop for ExtractIf<'_, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    fn drop(&mut self) {
+        // Move the items that weren't checked down to fill the gaps
+        let remaining = self.len - self.idx;
+        if self.extracted > 0 && remaining > 0 {
+            unsafe {
+                let src = self.arena_vec.buffer.add(self.idx);
+                ptr::copy(src, src.sub(self.extracted), remaining);
+            }
+        }
+        self.arena_vec.len.set(self.len - self.extracted);
+    }
+}
+
+/// See [`ArenaVec::splice`].
+pub struct Splice<'a, T, I>
+where
+    I: Iterator<Item = T>,
+{
+    arena_vec: &'a mut ArenaVec<T>,
+    replace_with: I,
+    /// The next item in the range to yield.
+    idx: usize,
+    /// The range being removed.
+    start: usize,
+    end: usize,
+    /// The length of the vec before splicing.
+    len: usize,
+}
+impl<T, I> Iterator for Splice<'_, T, I>
+where
+    I: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx < self.end {
+            let ptr = unsafe { self.arena_vec.buffer.add(self.idx) };
+            self.idx += 1;
+
+            Some(unsafe { ptr.read() })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.idx;
+        (remaining, Some(remaining))
+    }
+}
+impl<T, I> Drop for Splice<'_, T, I>
+where
+    I: Iterator<Item = T>,
+{
+    fn drop(&mut self) {
+        // Drop any removed items that weren't yielded
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.arena_vec.buffer.add(self.idx),
+                self.end - self.idx,
+            ));
+        }
+
+        let tail_len = self.len - self.end;
+        let buffer = self.arena_vec.buffer;
+
+        // Fill the gap the range left behind
+        let mut write = self.start;
+        while write < self.end {
+            let Some(item) = self.replace_with.next() else {
+                // Ran out of replacements, so move the tail down
+                unsafe { ptr::copy(buffer.add(self.end), buffer.add(write), tail_len) };
+                self.arena_vec.len.set(write + tail_len);
+                return;
+            };
+            unsafe { buffer.add(write).write(item) };
+            write += 1;
+            self.arena_vec.len.set(write);
+        }
+
+        // The gap's full, so any other replacements need the tail moved up
+        let rest: alloc::vec::Vec<T> = self.replace_with.by_ref().collect();
+        let added = rest.len();
+        if added > 0 {
+            self.arena_vec.ensure_capacity(self.len + added);
+            unsafe { ptr::copy(buffer.add(self.end), buffer.add(self.end + added), tail_len) };
+            for (offset, item) in rest.into_iter().enumerate() {
+                unsafe { buffer.add(self.end + offset).write(item) };
+            }
+        }
+        self.arena_vec.len.set(self.len + added);
+    }
+}
+
 pub struct Iter<'a, T> {
     arena_vec: &'a ArenaVec<T>,
     idx: usize,
@@ -984,8 +1467,16 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+/// See [`ArenaVec::iter_mut`].
 pub struct IterMut<'a, T> {
-    arena_vec: &'a mut ArenaVec<T>,
+    /// Keeps the arenavec borrowed exclusively while the iterator is alive.
+    _arena_vec: PhantomData<&'a mut ArenaVec<T>>,
+    /// The arenavec's buffer. Items are reached through this instead of
+    /// reborrowing the arenavec, so getting the next item doesn't conflict
+    /// with references to earlier items.
+    ptr: *mut T,
+    /// The arenavec's length when the iterator was created.
+    len: usize,
     idx: usize,
 }
 
@@ -994,13 +1485,17 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let idx = self.idx;
-        if idx >= self.arena_vec.len() {
+        if idx >= self.len {
             return None;
         }
         self.idx += 1;
 
-        let ptr = self.arena_vec.as_mut_ptr();
-        Some(unsafe { ptr.add(idx).as_mut().unwrap() })
+        Some(unsafe { &mut *self.ptr.add(idx) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.idx;
+        (remaining, Some(remaining))
     }
 }
 
@@ -1049,7 +1544,7 @@ impl Write for ArenaVec<u8> {
 
 #[cfg(test)]
 mod tests {
-    use super::ArenaVec;
+    use super::{ArenaVec, Error};
 
     #[test]
     fn do_it_work_tho() {
@@ -1121,6 +1616,18 @@ mod tests {
         assert_eq!(vec.get(3), None);
     }
 
+    #[test]
+    fn push_after_iter_mut() {
+        let mut vec = ArenaVec::from([1, 2, 3]);
+        vec.iter_mut().for_each(|val| *val += 1);
+        vec.push(5);
+
+        // Iterators that aren't run to the end release the vec too
+        vec.iter_mut().next();
+        vec.try_push(6).unwrap();
+        assert_eq!(vec.as_slice(), [2, 3, 4, 5, 6]);
+    }
+
     #[test]
     fn iter_mut_modify() {
         let mut vec = ArenaVec::default();
@@ -1177,4 +1684,485 @@ mod tests {
         vec.push(1);
         assert_eq!(vec.len(), 5);
     }
+
+    #[test]
+    fn grows_past_capacity() {
+        let vec: ArenaVec<u64> = ArenaVec::with_capacity(1);
+        let capacity = vec.capacity();
+        let capacity_bytes = vec.capacity_bytes();
+
+        for i in 0..capacity {
+            vec.push(i as u64);
+        }
+        assert_eq!(vec.len(), capacity);
+        assert_eq!(vec.capacity_bytes(), capacity_bytes);
+
+        vec.push(0);
+        assert_eq!(vec.len(), capacity + 1);
+        assert!(vec.capacity() > capacity);
+        assert_eq!(vec[capacity - 1], capacity as u64 - 1);
+    }
+
+    #[test]
+    fn split_at() {
+        let vec = ArenaVec::from([0, 1, 2, 3]);
+
+        assert_eq!(vec.split_at(0), (&[][..], &[0, 1, 2, 3][..]));
+        assert_eq!(vec.split_at(2), (&[0, 1][..], &[2, 3][..]));
+        assert_eq!(vec.split_at(4), (&[0, 1, 2, 3][..], &[][..]));
+    }
+
+    #[test]
+    fn chunks() {
+        let mut vec = ArenaVec::from([0, 1, 2, 3, 4]);
+
+        let mut chunks = vec.chunks(2);
+        assert_eq!(chunks.next(), Some(&[0, 1][..]));
+        assert_eq!(chunks.next(), Some(&[2, 3][..]));
+        assert_eq!(chunks.next(), Some(&[4][..]));
+        assert_eq!(chunks.next(), None);
+
+        for chunk in vec.chunks_mut(2) {
+            chunk[0] = 10;
+        }
+        assert_eq!(vec.as_slice(), &[10, 1, 10, 3, 10]);
+    }
+
+    #[test]
+    fn zero_sized_types() {
+        let vec = ArenaVec::default();
+        for _ in 0..3_000_000 {
+            vec.push(());
+        }
+
+        assert_eq!(vec.len(), 3_000_000);
+        assert_eq!(vec.iter().count(), 3_000_000);
+        assert_eq!(vec.get(2_999_999), Some(&()));
+        assert_eq!(vec.get(3_000_000), None);
+        assert_eq!(vec.capacity_bytes(), 0);
+        assert_eq!(vec.remaining_space(), usize::MAX);
+    }
+
+    #[test]
+    fn capacity_is_in_elements() {
+        let vec: ArenaVec<u64> = ArenaVec::with_capacity(10);
+        assert!(vec.capacity() >= 10);
+        assert_eq!(vec.capacity() * 8, vec.capacity_bytes());
+
+        for i in 0..1000 {
+            vec.push(i);
+        }
+        assert!(vec.capacity() >= vec.len());
+        assert_eq!(vec.capacity(), vec.capacity_bytes() / 8);
+    }
+
+    #[test]
+    fn equality() {
+        let a = ArenaVec::from([1, 2, 3]);
+        let b = ArenaVec::from([1, 2, 3]);
+        let c = ArenaVec::from([1, 2]);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a, [1, 2, 3]);
+        assert_eq!(a, &[1, 2, 3][..]);
+        assert_eq!(a, vec![1, 2, 3]);
+        assert_eq!(format!("{a:?}"), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn hash() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let state = RandomState::new();
+        let a = ArenaVec::from([1, 2, 3]);
+        let b = ArenaVec::from([1, 2, 3]);
+
+        assert_eq!(state.hash_one(&a), state.hash_one(&b));
+        assert_eq!(state.hash_one(&a), state.hash_one([1, 2, 3].as_slice()));
+    }
+
+    #[test]
+    fn huge_pages_fall_back() {
+        use crate::utils::MemoryAmount;
+
+        // Whether or not huge pages are available, this should work the same
+        let vec =
+            ArenaVec::with_reserved_memory_hugepages(MemoryAmount::Mebibytes(64).into_bytes());
+        for i in 0..100_000_u64 {
+            vec.push(i);
+        }
+
+        assert_eq!(vec.len(), 100_000);
+        assert!(vec.iter().copied().eq(0..100_000));
+        assert!(vec.reserved_memory() >= MemoryAmount::Mebibytes(64).into_bytes());
+    }
+
+    #[test]
+    fn try_extend_is_all_or_nothing() {
+        use {
+            crate::os::{Os, OsTrait},
+            std::rc::Rc,
+        };
+
+        let mut vec: ArenaVec<u64> = ArenaVec::with_reserved_memory(Os::page_size());
+        vec.extend(0..10);
+        let max = vec.remaining_space() + vec.len();
+
+        // The size hint is too big
+        assert!(vec.try_extend(0..max as u64).is_err());
+        assert_eq!(vec.len(), 10);
+        // There's no size hint, so we only find out partway through
+        assert!(vec.try_extend((0..max as u64).filter(|_| true)).is_err());
+        assert_eq!(vec.len(), 10);
+        assert!(vec.iter().copied().eq(0..10));
+
+        // Items that were pushed before the error are dropped
+        let val = Rc::new(());
+        let mut vec: ArenaVec<Rc<()>> = ArenaVec::with_reserved_memory(Os::page_size());
+        let max = vec.remaining_space();
+        assert!(vec
+            .try_extend((0..=max).map(|_| val.clone()).filter(|_| true))
+            .is_err());
+        assert!(vec.is_empty());
+        assert_eq!(Rc::strong_count(&val), 1);
+
+        assert!(vec.try_extend((0..max).map(|_| val.clone())).is_ok());
+        assert_eq!(vec.len(), max);
+    }
+
+    #[test]
+    fn resize() {
+        use {
+            crate::os::{Os, OsTrait},
+            std::rc::Rc,
+        };
+
+        // Growing commits all the memory it needs at once, instead of
+        // doubling the capacity over and over
+        let mut vec: ArenaVec<u8> = ArenaVec::new();
+        vec.resize(100_000, 7);
+        assert_eq!(vec.len(), 100_000);
+        assert_eq!(vec.capacity(), Os::page_align(100_000));
+        assert!(vec.iter().all(|val| *val == 7));
+
+        let mut vec: ArenaVec<u8> = ArenaVec::new();
+        vec.try_resize_with(100_000, || 7).unwrap();
+        assert_eq!(vec.capacity(), Os::page_align(100_000));
+
+        // Shrinking drops the removed items
+        let val = Rc::new(());
+        let mut vec = ArenaVec::new();
+        vec.resize(10, val.clone());
+        assert_eq!(Rc::strong_count(&val), 11);
+        vec.resize(4, val.clone());
+        assert_eq!(Rc::strong_count(&val), 5);
+        vec.truncate(0);
+        assert_eq!(Rc::strong_count(&val), 1);
+    }
+
+    #[test]
+    fn retain() {
+        use std::rc::Rc;
+
+        let mut vec: ArenaVec<u32> = (0..10).collect();
+        vec.retain(|val| *val % 3 == 0);
+        assert_eq!(vec, [0, 3, 6, 9]);
+
+        let mut vec: ArenaVec<u32> = (0..5).collect();
+        vec.retain_mut(|val| {
+            *val *= 10;
+            *val != 20
+        });
+        assert_eq!(vec, [0, 10, 30, 40]);
+
+        // Removed items are dropped
+        let val = Rc::new(());
+        let mut vec: ArenaVec<_> = (0..10).map(|idx| (idx, val.clone())).collect();
+        vec.retain(|(idx, _)| *idx < 3);
+        assert_eq!(vec.len(), 3);
+        assert_eq!(Rc::strong_count(&val), 4);
+    }
+
+    #[test]
+    fn extract_if() {
+        let mut vec: ArenaVec<u32> = (0..10).collect();
+        let evens: Vec<_> = vec.extract_if(|val| *val % 2 == 0).collect();
+        assert_eq!(evens, [0, 2, 4, 6, 8]);
+        assert_eq!(vec, [1, 3, 5, 7, 9]);
+
+        // Items that weren't checked yet stay in the vec
+        let mut vec: ArenaVec<String> = (0..10).map(|val| val.to_string()).collect();
+        let mut extract = vec.extract_if(|val| val.parse::<u32>().unwrap() % 2 == 0);
+        assert_eq!(extract.next().as_deref(), Some("0"));
+        assert_eq!(extract.next().as_deref(), Some("2"));
+        drop(extract);
+        assert_eq!(vec, ["1", "3", "4", "5", "6", "7", "8", "9"]);
+    }
+
+    #[test]
+    fn splice() {
+        // Replacement the same length as the range
+        let mut vec = ArenaVec::from([1, 2, 3, 4, 5]);
+        let removed: Vec<_> = vec.splice(1..3, [20, 30]).collect();
+        assert_eq!(removed, [2, 3]);
+        assert_eq!(vec, [1, 20, 30, 4, 5]);
+
+        // Empty range: a pure insert
+        let mut vec = ArenaVec::from([1, 2, 3]);
+        let removed: Vec<_> = vec.splice(1..1, [10, 11]).collect();
+        assert!(removed.is_empty());
+        assert_eq!(vec, [1, 10, 11, 2, 3]);
+
+        // Empty replacement: a pure removal
+        let mut vec = ArenaVec::from([1, 2, 3, 4]);
+        let removed: Vec<_> = vec.splice(..2, []).collect();
+        assert_eq!(removed, [1, 2]);
+        assert_eq!(vec, [3, 4]);
+
+        // Replacement longer than the range
+        let mut vec = ArenaVec::from([1, 2, 3]);
+        let removed: Vec<_> = vec.splice(1..=1, 10..15).collect();
+        assert_eq!(removed, [2]);
+        assert_eq!(vec, [1, 10, 11, 12, 13, 14, 3]);
+
+        // Replacement shorter than the range
+        let mut vec = ArenaVec::from([1, 2, 3, 4, 5, 6]);
+        let removed: Vec<_> = vec.splice(1.., [7]).collect();
+        assert_eq!(removed, [2, 3, 4, 5, 6]);
+        assert_eq!(vec, [1, 7]);
+
+        // Growing past the committed memory
+        let mut vec: ArenaVec<u64> = ArenaVec::from([0, 1]);
+        let capacity = vec.capacity();
+        drop(vec.splice(1..1, 0..capacity as u64));
+        assert_eq!(vec.len(), capacity + 2);
+        assert_eq!(vec[capacity], capacity as u64 - 1);
+        assert_eq!(vec[capacity + 1], 1);
+
+        // Removed items that aren't yielded are dropped
+        let mut vec: ArenaVec<String> = ["a", "b", "c"].map(String::from).into();
+        let mut splice = vec.splice(.., [String::from("d")]);
+        assert_eq!(splice.next().as_deref(), Some("a"));
+        drop(splice);
+        assert_eq!(vec, ["d"]);
+    }
+
+    #[test]
+    fn extend_from_arena() {
+        let mut vec = ArenaVec::from([1, 2]);
+        let other = ArenaVec::from([3, 4, 5]);
+        vec.extend_from_arena_copy(&other);
+        assert_eq!(vec, [1, 2, 3, 4, 5]);
+        assert_eq!(other, [3, 4, 5]);
+
+        let mut vec = ArenaVec::from([String::from("a")]);
+        let other = ArenaVec::from([String::from("b"), String::from("c")]);
+        vec.extend_from_arena(&other);
+        assert_eq!(vec, ["a", "b", "c"]);
+        assert_eq!(other, ["b", "c"]);
+    }
+
+    #[test]
+    fn position_and_find() {
+        let vec = ArenaVec::from([1, 2, 3, 2]);
+        assert_eq!(vec.position(|val| *val == 2), Some(1));
+        assert_eq!(vec.rposition(|val| *val == 2), Some(3));
+        assert_eq!(vec.find(|val| *val > 1), Some(&2));
+
+        assert_eq!(vec.position(|val| *val == 4), None);
+        assert_eq!(vec.rposition(|val| *val == 4), None);
+        assert_eq!(vec.find(|val| *val > 3), None);
+
+        let empty: ArenaVec<i32> = ArenaVec::new();
+        assert_eq!(empty.position(|_| true), None);
+        assert_eq!(empty.rposition(|_| true), None);
+        assert_eq!(empty.find(|_| true), None);
+    }
+
+    #[test]
+    fn reserve_shared() {
+        let vec = ArenaVec::<u64>::default();
+        vec.push(0);
+        let before = vec.capacity();
+
+        let vec = &vec;
+        let additional = before * 4;
+        vec.reserve_shared(additional);
+        let reserved = vec.capacity();
+        assert!(reserved > additional);
+
+        // Every push fits in the memory that was already committed
+        for idx in 0..additional as u64 {
+            vec.push(idx);
+            assert_eq!(vec.capacity(), reserved);
+        }
+    }
+
+    #[test]
+    fn try_get() {
+        let mut vec = ArenaVec::default();
+        vec.push(0);
+        vec.push(1);
+
+        assert_eq!(vec.try_get(1), Ok(&1));
+        assert_eq!(vec.try_get(2), Err(Error::IndexOutOfBounds));
+
+        *vec.try_get_mut(0).unwrap() = 5;
+        assert_eq!(vec.try_get(0), Ok(&5));
+        assert_eq!(vec.try_get_mut(2), Err(Error::IndexOutOfBounds));
+    }
+
+    #[test]
+    fn dedup() {
+        let mut vec = ArenaVec::default();
+        vec.dedup();
+        assert!(vec.is_empty());
+
+        vec.extend_from_slice(&[1, 1, 2, 1]);
+        vec.dedup();
+        assert_eq!(vec.as_slice(), [1, 2, 1]);
+
+        let mut vec = ArenaVec::default();
+        vec.extend_from_slice(&[10, 11, 20, 35, 31]);
+        vec.dedup_by_key(|val| *val / 10);
+        assert_eq!(vec.as_slice(), [10, 20, 35]);
+
+        // Removed items are dropped, and kept ones aren't
+        let item = std::rc::Rc::new(());
+        let mut vec = ArenaVec::default();
+        for _ in 0..3 {
+            vec.push(item.clone());
+        }
+        vec.dedup_by(|_, _| true);
+        assert_eq!(vec.len(), 1);
+        assert_eq!(std::rc::Rc::strong_count(&item), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn dedup_all() {
+        let mut vec = ArenaVec::default();
+        vec.extend_from_slice(&[1, 1, 2, 1]);
+        vec.dedup_all();
+        assert_eq!(vec.as_slice(), [1, 2]);
+
+        let mut vec = ArenaVec::default();
+        vec.extend_from_slice(&[3, 1, 3, 2, 1, 3]);
+        vec.dedup_all();
+        assert_eq!(vec.as_slice(), [3, 1, 2]);
+    }
+
+    #[test]
+    fn split_off() {
+        let filled = || {
+            let vec = ArenaVec::default();
+            for idx in 0..4 {
+                vec.push(idx);
+            }
+            vec
+        };
+
+        let mut vec = filled();
+        let tail = vec.split_off(2);
+        assert_eq!(vec.as_slice(), [0, 1]);
+        assert_eq!(tail.as_slice(), [2, 3]);
+
+        let mut vec = filled();
+        let tail = vec.split_off(0);
+        assert!(vec.is_empty());
+        assert_eq!(tail.as_slice(), [0, 1, 2, 3]);
+
+        let mut vec = filled();
+        let tail = vec.split_off(4);
+        assert_eq!(vec.as_slice(), [0, 1, 2, 3]);
+        assert!(tail.is_empty());
+
+        let mut vec = filled();
+        let tail = vec.try_split_off(1).unwrap();
+        assert_eq!(vec.as_slice(), [0]);
+        assert_eq!(tail.as_slice(), [1, 2, 3]);
+        let tail = vec.try_split_off(0).unwrap();
+        assert!(vec.is_empty());
+        assert_eq!(tail.as_slice(), [0]);
+        assert_eq!(vec.try_split_off(0).unwrap().len(), 0);
+        assert_eq!(vec.try_split_off(1).err(), Some(Error::IndexOutOfBounds));
+    }
+
+    #[test]
+    fn with_capacity() {
+        let vec = ArenaVec::<u64>::with_capacity(1_000_000);
+        assert!(vec.reserved_memory() >= 8_000_000);
+        assert!(vec.reserved_memory() >= ArenaVec::<u64>::DEFAULT_RESERVED_MEMORY);
+        assert!(vec.capacity() >= 1_000_000);
+        assert!(vec.capacity_bytes() <= vec.reserved_memory());
+
+        let vec = ArenaVec::<u8>::with_capacity(10);
+        assert_eq!(
+            vec.reserved_memory(),
+            ArenaVec::<u8>::DEFAULT_RESERVED_MEMORY
+        );
+    }
+
+    #[test]
+    fn clone() {
+        let vec = ArenaVec::default();
+        for idx in 0..5 {
+            vec.push(idx.to_string());
+        }
+
+        let cloned = vec.clone();
+        assert_eq!(cloned.len(), vec.len());
+        assert_eq!(cloned.as_slice(), vec.as_slice());
+        assert_eq!(cloned.capacity(), vec.capacity());
+
+        // The clone owns its own copies
+        drop(vec);
+        assert_eq!(cloned[4], "4");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn par_chunks() {
+        let mut vec: ArenaVec<u32> = (0..10).collect();
+        let sums = vec.par_chunks(4, |chunk| chunk.iter().sum::<u32>());
+        assert_eq!(sums, [6, 22, 17]);
+
+        let lens = vec.par_chunks_mut(3, |chunk| {
+            chunk.iter_mut().for_each(|val| *val *= 2);
+            chunk.len()
+        });
+        assert_eq!(lens, [3, 3, 3, 1]);
+        assert_eq!(vec.as_slice(), [0, 2, 4, 6, 8, 10, 12, 14, 16, 18]);
+
+        // Far more chunks than threads
+        let vec: ArenaVec<u32> = (0..100_000).collect();
+        let items = vec.par_chunks(1, |chunk| chunk[0]);
+        assert!(items.into_iter().eq(0..100_000));
+
+        let empty: ArenaVec<u32> = ArenaVec::new();
+        assert!(empty.par_chunks(4, |_| ()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "overflowed")]
+    fn capacity_overflow() {
+        let _: ArenaVec<u64> =
+            ArenaVec::with_reserved_memory_and_capacity(usize::MAX, usize::MAX / 4);
+    }
+
+    #[test]
+    fn clone_huge_pages() {
+        use crate::utils::MemoryAmount;
+
+        let vec =
+            ArenaVec::with_reserved_memory_hugepages(MemoryAmount::Mebibytes(64).into_bytes());
+        vec.extend_from_slice(&[1, 2, 3]);
+
+        // If huge pages aren't available, neither vec uses them
+        let cloned = vec.clone();
+        assert_eq!(cloned.uses_huge_pages(), vec.uses_huge_pages());
+        assert_eq!(cloned.reserved_memory(), vec.reserved_memory());
+        assert_eq!(cloned.as_slice(), [1, 2, 3]);
+    }
 }
@@ -2,7 +2,12 @@
 
 use {
     alloc::vec::Vec,
-    core::ops::{Index, IndexMut},
+    core::{
+        fmt::{self, Debug},
+        mem::{self, ManuallyDrop, MaybeUninit},
+        ops::{Index, IndexMut},
+        ptr,
+    },
 };
 
 /// A vector whose first few items are in a stack-based array.
@@ -11,14 +16,16 @@ use {
 /// that are pushed to it in a regular [`Vec`]. Unless [`StackVec::with_capacity`] is used,
 /// the stackvec will not allocate on the heap until its array has been filled.
 pub struct StackVec<T, const SIZE: usize> {
-    stack: [Option<T>; SIZE],
+    /// The stack-based array. Only the first `len` items (up to `SIZE`) are
+    /// initialised.
+    stack: [MaybeUninit<T>; SIZE],
     vec: Vec<T>,
     len: usize,
 }
 impl<T, const SIZE: usize> Default for StackVec<T, SIZE> {
     fn default() -> Self {
         Self {
-            stack: [const { None }; SIZE],
+            stack: [const { MaybeUninit::uninit() }; SIZE],
             vec: Vec::default(),
             len: 0,
         }
@@ -33,8 +40,8 @@ impl<T, const SIZE: usize> StackVec<T, SIZE> {
     /// the stackvec won't allocate unless the given capacity is larger than `SIZE`.
     pub fn with_capacity(cap: usize) -> Self {
         Self {
-            stack: [const { None }; SIZE],
-            vec: Vec::with_capacity(cap - SIZE),
+            stack: [const { MaybeUninit::uninit() }; SIZE],
+            vec: Vec::with_capacity(cap.saturating_sub(SIZE)),
             len: 0,
         }
     }
@@ -42,7 +49,7 @@ impl<T, const SIZE: usize> StackVec<T, SIZE> {
     /// Push a value to the [`StackVec`].
     pub fn push(&mut self, val: T) {
         if self.len < SIZE {
-            self.stack[self.len] = Some(val);
+            self.stack[self.len].write(val);
         } else {
             self.vec.push(val);
         }
@@ -51,17 +58,21 @@ impl<T, const SIZE: usize> StackVec<T, SIZE> {
     }
 
     pub fn get(&self, idx: usize) -> Option<&T> {
-        if idx < SIZE {
-            self.stack[idx].as_ref()
+        if idx >= self.len {
+            None
+        } else if idx < SIZE {
+            Some(unsafe { self.stack[idx].assume_init_ref() })
         } else {
-            self.vec.get(idx)
+            self.vec.get(idx - SIZE)
         }
     }
     pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
-        if idx < SIZE {
-            self.stack[idx].as_mut()
+        if idx >= self.len {
+            None
+        } else if idx < SIZE {
+            Some(unsafe { self.stack[idx].assume_init_mut() })
         } else {
-            self.vec.get_mut(idx)
+            self.vec.get_mut(idx - SIZE)
         }
     }
 
@@ -81,8 +92,24 @@ impl<T, const SIZE: usize> StackVec<T, SIZE> {
         self.len == 0
     }
 
+    /// The number of initialised items in the stack-based array.
+    fn stack_len(&self) -> usize {
+        self.len.min(SIZE)
+    }
+
+    /// Remove and drop every item in the [`StackVec`]. This doesn't free the
+    /// heap-based vector's memory.
     pub fn clear(&mut self) {
+        let stack_len = self.stack_len();
+        // Set `len` first, so if a destructor panics we leak items instead of
+        // dropping them twice
         self.len = 0;
+
+        unsafe {
+            ptr::slice_from_raw_parts_mut(self.stack.as_mut_ptr().cast::<T>(), stack_len)
+                .drop_in_place();
+        }
+        self.vec.clear();
     }
 
     pub fn iter(&self) -> Iter<'_, T, SIZE> {
@@ -102,19 +129,21 @@ impl<T, const SIZE: usize> Index<usize> for StackVec<T, SIZE> {
     type Output = T;
 
     fn index(&self, idx: usize) -> &Self::Output {
-        if idx < SIZE {
-            self.stack[idx].as_ref().unwrap()
-        } else {
-            self.vec.index(idx)
+        match self.get(idx) {
+            Some(val) => val,
+            None => panic!(
+                "Index out of bounds: The index is {idx} but the length is {}",
+                self.len
+            ),
         }
     }
 }
 impl<T, const SIZE: usize> IndexMut<usize> for StackVec<T, SIZE> {
     fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
-        if idx < SIZE {
-            self.stack[idx].as_mut().unwrap()
-        } else {
-            self.vec.index_mut(idx)
+        let len = self.len;
+        match self.get_mut(idx) {
+            Some(val) => val,
+            None => panic!("Index out of bounds: The index is {idx} but the length is {len}"),
         }
     }
 }
@@ -123,12 +152,34 @@ impl<T, const SIZE: usize> IntoIterator for StackVec<T, SIZE> {
     type IntoIter = IntoIter<T, SIZE>;
 
     fn into_iter(self) -> Self::IntoIter {
+        // The iterator takes ownership of the items, so the stackvec's
+        // destructor must not run
+        let mut this = ManuallyDrop::new(self);
+
         IntoIter {
-            stackvec: self,
+            stack: unsafe { ptr::read(&this.stack) },
+            stack_len: this.stack_len(),
+            vec: mem::take(&mut this.vec).into_iter(),
             progress: 0,
         }
     }
 }
+impl<'a, T, const SIZE: usize> IntoIterator for &'a StackVec<T, SIZE> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, SIZE>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl<'a, T, const SIZE: usize> IntoIterator for &'a mut StackVec<T, SIZE> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T, SIZE>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
 impl<T, const SIZE: usize> FromIterator<T> for StackVec<T, SIZE> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         let iter = iter.into_iter();
@@ -141,37 +192,72 @@ impl<T, const SIZE: usize> FromIterator<T> for StackVec<T, SIZE> {
         this
     }
 }
+impl<T, const SIZE: usize> Drop for StackVec<T, SIZE> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
 impl<T: Clone, const SIZE: usize> Clone for StackVec<T, SIZE> {
     fn clone(&self) -> Self {
-        Self {
-            stack: self.stack.clone(),
-            vec: self.vec.clone(),
-            len: self.len,
+        let mut new = Self::with_capacity(self.len);
+        for val in self {
+            new.push(val.clone());
         }
+
+        new
+    }
+}
+impl<T: Debug, const SIZE: usize> Debug for StackVec<T, SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+impl<T: PartialEq<U>, U, const SIZE: usize, const OTHER_SIZE: usize>
+    PartialEq<StackVec<U, OTHER_SIZE>> for StackVec<T, SIZE>
+{
+    fn eq(&self, other: &StackVec<U, OTHER_SIZE>) -> bool {
+        self.len == other.len && self.iter().zip(other.iter()).all(|(a, b)| a == b)
     }
 }
+impl<T: Eq, const SIZE: usize> Eq for StackVec<T, SIZE> {}
 
 pub struct IntoIter<T, const SIZE: usize> {
-    stackvec: StackVec<T, SIZE>,
+    stack: [MaybeUninit<T>; SIZE],
+    /// The number of initialised items in `stack`.
+    stack_len: usize,
+    vec: alloc::vec::IntoIter<T>,
+    /// The number of items that have been moved out of `stack`.
     progress: usize,
 }
 impl<T, const SIZE: usize> Iterator for IntoIter<T, SIZE> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.progress == self.stackvec.len {
-            return None;
-        }
+        if self.progress < self.stack_len {
+            let val = unsafe { self.stack[self.progress].assume_init_read() };
+            self.progress += 1;
 
-        let ptr = if self.progress < SIZE {
-            self.stackvec.stack[self.progress].as_mut().unwrap() as *mut T
+            Some(val)
         } else {
-            (&mut self.stackvec.vec[self.progress]) as *mut T
-        };
-
-        self.progress += 1;
-
-        Some(unsafe { ptr.read() })
+            self.vec.next()
+        }
+    }
+}
+impl<T, const SIZE: usize> Drop for IntoIter<T, SIZE> {
+    fn drop(&mut self) {
+        // Drop the stack items that weren't iterated over; `vec` drops its
+        // own remaining items
+        let remaining = self.stack_len - self.progress;
+        let start = self.progress;
+        self.progress = self.stack_len;
+
+        unsafe {
+            ptr::slice_from_raw_parts_mut(
+                self.stack.as_mut_ptr().add(start).cast::<T>(),
+                remaining,
+            )
+            .drop_in_place();
+        }
     }
 }
 
@@ -188,9 +274,9 @@ impl<'a, T, const SIZE: usize> Iterator for Iter<'a, T, SIZE> {
         }
 
         let val = if self.progress < SIZE {
-            self.stackvec.stack[self.progress].as_ref().unwrap()
+            unsafe { self.stackvec.stack[self.progress].assume_init_ref() }
         } else {
-            &self.stackvec.vec[self.progress]
+            &self.stackvec.vec[self.progress - SIZE]
         };
 
         self.progress += 1;
@@ -212,9 +298,9 @@ impl<'a, T, const SIZE: usize> Iterator for IterMut<'a, T, SIZE> {
         }
 
         let ptr = if self.progress < SIZE {
-            self.stackvec.stack[self.progress].as_mut().unwrap() as *mut T
+            self.stackvec.stack[self.progress].as_mut_ptr()
         } else {
-            &mut self.stackvec.vec[self.progress] as *mut T
+            &mut self.stackvec.vec[self.progress - SIZE] as *mut T
         };
 
         self.progress += 1;
@@ -225,7 +311,19 @@ impl<'a, T, const SIZE: usize> Iterator for IterMut<'a, T, SIZE> {
 
 #[cfg(test)]
 mod tests {
-    use super::StackVec;
+    use {
+        super::StackVec,
+        core::{cell::Cell, mem},
+        std::rc::Rc,
+    };
+
+    /// Counts how many times it's been dropped.
+    struct DropCounter(Rc<Cell<usize>>);
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
 
     #[test]
     fn push() {
@@ -290,4 +388,91 @@ mod tests {
         let sv: StackVec<u32, 3> = StackVec::with_capacity(4);
         assert_eq!(sv.capacity(), 4);
     }
+
+    #[test]
+    fn equality() {
+        let a: StackVec<u32, 2> = (0..5).collect();
+        let b: StackVec<u32, 2> = (0..5).collect();
+        let c: StackVec<u32, 4> = (0..5).collect();
+        let d: StackVec<u32, 2> = (0..4).collect();
+
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        assert_ne!(a, d);
+        assert_eq!(a[3], 3);
+        assert_eq!(a.get(4), Some(&4));
+        assert_eq!(format!("{a:?}"), "[0, 1, 2, 3, 4]");
+    }
+
+    #[test]
+    fn for_loop() {
+        let mut sv: StackVec<u32, 2> = (0..4).collect();
+
+        for val in &mut sv {
+            *val *= 2;
+        }
+
+        let mut expected = 0;
+        for val in &sv {
+            assert_eq!(*val, expected);
+            expected += 2;
+        }
+        assert_eq!(expected, 8);
+    }
+
+    #[test]
+    fn drops() {
+        let drops = Rc::new(Cell::new(0));
+
+        let mut sv: StackVec<DropCounter, 2> = StackVec::new();
+        for _ in 0..4 {
+            sv.push(DropCounter(drops.clone()));
+        }
+        drop(sv);
+        assert_eq!(drops.get(), 4);
+
+        // Partially-consumed iterators drop the rest of the items
+        drops.set(0);
+        let sv: StackVec<DropCounter, 2> = (0..4).map(|_| DropCounter(drops.clone())).collect();
+        let mut iter = sv.into_iter();
+        drop(iter.next());
+        assert_eq!(drops.get(), 1);
+        drop(iter);
+        assert_eq!(drops.get(), 4);
+
+        // The stack array isn't dropped past `len`
+        drops.set(0);
+        let mut sv: StackVec<DropCounter, 4> = StackVec::new();
+        sv.push(DropCounter(drops.clone()));
+        assert!(sv.get(1).is_none());
+        drop(sv);
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn no_option_overhead() {
+        assert_eq!(
+            mem::size_of::<StackVec<u8, 8>>(),
+            mem::size_of::<[u8; 8]>() + mem::size_of::<Vec<u8>>() + mem::size_of::<usize>()
+        );
+    }
+
+    #[test]
+    fn clear_drops_once() {
+        let drops = Rc::new(Cell::new(0));
+
+        let mut sv: StackVec<DropCounter, 2> = StackVec::new();
+        for _ in 0..3 {
+            sv.push(DropCounter(drops.clone()));
+        }
+        sv.clear();
+        assert_eq!(drops.get(), 3);
+        assert!(sv.is_empty());
+
+        // Items pushed after clearing are dropped with the stackvec, and the
+        // cleared items aren't dropped again
+        sv.push(DropCounter(drops.clone()));
+        drop(sv);
+        assert_eq!(drops.get(), 4);
+    }
 }
@@ -0,0 +1,253 @@
+//! Module for [`SyncArenaVec`].
+
+use {
+    super::arenavec::{Error, Result},
+    crate::os::{Os, OsTrait},
+    core::{
+        mem,
+        ops::Deref,
+        ptr::{self, NonNull},
+        slice,
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+
+/// A thread-safe version of [`ArenaVec`]. Multiple threads can push to a
+/// sync arenavec at the same time, through a shared reference.
+///
+/// Like an [`ArenaVec`], a sync arenavec reserves a large block of virtual
+/// addresses up front and commits memory in that block as it grows, so its
+/// items never move. Pushing works in three steps:
+/// 1. The pushing thread claims the next slot with an atomic counter, so every
+///    push gets its own slot.
+/// 2. If the slot isn't in committed memory yet, one thread commits more
+///    memory while any others that need it wait.
+/// 3. The value is written to the slot, and once every earlier slot has also
+///    been written, the arenavec's length is bumped to include it.
+///
+/// Because of step 3, [`SyncArenaVec::len`] only ever covers fully-written
+/// items, so reading the arenavec while other threads push to it is safe. The
+/// tradeoff is that a push can briefly wait on pushes that claimed earlier
+/// slots.
+///
+/// Sync arenavecs can only grow through a shared reference; removing items
+/// requires `&mut self`.
+///
+/// [`ArenaVec`]: super::ArenaVec
+pub struct SyncArenaVec<T> {
+    /// The total amount of memory the arenavec reserved when it was created.
+    reserved_memory: usize,
+    /// The amount of memory, in bytes, the arenavec has committed.
+    committed_memory: AtomicUsize,
+    /// Set while a thread is committing more memory.
+    growing: AtomicBool,
+    /// The number of slots that have been claimed by pushes. Some of these
+    /// may not be written yet.
+    claimed: AtomicUsize,
+    /// The number of items that have been written. Every item below this
+    /// index is initialised.
+    len: AtomicUsize,
+    /// The base of the memory buffer storing all the arenavec's items.
+    buffer: NonNull<T>,
+}
+unsafe impl<T: Send> Send for SyncArenaVec<T> {}
+unsafe impl<T: Send + Sync> Sync for SyncArenaVec<T> {}
+impl<T> SyncArenaVec<T> {
+    /// See [`ArenaVec::DEFAULT_RESERVED_MEMORY`].
+    ///
+    /// [`ArenaVec::DEFAULT_RESERVED_MEMORY`]: super::ArenaVec::DEFAULT_RESERVED_MEMORY
+    pub const DEFAULT_RESERVED_MEMORY: usize = super::ArenaVec::<T>::DEFAULT_RESERVED_MEMORY;
+
+    /// Creates a [`SyncArenaVec`] that reserves
+    /// [`SyncArenaVec::DEFAULT_RESERVED_MEMORY`] bytes. This does not
+    /// allocate.
+    pub fn new() -> Self {
+        Self::with_reserved_memory(Self::DEFAULT_RESERVED_MEMORY)
+    }
+
+    /// Creates a [`SyncArenaVec`] with the specified amount of reserved
+    /// virtual addresses. This does not allocate.
+    pub fn with_reserved_memory(reserved_memory: usize) -> Self {
+        let reserved_memory = Os::page_align(reserved_memory);
+        let buffer = Os::reserve(reserved_memory).unwrap();
+
+        Self {
+            reserved_memory,
+            committed_memory: AtomicUsize::new(0),
+            growing: AtomicBool::new(false),
+            claimed: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+            buffer: buffer.cast(),
+        }
+    }
+
+    /// Push a value to the arenavec, panicking if it runs out of reserved
+    /// memory.
+    pub fn push(&self, val: T) {
+        if self.try_push(val).is_err() {
+            panic!("SyncArenaVec needed to grow, but ran out of reserved memory");
+        }
+    }
+
+    /// Push a value to the arenavec, or return an error if it's out of
+    /// reserved memory.
+    ///
+    /// If this errors, the slot this push claimed is never filled, so every
+    /// later push will fail too.
+    pub fn try_push(&self, val: T) -> Result<()> {
+        let idx = self.claimed.fetch_add(1, Ordering::Relaxed);
+        let required_memory = idx
+            .checked_add(1)
+            .and_then(|len| len.checked_mul(mem::size_of::<T>()))
+            .ok_or(Error::OutOfMemoryAddresses)?;
+        self.ensure_committed(required_memory)?;
+
+        unsafe { self.buffer.add(idx).write(val) };
+
+        // Wait for earlier pushes to finish, so `len` only covers written
+        // items
+        while self
+            .len
+            .compare_exchange_weak(idx, idx + 1, Ordering::Release, Ordering::Relaxed)
+            .is_err()
+        {
+            backoff();
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure at least `required_memory` bytes are committed.
+    fn ensure_committed(&self, required_memory: usize) -> Result<()> {
+        if required_memory > self.reserved_memory {
+            return Err(Error::OutOfMemoryAddresses);
+        }
+
+        loop {
+            let committed_memory = self.committed_memory.load(Ordering::Acquire);
+            if committed_memory >= required_memory {
+                return Ok(());
+            }
+
+            if self
+                .growing
+                .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                backoff();
+                continue;
+            }
+
+            // Another thread may have grown the arenavec while we were
+            // waiting
+            let committed_memory = self.committed_memory.load(Ordering::Acquire);
+            if committed_memory < required_memory {
+                // Double in size if possible, like `ArenaVec`
+                let new_committed_memory =
+                    Os::page_align(required_memory.max(committed_memory * 2))
+                        .min(self.reserved_memory);
+                unsafe {
+                    Os::commit(
+                        self.buffer.byte_add(committed_memory).cast(),
+                        new_committed_memory - committed_memory,
+                    );
+                }
+                self.committed_memory
+                    .store(new_committed_memory, Ordering::Release);
+            }
+
+            self.growing.store(false, Ordering::Release);
+            return Ok(());
+        }
+    }
+
+    /// The number of fully-pushed items in the arenavec.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.as_slice().get(idx)
+    }
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buffer.as_ptr(), self.len()) }
+    }
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.buffer.as_ptr(), self.len()) }
+    }
+
+    /// Remove all the items in the arenavec. This doesn't decommit any memory.
+    pub fn clear(&mut self) {
+        let len = self.len();
+        *self.len.get_mut() = 0;
+        *self.claimed.get_mut() = 0;
+
+        unsafe {
+            ptr::slice_from_raw_parts_mut(self.buffer.as_ptr(), len).drop_in_place();
+        }
+    }
+}
+/// Waits a moment before retrying an atomic operation. With `std`, this lets
+/// other threads run, so a push we're waiting on can finish even when there
+/// are more threads than cores.
+fn backoff() {
+    #[cfg(any(feature = "std", test))]
+    std::thread::yield_now();
+    #[cfg(not(any(feature = "std", test)))]
+    core::hint::spin_loop();
+}
+
+impl<T> Default for SyncArenaVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T> Drop for SyncArenaVec<T> {
+    fn drop(&mut self) {
+        self.clear();
+
+        unsafe {
+            Os::decommit(self.buffer.cast(), *self.committed_memory.get_mut());
+            Os::dereserve(self.buffer.cast(), self.reserved_memory);
+        }
+    }
+}
+impl<T> Deref for SyncArenaVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::SyncArenaVec, std::thread};
+
+    #[test]
+    fn concurrent_push() {
+        const THREADS: usize = 8;
+        const PUSHES: usize = 5_000;
+
+        let vec = SyncArenaVec::new();
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let vec = &vec;
+                scope.spawn(move || {
+                    for i in 0..PUSHES {
+                        vec.push(thread * PUSHES + i);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(vec.len(), THREADS * PUSHES);
+
+        let mut values = vec.to_vec();
+        values.sort_unstable();
+        assert!(values.into_iter().eq(0..THREADS * PUSHES));
+    }
+}
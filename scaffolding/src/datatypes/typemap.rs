@@ -17,7 +17,8 @@ use {
 /// Stores a single instance for some number of types. This acts like a
 /// [`std::collections::HashMap`], except the keys are types and the values are
 /// instances of those types. This type uses [`TypeId`]s, which are already
-/// type hashes, so it doesn't perform any hashing itself.
+/// type hashes, so it doesn't perform any hashing itself (though the
+/// `mix-type-ids` feature mixes their bits a little first).
 ///
 /// Note that types can't be removed from a [`TypeMap`] after they're inserted.
 /// This implementation allows the typemap to use an arena allocator internally,
@@ -30,6 +31,15 @@ use {
 /// - Typemaps will automatically reallocate with twice as many entries and
 ///   twice as much storage whenever [`TypeMap::insert`] is called and the
 ///   typemap is full.
+///
+/// # Pointer Invalidation
+/// Reallocating moves every value in the typemap. References from
+/// [`TypeMap::get`] and [`TypeMap::get_mut`] borrow the typemap, so the
+/// compiler won't let them outlive an insert, but **pointers from
+/// [`TypeMap::get_raw`] dangle after any [`TypeMap::insert`] or
+/// [`TypeMap::resize`]**. Don't hold onto raw pointers across those calls;
+/// look the value up again instead. [`TypeMap::modify`] is a convenient way
+/// to make a short-lived change to a value.
 pub struct TypeMap {
     /// A list of [`TypeMapEntry`]s, for every type that's been inserted into
     /// the [`TypeMap`].
@@ -120,11 +130,26 @@ impl TypeMap {
         }
     }
 
-    pub fn contains<T: Any>(&self) -> bool {
-        let type_id = PubTypeId::of::<T>();
-        let idx = type_id.val.0 as usize % self.entries.len();
+    /// Make room for at least `additional_entries` more types and
+    /// `additional_storage` more bytes of values, so that many inserts won't
+    /// have to reallocate. This reallocates at most once. Values are aligned
+    /// in storage, so `additional_storage` should include room for padding.
+    pub fn reserve(&mut self, additional_entries: usize, additional_storage: usize) {
+        let entry_capacity = self
+            .available_entries()
+            .max(self.num_entries + additional_entries);
+        let storage_capacity = self
+            .storage_capacity()
+            .max(self.used_storage + additional_storage);
+
+        if entry_capacity != self.available_entries() || storage_capacity != self.storage_capacity()
+        {
+            self.resize(entry_capacity, storage_capacity);
+        }
+    }
 
-        unsafe { self.entries.get_unchecked(idx).is_some() }
+    pub fn contains<T: Any>(&self) -> bool {
+        self._get(PubTypeId::of::<T>()).is_some()
     }
 
     pub fn get<T: Any>(&self) -> Option<&T> {
@@ -133,12 +158,47 @@ impl TypeMap {
             None => None,
         }
     }
+    /// Get the value of type `T`, or `fallback` if there isn't one. This is
+    /// usually used with a `static` default, so call sites don't have to
+    /// write `get().unwrap_or(&DEFAULT)`:
+    ///
+    /// ```
+    /// # use scaffolding::datatypes::TypeMap;
+    /// static DEFAULT_PORT: u16 = 8080;
+    ///
+    /// let mut map = TypeMap::new(1, 2);
+    /// assert_eq!(*map.get_or(&DEFAULT_PORT), 8080);
+    /// map.insert(3000_u16);
+    /// assert_eq!(*map.get_or(&DEFAULT_PORT), 3000);
+    /// ```
+    pub fn get_or<'a, T: Any>(&'a self, fallback: &'a T) -> &'a T {
+        self.get().unwrap_or(fallback)
+    }
     pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
         match self._get(PubTypeId::of::<T>()) {
             Some(ptr) => unsafe { Some(&mut *ptr.cast()) },
             None => None,
         }
     }
+    /// Change the value of type `T` with `f`, if there is one, and return
+    /// what `f` returns. The value is only borrowed for the duration of `f`,
+    /// so, unlike a reference from [`TypeMap::get_mut`], it's impossible to
+    /// accidentally keep it around while inserting:
+    ///
+    /// ```compile_fail
+    /// # use scaffolding::datatypes::TypeMap;
+    /// let mut map = TypeMap::default();
+    /// map.insert(1_u32);
+    /// let val: &mut u32 = map.get_mut().unwrap();
+    /// map.insert(1_u64); // Error: `map` is still mutably borrowed
+    /// *val += 1;
+    /// ```
+    pub fn modify<T: Any, R>(&mut self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.get_mut().map(f)
+    }
+    /// Get a raw pointer to the value with the given type ID. The pointer is
+    /// invalidated by the next [`TypeMap::insert`] or [`TypeMap::resize`];
+    /// see [the type-level docs](TypeMap#pointer-invalidation).
     pub fn get_raw(&self, type_id: PubTypeId) -> Option<NonNull<()>> {
         self._get(type_id)
             .map(|ptr| unsafe { NonNull::new_unchecked(ptr.cast()) })
@@ -194,9 +254,9 @@ impl TypeMap {
         }
 
         let type_id = PubTypeId::of::<T>();
-        let idx = type_id.val.0 as usize % self.entries.len();
+        let idx = slot(type_id, self.entries.len());
 
-        // SAFETY: The idx is the typeid % self.entries.len(), so we know it's in-bounds
+        // SAFETY: `slot` always returns an index less than `self.entries.len()`
         let existing_entry = unsafe { self.entries.get_unchecked_mut(idx) };
 
         match existing_entry {
@@ -289,8 +349,8 @@ impl TypeMap {
     fn copy_entry(&mut self, mut entry: TypeMapEntry) {
         entry.collision_slot = None;
 
-        let idx = entry.type_id.val.0 as usize % self.entries.len();
-        // SAFETY: The idx is the typeid % self.entries.len(), so we know it's in-bounds
+        let idx = slot(entry.type_id, self.entries.len());
+        // SAFETY: `slot` always returns an index less than `self.entries.len()`
         let existing_entry = unsafe { self.entries.get_unchecked_mut(idx) };
 
         match existing_entry {
@@ -327,7 +387,7 @@ impl TypeMap {
     }
 
     fn _get(&self, type_id: PubTypeId) -> Option<*mut u8> {
-        let idx = type_id.val.0 as usize % self.entries.len();
+        let idx = slot(type_id, self.entries.len());
         let entry = unsafe { self.entries.get_unchecked(idx).as_ref() };
 
         match entry {
@@ -363,6 +423,27 @@ impl Drop for TypeMap {
     }
 }
 
+/// The index of the entry slot a type goes in, in a typemap with `len` slots.
+///
+/// [`TypeId`]s are already hashes, so by default the slot is just the type
+/// ID's low word modulo `len`. With the `mix-type-ids` feature, the low word
+/// is mixed with a Fibonacci hash first, which spreads out type IDs that
+/// happen to share a remainder, so there are fewer collisions to walk
+/// through.
+#[inline(always)]
+fn slot(type_id: PubTypeId, len: usize) -> usize {
+    #[cfg(feature = "mix-type-ids")]
+    let raw = {
+        // 2^64 / the golden ratio
+        let mixed = type_id.val.0.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        mixed ^ (mixed >> 32)
+    };
+    #[cfg(not(feature = "mix-type-ids"))]
+    let raw = type_id.val.0;
+
+    raw as usize % len
+}
+
 /// Identical to [`TypeId`], except its value is public. Because it stores the same data, this
 /// type can be safely transmuted to/from a regular [`TypeId`], allowing access to its raw value.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -481,4 +562,90 @@ mod tests {
         assert_eq!(store.available_entries(), 4);
         assert_eq!(store.storage_capacity(), 200);
     }
+
+    #[test]
+    fn modify() {
+        let mut store = TypeMap::new(1, 8);
+        store.insert(SomeOtherType { val: 1 });
+
+        let old = store.modify(|val: &mut SomeOtherType| mem::replace(&mut val.val, 2));
+        assert_eq!(old, Some(1));
+        assert_eq!(store.modify(|_: &mut SomeEnum| ()), None);
+
+        // This resizes the typemap, which would invalidate a reference held
+        // from before it
+        store.insert(SomeEnum::Idk);
+        store.modify(|val: &mut SomeOtherType| val.val += 1);
+        assert_eq!(store.get::<SomeOtherType>().unwrap().val, 3);
+    }
+
+    #[test]
+    fn many_types() {
+        struct Numbered<const N: usize>(usize);
+
+        macro_rules! for_each_number {
+            ($mac:ident, $store:ident) => {
+                $mac!($store; 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31)
+            };
+        }
+        macro_rules! insert {
+            ($store:ident; $($n:literal)*) => { $($store.insert(Numbered::<$n>($n));)* };
+        }
+        macro_rules! check {
+            ($store:ident; $($n:literal)*) => { $(
+                assert!($store.contains::<Numbered<$n>>());
+                assert_eq!($store.get::<Numbered<$n>>().unwrap().0, $n);
+            )* };
+        }
+
+        // Lots of types in a small map, so there are plenty of collisions
+        let mut store = TypeMap::new(4, 64);
+        for_each_number!(insert, store);
+        assert_eq!(store.num_entries(), 32);
+        for_each_number!(check, store);
+        assert!(!store.contains::<Numbered<32>>());
+        assert!(store.get::<Numbered<32>>().is_none());
+    }
+
+    #[test]
+    fn get_or() {
+        static FALLBACK: SomeOtherType = SomeOtherType { val: -1 };
+
+        let mut store = TypeMap::new(2, 16);
+        store.insert(SomeEnum::Idk);
+        assert_eq!(store.get_or(&FALLBACK).val, -1);
+        assert_eq!(*store.get_or(&SomeEnum::Variant), SomeEnum::Idk);
+
+        store.insert(SomeOtherType { val: 7 });
+        assert_eq!(store.get_or(&FALLBACK).val, 7);
+    }
+
+    #[test]
+    fn reserve() {
+        let mut store = TypeMap::new(1, 8);
+        store.insert(0_u8);
+
+        store.reserve(10, 256);
+        assert_eq!(store.available_entries(), 11);
+        assert_eq!(store.storage_capacity(), 257);
+
+        // Reserving space that's already there doesn't reallocate
+        store.reserve(5, 100);
+        assert_eq!(store.available_entries(), 11);
+
+        store.insert(0_u16);
+        store.insert(0_u32);
+        store.insert(0_u64);
+        store.insert(0_u128);
+        store.insert(0_i8);
+        store.insert(0_i16);
+        store.insert(0_i32);
+        store.insert(0_i64);
+        store.insert(0_i128);
+        store.insert(0_usize);
+        assert_eq!(store.num_entries(), 11);
+        assert_eq!(store.available_entries(), 11);
+        assert_eq!(store.storage_capacity(), 257);
+        assert_eq!(store.get::<u128>(), Some(&0));
+    }
 }
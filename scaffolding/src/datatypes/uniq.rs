@@ -2,7 +2,7 @@ use {
     super::ArenaVec,
     core::{
         cell::{Cell, UnsafeCell},
-        mem, slice,
+        mem, ptr, slice,
     },
 };
 
@@ -62,40 +62,70 @@ impl Default for Uniq {
 impl Uniq {
     /// Create a [`Uniq`] that can store `cap` values.
     pub fn with_capacity(cap: usize) -> Self {
-        let entries = ArenaVec::with_capacity(mem::size_of::<Option<UniqEntry>>() * cap);
-        (0..cap).for_each(|_| {
-            entries.push(None);
-        });
-
         Self {
             data: ArenaVec::default(),
-            entries: UnsafeCell::new(entries),
+            entries: UnsafeCell::new(Self::empty_slots(cap)),
             used_entries: Cell::new(0),
         }
     }
+    fn empty_slots(cap: usize) -> ArenaVec<Option<UniqEntry>> {
+        let entries = ArenaVec::with_capacity(cap);
+        (0..cap).for_each(|_| {
+            entries.push(None);
+        });
+        entries
+    }
 
     /// Get a cached value with a [`UniqKey`], or supply a default value if
     /// there isn't one cached.
     #[allow(clippy::mut_from_ref)]
     pub fn get<T>(&self, key: UniqKey, default: impl FnOnce() -> T) -> &mut T {
+        self.get_inner(key, None, default)
+    }
+    /// Like [`Uniq::get`], but if the value isn't cached yet, it's cached with
+    /// a tag. Every value with the same tag can be removed at once with
+    /// [`Uniq::reset_tag`].
+    #[allow(clippy::mut_from_ref)]
+    pub fn get_tagged<T>(
+        &self,
+        key: UniqKey,
+        tag: &'static str,
+        default: impl FnOnce() -> T,
+    ) -> &mut T {
+        self.get_inner(key, Some(tag), default)
+    }
+    #[allow(clippy::mut_from_ref)]
+    fn get_inner<T>(
+        &self,
+        key: UniqKey,
+        tag: Option<&'static str>,
+        default: impl FnOnce() -> T,
+    ) -> &mut T {
         let entries = unsafe { &mut *self.entries.get() };
         let raw_key = key.0;
 
-        match self.idx_of(key) {
+        // Growing moves entries around, so it has to happen before we find
+        // where the new entry goes
+        let mut idx = self.idx_of(raw_key);
+        if !matches!(idx, UniqIndex::Exact(_)) && self.grow_if_full() {
+            idx = self.idx_of(raw_key);
+        }
+
+        match idx {
             UniqIndex::Exact(idx) => {
                 let entry = entries[idx].as_ref().unwrap();
                 unsafe { &mut *entry.val.cast() }
             }
             UniqIndex::Collision(last_idx) => {
-                let entry_idx = self.next_idx();
-                self.insert(entry_idx, raw_key, default());
+                let entry_idx = self.free_idx();
+                self.insert(entry_idx, raw_key, tag, default());
                 entries[last_idx].as_mut().unwrap().collision_slot = Some(entry_idx);
 
                 let entry = entries[entry_idx].as_ref().unwrap();
                 unsafe { &mut *entry.val.cast() }
             }
             UniqIndex::None(entry_idx) => {
-                self.insert(entry_idx, raw_key, default());
+                self.insert(entry_idx, raw_key, tag, default());
 
                 let entry = entries[entry_idx].as_ref().unwrap();
                 unsafe { &mut *entry.val.cast() }
@@ -109,32 +139,74 @@ impl Uniq {
         self.get(key, Default::default)
     }
 
-    fn insert<T>(&self, idx: usize, key: usize, val: T) {
+    fn insert<T>(&self, idx: usize, key: usize, tag: Option<&'static str>, val: T) {
         let type_size = mem::size_of::<T>();
 
+        // Pad `data` so the value is aligned. `data`'s buffer starts on a
+        // page boundary, so aligning the index aligns the pointer.
+        let align = mem::align_of::<T>();
+        while !self.data.len().is_multiple_of(align) {
+            self.data.push(0);
+        }
+
         let start_idx = self.data.len();
         let bytes = unsafe { slice::from_raw_parts(&val as *const T as *const u8, type_size) };
         self.data.extend_from_slice(bytes);
+        // The value now lives in `data`, and is dropped from there
+        mem::forget(val);
 
         let entries = unsafe { &mut *self.entries.get() };
         entries[idx] = Some(UniqEntry {
             key,
-            val: &self.data[start_idx] as *const u8 as *mut u8,
+            val: self.data.as_ptr().wrapping_add(start_idx) as *mut u8,
             collision_slot: None,
+            tag,
+            drop: drop_entry::<T>,
         });
         self.used_entries.set(self.used_entries.get() + 1);
     }
 
-    fn idx_of(&self, key: UniqKey) -> UniqIndex {
+    /// Remove every cached value, running their destructors. After this, the
+    /// next [`Uniq::get`] for any key will use its default value again.
+    pub fn clear(&mut self) {
+        let entries = self.entries.get_mut();
+        for slot in entries.iter_mut() {
+            if let Some(entry) = slot.take() {
+                (entry.drop)(entry.val);
+            }
+        }
+
+        self.used_entries.set(0);
+        self.data.clear();
+    }
+    /// Remove every value that was cached with the given tag by
+    /// [`Uniq::get_tagged`], running their destructors. The next
+    /// [`Uniq::get_tagged`] for any of their keys will use its default value
+    /// again. Other values are kept.
+    pub fn reset_tag(&mut self, tag: &str) {
+        let cap = self.entries.get_mut().len();
+        let old_entries = mem::replace(self.entries.get_mut(), Self::empty_slots(cap));
+        self.used_entries.set(0);
+
+        for entry in old_entries.into_iter().flatten() {
+            if entry.tag == Some(tag) {
+                (entry.drop)(entry.val);
+            } else {
+                self.reinsert(entry);
+            }
+        }
+    }
+
+    fn idx_of(&self, key: usize) -> UniqIndex {
         let entries = unsafe { &mut *self.entries.get() };
 
-        let mut idx = key.0 % entries.len();
+        let mut idx = key % entries.len();
         loop {
             let Some(entry) = entries[idx].as_mut() else {
                 return UniqIndex::None(idx);
             };
 
-            if entry.key == key.0 {
+            if entry.key == key {
                 return UniqIndex::Exact(idx);
             }
 
@@ -144,33 +216,53 @@ impl Uniq {
             idx = new_idx;
         }
     }
-    fn next_idx(&self) -> usize {
+    /// Doubles the number of slots if they're all used, and returns if it did.
+    fn grow_if_full(&self) -> bool {
         let entries = unsafe { &mut *self.entries.get() };
 
         if self.used_entries.get() == entries.len() {
             let cap = entries.len() * 2;
-            let mut new_entries =
-                ArenaVec::with_capacity(mem::size_of::<Option<UniqEntry>>() * cap);
+            let mut new_entries = ArenaVec::with_capacity(cap);
             (0..cap).for_each(|_| {
                 new_entries.push(None);
             });
 
             mem::swap(entries, &mut new_entries);
+            self.used_entries.set(0);
 
-            new_entries.into_iter().flatten().for_each(|entry| {
-                match self.idx_of(UniqKey(entry.key)) {
-                    UniqIndex::None(idx) => {
-                        entries[idx] = Some(entry);
-                    }
-                    UniqIndex::Collision(collision_idx) => {
-                        let entry_idx = self.next_idx();
-                        entries[entry_idx] = Some(entry);
-                        entries[collision_idx].as_mut().unwrap().collision_slot = Some(entry_idx);
-                    }
-                    UniqIndex::Exact(_) => unreachable!(),
-                }
-            });
+            new_entries
+                .into_iter()
+                .flatten()
+                .for_each(|entry| self.reinsert(entry));
+
+            true
+        } else {
+            false
         }
+    }
+    /// Put an existing entry back into the slots, after they've been
+    /// replaced. There must be a free slot for it.
+    fn reinsert(&self, mut entry: UniqEntry) {
+        let entries = unsafe { &mut *self.entries.get() };
+        entry.collision_slot = None;
+
+        match self.idx_of(entry.key) {
+            UniqIndex::None(idx) => {
+                entries[idx] = Some(entry);
+            }
+            UniqIndex::Collision(collision_idx) => {
+                let entry_idx = self.free_idx();
+                entries[entry_idx] = Some(entry);
+                entries[collision_idx].as_mut().unwrap().collision_slot = Some(entry_idx);
+            }
+            UniqIndex::Exact(_) => unreachable!(),
+        }
+        self.used_entries.set(self.used_entries.get() + 1);
+    }
+    /// The index of an empty slot. There must be one; see
+    /// [`Uniq::grow_if_full`].
+    fn free_idx(&self) -> usize {
+        let entries = unsafe { &*self.entries.get() };
 
         entries
             .iter()
@@ -180,6 +272,11 @@ impl Uniq {
             .0
     }
 }
+impl Drop for Uniq {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
 unsafe impl Send for Uniq {}
 
 /// A key for accessing a cached value from a [`Uniq`]. You can make a
@@ -213,6 +310,15 @@ struct UniqEntry {
     key: usize,
     val: *mut u8,
     collision_slot: Option<usize>,
+    /// The tag this entry was cached with. See [`Uniq::get_tagged`].
+    tag: Option<&'static str>,
+    /// Runs the destructor for the value at `val`.
+    drop: fn(*mut u8),
+}
+
+/// The [`UniqEntry::drop`] function for a value of type `T`.
+fn drop_entry<T>(val: *mut u8) {
+    unsafe { ptr::drop_in_place(val.cast::<T>()) }
 }
 
 /// Generates a [`UniqKey`] based on the column, line, and file where the macro
@@ -263,6 +369,7 @@ mod tests {
         super::Uniq,
         crate::{self as scaffolding, datatypes::uniq::UniqKey},
         core::hash::Hash,
+        std::{cell::Cell, rc::Rc},
     };
 
     #[allow(dead_code)]
@@ -314,4 +421,90 @@ mod tests {
         }
         assert_eq!(breaks(), breaks());
     }
+
+    /// Counts how many times it's been dropped.
+    struct DropCounter(Rc<Cell<usize>>);
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn clear() {
+        let drops = Rc::new(Cell::new(0));
+        let mut uniq = Uniq::default();
+
+        // Enough values to make the uniq grow
+        for i in 0..8 {
+            uniq.get(uniq_key!(i), || DropCounter(drops.clone()));
+        }
+        add_one_and_check(&mut uniq, 1);
+        assert_eq!(drops.get(), 0);
+
+        uniq.clear();
+        assert_eq!(drops.get(), 8);
+
+        // Everything starts fresh after clearing
+        add_one_and_check(&mut uniq, 1);
+    }
+
+    #[test]
+    fn reset_tag() {
+        let drops = Rc::new(Cell::new(0));
+        let mut uniq = Uniq::default();
+
+        // Caches `i` tagged with if it's even or odd, then multiplies the
+        // cached value by `scale`
+        fn get(uniq: &Uniq, i: usize, drops: &Rc<Cell<usize>>, scale: usize) -> usize {
+            let tag = if i.is_multiple_of(2) { "even" } else { "odd" };
+            let cached = uniq.get_tagged(uniq_key!(i), tag, || (i, DropCounter(drops.clone())));
+            cached.0 *= scale;
+            cached.0
+        }
+
+        // Enough values to make the uniq grow
+        for i in 0..6 {
+            get(&uniq, i, &drops, 10);
+        }
+        add_one_and_check(&mut uniq, 1);
+
+        uniq.reset_tag("even");
+        assert_eq!(drops.get(), 3);
+
+        for i in 0..6_usize {
+            let expected = if i.is_multiple_of(2) { i } else { i * 10 };
+            assert_eq!(get(&uniq, i, &drops, 1), expected);
+        }
+        // Untagged values are kept too
+        add_one_and_check(&mut uniq, 2);
+    }
+
+    #[test]
+    fn values_are_aligned() {
+        #[repr(align(8))]
+        #[derive(Debug, PartialEq)]
+        struct Aligned(u8);
+
+        let uniq = Uniq::default();
+        assert_eq!(*uniq.get(uniq_key!(), || 7_u8), 7);
+        let aligned = uniq.get(uniq_key!(), || Aligned(42));
+        assert!((aligned as *const Aligned).is_aligned());
+        assert_eq!(*aligned, Aligned(42));
+
+        let wide: &mut u64 = uniq.get(uniq_key!(), || u64::MAX);
+        assert!((wide as *const u64).is_aligned());
+        assert_eq!(*wide, u64::MAX);
+    }
+
+    #[test]
+    fn drop_runs_destructors() {
+        let drops = Rc::new(Cell::new(0));
+        let uniq = Uniq::default();
+        uniq.get(uniq_key!(), || DropCounter(drops.clone()));
+        assert_eq!(drops.get(), 0);
+
+        drop(uniq);
+        assert_eq!(drops.get(), 1);
+    }
 }
@@ -63,16 +63,21 @@ impl<T: Default + Reset> Warehouse<T> {
     /// returning the type to the [`Warehouse`]. See the type-level docs for
     /// more info.
     pub fn take_instance(&self) -> T {
-        let storage = unsafe { &mut *self.storage.get() };
-        storage.remove(0).unwrap_or_default()
+        // Safety: Warehouses aren't `Sync`, and the only other borrow of
+        // `storage` is in `return_instance`. Neither borrow outlives its
+        // method or runs user code while it's alive, so they never overlap.
+        let instance = unsafe { (*self.storage.get()).pop() };
+        instance.unwrap_or_default()
     }
 
     /// Return a taken instance of `T` to the [`Warehouse`].
     /// [`WarehouseValue`]s call this  method automatically when dropped.
     pub fn return_instance(&self, mut val: T) {
-        let storage = unsafe { &*self.storage.get() };
+        // Reset before borrowing `storage`, in case `reset` uses this
+        // warehouse
         val.reset();
-        storage.push(val);
+        // Safety: See `take_instance`
+        unsafe { (*self.storage.get()).push(val) };
     }
 }
 
@@ -189,3 +194,37 @@ mod std_impls {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleaved_instances() {
+        let warehouse: Warehouse<ArenaVec<u32>> = Warehouse::default();
+
+        let a = warehouse.get_instance();
+        let b = warehouse.get_instance();
+        a.push(1);
+        b.push(2);
+        drop(a);
+
+        // Reuses `a`'s instance, which was reset when it was returned
+        let c = warehouse.get_instance();
+        assert!(c.is_empty());
+        let d = warehouse.get_instance();
+
+        drop(b);
+        drop(d);
+        let taken = warehouse.take_instance();
+        warehouse.return_instance(taken);
+        drop(c);
+
+        let instances = [
+            warehouse.take_instance(),
+            warehouse.take_instance(),
+            warehouse.take_instance(),
+        ];
+        assert!(instances.iter().all(ArenaVec::is_empty));
+    }
+}
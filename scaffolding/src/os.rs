@@ -9,6 +9,11 @@ use core::{alloc::Layout, ffi::c_void, ptr::NonNull};
 pub trait OsTrait {
     /// Which OS this program is running on.
     const TYPE: OsType;
+    /// If [`OsTrait::reserve`] only reserves virtual addresses, without
+    /// allocating any memory. When this is false, reserved memory is
+    /// allocated immediately, so reserving large amounts of memory is
+    /// expensive.
+    const LAZY_RESERVE: bool = true;
 
     /// The size of a single memory page in this OS.
     fn page_size() -> usize;
@@ -30,6 +35,30 @@ pub trait OsTrait {
     /// `ptr` must point to a valid region of memory that was reserved with
     /// [`OsTrait::reserve`].
     unsafe fn commit(ptr: NonNull<c_void>, amount: usize);
+    /// The size of a huge page in this OS, or `None` if huge pages aren't
+    /// supported.
+    fn huge_page_size() -> Option<usize> {
+        None
+    }
+    /// Reserve `amount` bytes of virtual memory backed by huge pages, which
+    /// reduces TLB pressure for very large buffers. `amount` must be a
+    /// multiple of [`OsTrait::huge_page_size`].
+    ///
+    /// This returns `None` if huge pages aren't supported or none are
+    /// available; callers should fall back to [`OsTrait::reserve`].
+    fn reserve_huge(_amount: usize) -> Option<NonNull<c_void>> {
+        None
+    }
+    /// Commit `amount` bytes of memory reserved with
+    /// [`OsTrait::reserve_huge`]. `amount` must be a multiple of
+    /// [`OsTrait::huge_page_size`].
+    ///
+    /// # Safety
+    /// `ptr` must point to a valid region of memory that was reserved with
+    /// [`OsTrait::reserve_huge`].
+    unsafe fn commit_huge(ptr: NonNull<c_void>, amount: usize) {
+        unsafe { Self::commit(ptr, amount) }
+    }
     /// Allocate memory for the given layout.
     fn allocate(layout: Layout) -> Option<NonNull<c_void>>;
 
@@ -61,16 +90,29 @@ pub enum OsType {
     Linux,
     MacOS,
     Windows,
+    /// The fallback implementation from the `portable` feature, which works
+    /// on any target with an allocator.
+    Portable,
 }
 
 // OS implementations
 
-#[cfg(target_family = "unix")]
+#[cfg(all(target_family = "unix", not(feature = "portable")))]
 mod unix_common;
 
-#[cfg_attr(target_os = "linux", path = "os/linux.rs")]
-#[cfg_attr(target_os = "macos", path = "os/mac.rs")]
-#[cfg_attr(target_os = "windows", path = "os/windows.rs")]
+#[cfg_attr(feature = "portable", path = "os/portable.rs")]
+#[cfg_attr(
+    all(target_os = "linux", not(feature = "portable")),
+    path = "os/linux.rs"
+)]
+#[cfg_attr(
+    all(target_os = "macos", not(feature = "portable")),
+    path = "os/mac.rs"
+)]
+#[cfg_attr(
+    all(target_os = "windows", not(feature = "portable")),
+    path = "os/windows.rs"
+)]
 mod os_impl;
 
 #[doc(inline)]
@@ -79,8 +121,15 @@ pub use os_impl::Os;
 
 use crate::utils;
 
-#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
-compile_error!("Scaffolding isn't currently supported for the operating system you're building for. Feel free to comment on or open an issue on GitHub.");
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "windows",
+    feature = "portable"
+)))]
+compile_error!("Scaffolding isn't currently supported for the operating system you're building for. You can enable the `portable` feature to use a fallback that works anywhere with an allocator. Feel free to comment on or open an issue on GitHub.");
+#[cfg(all(feature = "portable", feature = "os-allocator"))]
+compile_error!("The `portable` OS implementation uses the global allocator, so it can't be the global allocator.");
 
 /// A basic global allocator using the OS' allocate and deallocate functions.
 ///
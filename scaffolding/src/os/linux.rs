@@ -1,6 +1,11 @@
 use {
     super::{unix_common, OsTrait, OsType},
-    core::{alloc::Layout, ffi::c_void, ptr::NonNull},
+    core::{
+        alloc::Layout,
+        ffi::c_void,
+        ptr::{self, NonNull},
+    },
+    libc::{mmap, MAP_ANONYMOUS, MAP_FAILED, MAP_HUGETLB, MAP_PRIVATE, PROT_NONE},
 };
 
 pub struct Os;
@@ -18,6 +23,38 @@ impl OsTrait for Os {
     unsafe fn commit(ptr: NonNull<c_void>, amount: usize) {
         unix_common::commit(ptr, amount)
     }
+    fn huge_page_size() -> Option<usize> {
+        // The default huge page size on x86-64 and (4KiB-page) ARM64, which
+        // is what `MAP_HUGETLB` uses
+        if cfg!(any(target_arch = "x86_64", target_arch = "aarch64")) {
+            Some(2 * 1024 * 1024)
+        } else {
+            None
+        }
+    }
+    fn reserve_huge(amount: usize) -> Option<NonNull<c_void>> {
+        Self::huge_page_size()?;
+
+        // Without `MAP_NORESERVE`, Linux takes the huge pages from its pool
+        // now, so this fails cleanly if there aren't enough, instead of
+        // crashing when the memory is first touched
+        let ptr = unsafe {
+            mmap(
+                ptr::null_mut(),
+                amount,
+                PROT_NONE,
+                MAP_PRIVATE | MAP_ANONYMOUS | MAP_HUGETLB,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == MAP_FAILED {
+            return None;
+        }
+
+        NonNull::new(ptr)
+    }
     fn allocate(layout: Layout) -> Option<NonNull<c_void>> {
         unix_common::allocate(layout)
     }
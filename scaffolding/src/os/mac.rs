@@ -6,7 +6,7 @@ use {
 pub struct Os;
 
 impl OsTrait for Os {
-    const TYPE: OsType = OsType::Linux;
+    const TYPE: OsType = OsType::MacOS;
 
     fn page_size() -> usize {
         unix_common::page_size()
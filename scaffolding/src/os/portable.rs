@@ -0,0 +1,135 @@
+//! A fallback [`OsTrait`] implementation that only uses the global allocator,
+//! for targets without virtual memory APIs (like WASM). Enabled with the
+//! `portable` feature.
+//!
+//! Without virtual memory, memory can't be reserved without also being
+//! allocated, so [`OsTrait::reserve`] allocates the whole reservation up front
+//! and [`OsTrait::commit`] does nothing. See [`OsTrait::LAZY_RESERVE`].
+//!
+//! This has two costs for [`ArenaVec`](crate::datatypes::ArenaVec):
+//! - Every arenavec allocates its whole reservation when it's created, even
+//!   if it stays empty. That's
+//!   [`DEFAULT_RESERVED_MEMORY`](crate::datatypes::ArenaVec::DEFAULT_RESERVED_MEMORY)
+//!   (1MiB) unless it was made with a different amount.
+//! - The reservation is a hard cap. Arenavecs hand out references to their
+//!   elements while they're still growing, so they can't move their buffer
+//!   to reallocate it; pushing past the reservation fails (or panics, for
+//!   the non-`try_` methods). Arenavecs that need to hold more than 1MiB
+//!   should be made with
+//!   [`ArenaVec::with_reserved_memory`](crate::datatypes::ArenaVec::with_reserved_memory).
+
+use {
+    super::{OsTrait, OsType},
+    alloc::alloc::{self as global, Layout},
+    core::{ffi::c_void, mem, ptr::NonNull},
+};
+
+/// The alignment of reserved memory. There aren't really pages without
+/// virtual memory, but this keeps reservations aligned for any type.
+const PAGE_SIZE: usize = 4096;
+
+pub struct Os;
+
+impl OsTrait for Os {
+    const TYPE: OsType = OsType::Portable;
+    const LAZY_RESERVE: bool = false;
+
+    fn page_size() -> usize {
+        PAGE_SIZE
+    }
+
+    fn reserve(amount: usize) -> Option<NonNull<c_void>> {
+        if amount == 0 {
+            // Nothing to allocate, but the pointer still has to be aligned
+            return NonNull::new(PAGE_SIZE as *mut c_void);
+        }
+
+        let layout = Layout::from_size_align(amount, PAGE_SIZE).ok()?;
+        NonNull::new(unsafe { global::alloc(layout) }.cast())
+    }
+    unsafe fn commit(_ptr: NonNull<c_void>, _amount: usize) {
+        // Reserved memory is already allocated
+    }
+    fn allocate(layout: Layout) -> Option<NonNull<c_void>> {
+        // `deallocate` isn't given the alignment, so it's stored in a header
+        // right before the allocation
+        let align = layout.align().max(mem::size_of::<usize>());
+        let full_layout = Layout::from_size_align(layout.size() + align, align).ok()?;
+
+        unsafe {
+            let base = global::alloc(full_layout);
+            if base.is_null() {
+                return None;
+            }
+            let ptr = base.add(align);
+            ptr.cast::<usize>().sub(1).write(align);
+
+            NonNull::new(ptr.cast())
+        }
+    }
+
+    unsafe fn dereserve(ptr: NonNull<c_void>, amount: usize) {
+        if amount != 0 {
+            unsafe {
+                global::dealloc(
+                    ptr.as_ptr().cast(),
+                    Layout::from_size_align_unchecked(amount, PAGE_SIZE),
+                )
+            }
+        }
+    }
+    unsafe fn decommit(_ptr: NonNull<c_void>, _amount: usize) {
+        // Memory is only freed when it's dereserved
+    }
+    unsafe fn deallocate(ptr: NonNull<c_void>, amount: usize) {
+        unsafe {
+            let align = ptr.as_ptr().cast::<usize>().sub(1).read();
+            let base = ptr.as_ptr().cast::<u8>().sub(align);
+
+            global::dealloc(
+                base,
+                Layout::from_size_align_unchecked(amount + align, align),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::Os,
+        crate::{datatypes::ArenaVec, os::OsTrait},
+        core::alloc::Layout,
+    };
+
+    #[test]
+    fn arenavec_without_virtual_memory() {
+        let mut vec = ArenaVec::new();
+        assert_eq!(
+            vec.reserved_memory(),
+            ArenaVec::<u64>::DEFAULT_RESERVED_MEMORY
+        );
+
+        for i in 0..10_000_u64 {
+            vec.push(i);
+        }
+        assert_eq!(vec.len(), 10_000);
+        assert!(vec.iter().copied().eq(0..10_000));
+
+        assert_eq!(vec.pop(), Some(9_999));
+        assert_eq!(vec.remove(0), Some(0));
+        vec.truncate(10);
+        assert_eq!(vec, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+        vec.clear();
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn overaligned_allocations() {
+        let layout = Layout::from_size_align(24, 64).unwrap();
+        let ptr = Os::allocate(layout).unwrap();
+        assert_eq!(ptr.as_ptr() as usize % 64, 0);
+        unsafe { Os::deallocate(ptr, layout.size()) };
+    }
+}
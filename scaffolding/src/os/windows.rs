@@ -42,6 +42,28 @@ impl OsTrait for Os {
             );
         }
     }
+    fn huge_page_size() -> Option<usize> {
+        match unsafe { GetLargePageMinimum() } {
+            0 => None,
+            size => Some(size),
+        }
+    }
+    fn reserve_huge(amount: usize) -> Option<NonNull<c_void>> {
+        // Large pages can't be committed later, so they're reserved and
+        // committed at once. This needs the `SeLockMemoryPrivilege`, and fails
+        // without it.
+        NonNull::new(unsafe {
+            VirtualAlloc(
+                ptr::null_mut(),
+                amount,
+                AllocationType::Reserve | AllocationType::Commit | AllocationType::LargePages,
+                MemoryProtection::ReadWrite.into(),
+            )
+        })
+    }
+    unsafe fn commit_huge(_ptr: NonNull<c_void>, _amount: usize) {
+        // Already committed by `reserve_huge`
+    }
     fn allocate(layout: Layout) -> Option<NonNull<c_void>> {
         NonNull::new(unsafe {
             VirtualAlloc(
@@ -109,6 +131,7 @@ bitflags! {
     bitflags AllocationType {
         Commit = 0x00001000,
         Reserve = 0x00002000,
+        LargePages = 0x20000000,
     }
 }
 bitflags! {
@@ -132,6 +155,7 @@ bitflags! {
 #[link(name = "kernel32")]
 extern "C" {
     fn GetSystemInfo(lpSystemInfo: *mut SystemInfo);
+    fn GetLargePageMinimum() -> usize;
     fn VirtualAlloc(
         lpAddress: *mut c_void,
         dwSize: usize,
@@ -1,8 +1,11 @@
 //! Defines the [`World`], and types that interact with it.
 
+pub mod builder;
 pub mod executable;
 pub mod executable_args;
+pub mod mutation;
 pub mod plugin;
+pub mod snapshot;
 
 use {
     crate::datatypes::{
@@ -10,6 +13,7 @@ use {
         uniq::Uniq,
         ArenaVec,
     },
+    alloc::{boxed::Box, vec::Vec},
     core::{
         any::Any,
         mem,
@@ -19,9 +23,12 @@ use {
     },
 };
 
+pub use builder::WorldBuilder;
 pub use executable::*;
 pub use executable_args::*;
+pub use mutation::{ModifySingleton, Mutation, ReversibleMutation, SetSingleton};
 pub use plugin::*;
+pub use snapshot::{RestoreError, Snapshot};
 
 pub struct Msg<M: 'static>(NonNull<M>);
 impl<M: 'static> Deref for Msg<M> {
@@ -42,12 +49,35 @@ impl<M: 'static> Msg<M> {
     }
 }
 
+/// A change to the [`World`] that was queued with [`World::queue_command`].
+pub type Command = Box<dyn FnOnce(&mut World)>;
+
 pub struct World {
     pub plugins: TypeMap,
     pub singletons: TypeMap,
     pub states: Uniq,
     pub msg_handlers: TypeMap,
     msg_buffer: ArenaVec<u8>,
+    /// Commands queued by [`World::queue_command`], which are applied by
+    /// [`World::process_msgs`].
+    commands: ArenaVec<Command>,
+    /// An empty buffer that [`World::apply_commands`] swaps with
+    /// [`World::commands`], so it doesn't have to reserve a new one every
+    /// time it applies them.
+    spare_commands: Option<ArenaVec<Command>>,
+    snapshotters: Vec<snapshot::Snapshotter>,
+    /// The type names of every loaded plugin, in the order they were loaded.
+    plugin_names: Vec<&'static str>,
+    /// The most times [`World::process_msgs`] will go back for messages that
+    /// were sent while it was handling messages. This stops message handlers
+    /// that keep sending each other messages from looping forever; any
+    /// messages left after this many rounds are handled by the next call.
+    pub max_msg_rounds: usize,
+    /// How many of the executables that are currently running take an
+    /// `&mut ExecutableArg`. [`SubExecutor`] uses this to catch nested
+    /// executables that could alias a mutable borrow.
+    #[cfg(debug_assertions)]
+    pub(crate) mutable_borrows: core::cell::Cell<usize>,
 }
 impl World {
     #[inline(always)]
@@ -60,21 +90,114 @@ impl World {
         singletons: usize,
         states: usize,
         msg_handlers: usize,
+    ) -> Self {
+        Self::from_maps(
+            TypeMap::new(plugins, 1_000),
+            TypeMap::new(singletons, 1_000_000),
+            Uniq::with_capacity(states),
+            TypeMap::new(msg_handlers, 1_000),
+        )
+    }
+    /// Build a [`WorldBuilder`], which adds a batch of singletons and plugins
+    /// to a new world with only one allocation for each of its maps.
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::new()
+    }
+    fn from_maps(
+        plugins: TypeMap,
+        singletons: TypeMap,
+        states: Uniq,
+        msg_handlers: TypeMap,
     ) -> Self {
         Self {
-            plugins: TypeMap::new(plugins, 1_000),
-            singletons: TypeMap::new(singletons, 1_000_000),
-            states: Uniq::with_capacity(states),
-            msg_handlers: TypeMap::new(msg_handlers, 1_000),
+            plugins,
+            singletons,
+            states,
+            msg_handlers,
             msg_buffer: ArenaVec::default(),
+            commands: ArenaVec::default(),
+            spare_commands: None,
+            snapshotters: Vec::new(),
+            plugin_names: Vec::new(),
+            max_msg_rounds: 100,
+            #[cfg(debug_assertions)]
+            mutable_borrows: core::cell::Cell::new(0),
         }
     }
 
+    /// Add a singleton to the world. If there's already a singleton of the
+    /// same type, it's replaced and dropped; see
+    /// [`World::try_add_singleton`] to catch that instead.
     pub fn add_singleton<S: Any>(&mut self, state: S) -> &mut Self {
         self.singletons.insert(state);
 
         self
     }
+    /// Add a singleton to the world, unless there's already a singleton of
+    /// the same type. In that case the existing singleton is kept, and
+    /// `state` is handed back in the error.
+    pub fn try_add_singleton<S: Any>(&mut self, state: S) -> Result<&mut Self, S> {
+        if self.singletons.contains::<S>() {
+            return Err(state);
+        }
+
+        Ok(self.add_singleton(state))
+    }
+
+    /// Add a singleton that will be saved by [`World::snapshot_singletons`]
+    /// and loaded by [`World::restore_singletons`].
+    pub fn add_snapshot_singleton<S: Snapshot + Any>(&mut self, state: S) -> &mut Self {
+        let snapshotter = snapshot::Snapshotter::of::<S>();
+        if !self
+            .snapshotters
+            .iter()
+            .any(|other| other.type_name == snapshotter.type_name)
+        {
+            self.snapshotters.push(snapshotter);
+        }
+
+        self.add_singleton(state)
+    }
+    /// Save every singleton added with [`World::add_snapshot_singleton`] to
+    /// bytes.
+    pub fn snapshot_singletons(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut singleton = Vec::new();
+
+        for snapshotter in &self.snapshotters {
+            singleton.clear();
+            (snapshotter.snapshot)(self, &mut singleton);
+
+            snapshot::write_chunk(&mut out, snapshotter.type_name.as_bytes());
+            snapshot::write_chunk(&mut out, &singleton);
+        }
+
+        out
+    }
+    /// Load singletons from bytes made by [`World::snapshot_singletons`].
+    ///
+    /// Singletons are matched up by type name, so the order they were added
+    /// in doesn't matter. If this fails partway through, singletons before
+    /// the error will have already been restored.
+    pub fn restore_singletons(&mut self, mut bytes: &[u8]) -> Result<(), RestoreError> {
+        while !bytes.is_empty() {
+            let type_name = snapshot::read_chunk(&mut bytes)?;
+            let singleton = snapshot::read_chunk(&mut bytes)?;
+
+            let snapshotter = self
+                .snapshotters
+                .iter()
+                .find(|snapshotter| snapshotter.type_name.as_bytes() == type_name)
+                .ok_or(RestoreError::UnknownSingleton)?;
+            let (restore, type_name) = (snapshotter.restore, snapshotter.type_name);
+
+            if !restore(self, singleton) {
+                return Err(RestoreError::InvalidSingleton(type_name));
+            }
+        }
+
+        Ok(())
+    }
 
     pub fn try_get_singleton<S: Any>(&self) -> Option<&S> {
         self.singletons.get()
@@ -90,6 +213,18 @@ impl World {
                 )
             )
     }
+    /// Get the singleton of type `S`, adding the value returned by `default`
+    /// first if it isn't in the world yet. `default` is only called if the
+    /// singleton needs to be added.
+    pub fn get_or_add_singleton<S: Any>(&mut self, default: impl FnOnce() -> S) -> &mut S {
+        if !self.singletons.contains::<S>() {
+            // Inserting can resize the typemap, so the reference has to be
+            // fetched afterwards
+            self.singletons.insert(default());
+        }
+
+        self.singletons.get_mut().unwrap()
+    }
     pub fn get_singleton_mut<S: Any>(&mut self) -> &mut S {
         self.singletons.get_mut().unwrap_or_else(||
         panic!(
@@ -103,6 +238,7 @@ impl World {
         if !self.plugins.contains::<P>() {
             plugin.load(self);
             self.plugins.insert(plugin);
+            self.plugin_names.push(core::any::type_name::<P>());
         }
 
         self
@@ -110,17 +246,62 @@ impl World {
     pub fn has_plugin<P: Plugin>(&self) -> bool {
         self.plugins.contains::<P>()
     }
+    /// The type names of every plugin in this [`World`], in the order they
+    /// were added.
+    pub fn loaded_plugins(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.plugin_names.iter().copied()
+    }
 
     /// Run an [`Executable`] with the data in this [`World`], then immediately
     /// apply any messages it sent.
     pub fn execute<'a, Args, E: Executable<'a, Args>>(&mut self, executable: E) -> E::Output {
-        let out = executable.execute(self);
+        let out = self.profile(core::any::type_name::<E>(), || executable.execute(self));
         self.process_msgs();
         out
     }
+    /// Run an [`ExecutableWithState`] with a mutable borrow of `state`, then
+    /// apply any messages it sent, like [`World::execute`]. Because the
+    /// executable only borrows the state, the state is handed back afterwards
+    /// along with the executable's output, so it can be threaded through
+    /// multiple executables.
+    pub fn execute_with_state_returning<State, Args, Output, E>(
+        &mut self,
+        mut state: State,
+        executable: E,
+    ) -> (Output, State)
+    where
+        E: for<'a> ExecutableWithState<'a, &'a mut State, Args, Output = Output>,
+    {
+        let out = executable.execute(&mut state, self);
+        self.process_msgs();
+        (out, state)
+    }
+    /// Run `f`, and record how long it took under `label` if the world has a
+    /// [`ProfileData`] singleton.
+    #[inline(always)]
+    pub fn profile<R>(&self, label: &'static str, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "std")]
+        if let Some(profile) = self.try_get_singleton::<ProfileData>() {
+            let start = std::time::Instant::now();
+            let out = f();
+            profile.record(label, start.elapsed());
+            return out;
+        }
+
+        #[cfg(not(feature = "std"))]
+        let _ = label;
+        f()
+    }
     /// Run an [`Executable`] with the data in this [`World`], but don't
     /// apply any messages it sent.
-    pub fn execute_immut<'a, Args, E: Executable<'a, Args>>(&self, executable: E) -> E::Output {
+    ///
+    /// This only accepts executables that take `&ExecutableArg`s, not
+    /// `&mut ExecutableArg`s (see [`ImmutableArgs`]), since nothing is
+    /// supposed to change the world through an `&World`.
+    pub fn execute_immut<'a, Args: ImmutableArgs, E: Executable<'a, Args>>(
+        &self,
+        executable: E,
+    ) -> E::Output {
         executable.execute(self)
     }
 
@@ -158,10 +339,45 @@ impl World {
         // if it has one
         mem::forget(msg);
     }
+    /// Queue a change to the [`World`], which will be applied the next time
+    /// messages are processed. This allows structural changes, like adding
+    /// singletons or plugins, from places that only have an `&World`, such as
+    /// executables.
+    pub fn queue_command(&self, command: impl FnOnce(&mut World) + 'static) {
+        self.commands.push(Box::new(command));
+    }
     pub fn add_msg_handler<M: 'static>(&mut self, handler: fn(&mut World, Msg<M>)) {
         self.msg_handlers.insert(handler);
     }
+    /// Handle every message sent with [`World::send_msg`], then apply every
+    /// command queued with [`World::queue_command`]. Messages and commands
+    /// sent while doing this are processed too, in the order they were sent,
+    /// for up to [`World::max_msg_rounds`] rounds.
     pub fn process_msgs(&mut self) {
+        for _ in 0..self.max_msg_rounds {
+            if self.msg_buffer.is_empty() && self.commands.is_empty() {
+                break;
+            }
+
+            self.handle_msgs();
+            self.apply_commands();
+        }
+    }
+    fn apply_commands(&mut self) {
+        if self.commands.is_empty() {
+            return;
+        }
+
+        // Commands can queue more commands, so they go in a separate buffer
+        // while these are applied
+        let spare = self.spare_commands.take().unwrap_or_default();
+        let mut commands = mem::replace(&mut self.commands, spare);
+        for command in commands.drain() {
+            command(self);
+        }
+        self.spare_commands = Some(commands);
+    }
+    fn handle_msgs(&mut self) {
         if self.msg_buffer.is_empty() {
             return;
         }
@@ -204,3 +420,209 @@ impl Default for World {
         Self::with_capacities(100, 1_000, 100, 100)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandQueue, Msg, Plugin, Singleton, World};
+
+    #[derive(Default)]
+    struct PluginA;
+    impl Plugin for PluginA {
+        fn load(&mut self, _: &mut World) {}
+    }
+    #[derive(Default)]
+    struct PluginB;
+    impl Plugin for PluginB {
+        fn load(&mut self, world: &mut World) {
+            world.add_plugin(PluginA);
+        }
+    }
+
+    #[test]
+    fn loaded_plugins() {
+        let mut world = World::new();
+        world.add_plugin(PluginB).add_plugin(PluginA);
+
+        let plugins: Vec<_> = world.loaded_plugins().collect();
+        assert_eq!(
+            plugins,
+            [
+                core::any::type_name::<PluginA>(),
+                core::any::type_name::<PluginB>()
+            ]
+        );
+    }
+
+    #[test]
+    fn execute_with_state_returning() {
+        let mut world = World::new();
+        world.add_singleton(2_u32);
+
+        fn step(counter: &mut u32, step: &Singleton<u32>) -> u32 {
+            *counter += **step;
+            *counter
+        }
+
+        let counter = 0_u32;
+        let (out, counter) = world.execute_with_state_returning(counter, step);
+        assert_eq!((out, counter), (2, 2));
+        let (out, counter) = world.execute_with_state_returning(counter, step);
+        assert_eq!((out, counter), (4, 4));
+
+        let (_, counter) = world.execute_with_state_returning(counter, |counter: &mut u32| {
+            *counter += 1;
+        });
+        assert_eq!(counter, 5);
+    }
+
+    #[test]
+    fn get_or_add_singleton() {
+        let mut world = World::new();
+
+        assert_eq!(*world.get_or_add_singleton(|| 1_u32), 1);
+        *world.get_or_add_singleton(|| 2_u32) += 10;
+        assert_eq!(
+            *world.get_or_add_singleton::<u32>(|| panic!("Singleton was re-added")),
+            11
+        );
+    }
+
+    #[test]
+    fn try_add_singleton() {
+        let mut world = World::new();
+
+        assert!(world.try_add_singleton(1_u32).is_ok());
+        assert_eq!(world.try_add_singleton(2_u32).err(), Some(2));
+        assert_eq!(*world.get_singleton::<u32>(), 1);
+
+        // `add_singleton` still overwrites
+        world.add_singleton(3_u32);
+        assert_eq!(*world.get_singleton::<u32>(), 3);
+    }
+
+    #[test]
+    fn builder() {
+        struct Numbered<const N: usize>(u64);
+        #[derive(Default)]
+        struct Counter;
+        impl Plugin for Counter {
+            fn load(&mut self, world: &mut World) {
+                *world.get_singleton_mut::<u32>() += 1;
+            }
+        }
+
+        macro_rules! for_each_number {
+            ($mac:ident, $builder:ident) => {
+                $mac!($builder; 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18)
+            };
+        }
+        macro_rules! add {
+            ($builder:ident; $($n:literal)*) => { $(let $builder = $builder.add_singleton(Numbered::<$n>($n));)* };
+        }
+        macro_rules! check {
+            ($world:ident; $($n:literal)*) => { $(
+                assert_eq!($world.get_singleton::<Numbered<$n>>().0, $n);
+            )* };
+        }
+
+        let builder = World::builder().add_plugin(Counter);
+        for_each_number!(add, builder);
+        let world = builder.add_singleton(0_u32).build();
+
+        // The maps were allocated once, at exactly the size they needed
+        assert_eq!(world.singletons.num_entries(), 20);
+        assert_eq!(world.singletons.available_entries(), 20);
+        assert_eq!(world.singletons.storage_capacity(), 1 + 19 * 15 + 7);
+        assert_eq!(world.plugins.available_entries(), 1);
+        for_each_number!(check, world);
+
+        // Plugins are loaded after the singletons are added
+        assert!(world.has_plugin::<Counter>());
+        assert_eq!(*world.get_singleton::<u32>(), 1);
+    }
+
+    #[test]
+    fn empty_builder() {
+        let mut world = World::builder().build();
+        assert!(world.try_get_singleton::<u32>().is_none());
+        assert!(!world.has_plugin::<PluginA>());
+
+        world.add_singleton(1_u32).add_plugin(PluginA);
+        assert_eq!(*world.get_singleton::<u32>(), 1);
+        assert!(world.has_plugin::<PluginA>());
+    }
+
+    #[test]
+    fn builder_with_only_singletons() {
+        let mut world = World::builder().add_singleton(1_u32).build();
+        assert!(!world.has_plugin::<PluginB>());
+
+        world.add_plugin(PluginB);
+        assert!(world.has_plugin::<PluginA>());
+        assert!(world.has_plugin::<PluginB>());
+        assert_eq!(*world.get_singleton::<u32>(), 1);
+    }
+
+    #[test]
+    fn queued_commands() {
+        let mut world = World::new();
+
+        world.execute(|commands: &CommandQueue| {
+            commands.queue(|world| {
+                world.add_singleton(42_u32);
+            });
+        });
+        assert_eq!(world.try_get_singleton::<u32>(), Some(&42));
+
+        // Applying commands reuses the same two buffers
+        let buffers = |world: &World| {
+            let mut buffers = [
+                world.commands.as_ptr(),
+                world.spare_commands.as_ref().unwrap().as_ptr(),
+            ];
+            buffers.sort();
+            buffers
+        };
+        let before = buffers(&world);
+        for value in 0..3_u32 {
+            world.queue_command(move |world| {
+                world.add_singleton(value);
+            });
+            world.process_msgs();
+            assert_eq!(world.try_get_singleton::<u32>(), Some(&value));
+        }
+        assert_eq!(buffers(&world), before);
+    }
+
+    struct First;
+    struct Second;
+    struct Forever;
+
+    #[test]
+    fn cascading_msgs() {
+        let mut world = World::new();
+        world.add_singleton(Vec::<&str>::new());
+        world.add_msg_handler(|world: &mut World, _: Msg<First>| {
+            world.get_singleton_mut::<Vec<&str>>().push("first");
+            world.send_msg(Second);
+        });
+        world.add_msg_handler(|world: &mut World, _: Msg<Second>| {
+            world.get_singleton_mut::<Vec<&str>>().push("second");
+        });
+
+        world.send_msg(First);
+        world.process_msgs();
+        assert_eq!(world.get_singleton::<Vec<&str>>(), &["first", "second"]);
+
+        // Handlers that keep sending messages are cut off
+        world.add_singleton(0_u32);
+        world.add_msg_handler(|world: &mut World, _: Msg<Forever>| {
+            *world.get_singleton_mut::<u32>() += 1;
+            world.send_msg(Forever);
+        });
+        world.max_msg_rounds = 5;
+        world.send_msg(Forever);
+        world.process_msgs();
+        assert_eq!(*world.get_singleton::<u32>(), 5);
+    }
+}
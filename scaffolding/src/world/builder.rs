@@ -0,0 +1,83 @@
+//! Defines [`WorldBuilder`].
+
+use {
+    super::{Plugin, World},
+    crate::datatypes::{typemap::TypeMap, uniq::Uniq},
+    alloc::{boxed::Box, vec::Vec},
+    core::{any::Any, mem},
+};
+
+/// Builds a [`World`] with a batch of singletons and plugins. Unlike adding
+/// them to a [`World`] one at a time, this adds up how much room they need
+/// first, then allocates the world's maps once at that size, so they never
+/// have to grow while the world is being set up.
+///
+/// Plugins can still add more to the world when they're loaded, which may
+/// grow its maps; only the singletons and plugins given to the builder are
+/// accounted for. See [`World::builder`].
+#[derive(Default)]
+pub struct WorldBuilder {
+    singletons: Vec<PendingInsert>,
+    plugins: Vec<PendingInsert>,
+}
+/// A value that will be inserted into the world by [`WorldBuilder::build`].
+struct PendingInsert {
+    /// How much storage the value could take up in a [`TypeMap`], including
+    /// padding to align it.
+    storage: usize,
+    insert: Box<dyn FnOnce(&mut World)>,
+}
+impl PendingInsert {
+    fn new<T: Any>(insert: impl FnOnce(&mut World) + 'static) -> Self {
+        Self {
+            storage: mem::size_of::<T>() + mem::align_of::<T>() - 1,
+            insert: Box::new(insert),
+        }
+    }
+}
+impl WorldBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a singleton to the world. See [`World::add_singleton`].
+    pub fn add_singleton<S: Any>(mut self, singleton: S) -> Self {
+        self.singletons.push(PendingInsert::new::<S>(move |world| {
+            world.add_singleton(singleton);
+        }));
+        self
+    }
+    /// Add a plugin to the world. Plugins are loaded after every singleton
+    /// given to the builder is added, in the order they were given. See
+    /// [`World::add_plugin`].
+    pub fn add_plugin<P: Plugin>(mut self, plugin: P) -> Self {
+        self.plugins.push(PendingInsert::new::<P>(move |world| {
+            world.add_plugin(plugin);
+        }));
+        self
+    }
+
+    /// Allocate the world, then add every singleton and plugin to it.
+    pub fn build(self) -> World {
+        let map_for = |inserts: &[PendingInsert]| {
+            // A typemap counts as full once its storage is used up, even if
+            // only zero-sized types are left to insert, so leave a spare byte
+            let storage = 1 + inserts.iter().map(|insert| insert.storage).sum::<usize>();
+            // Looking something up in a typemap with no entries panics, so
+            // always leave room for at least one
+            TypeMap::new(inserts.len().max(1), storage)
+        };
+        let mut world = World::from_maps(
+            map_for(&self.plugins),
+            map_for(&self.singletons),
+            Uniq::with_capacity(100),
+            TypeMap::new(100, 1_000),
+        );
+
+        for pending in self.singletons.into_iter().chain(self.plugins) {
+            (pending.insert)(&mut world);
+        }
+
+        world
+    }
+}
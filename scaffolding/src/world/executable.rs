@@ -230,6 +230,8 @@ impl<Args, State: 'static, E: ExecutableWithState<'static, State, Args>> Dynamic
 pub trait ExecutableArgRef {
     /// The [`ExecutableArg`] being borrowed.
     type EA: ExecutableArg;
+    /// If this is an `&mut ExecutableArg`.
+    const MUTABLE: bool;
     /// The borrowed executable arg's type - either `&ExecutableArg` or
     /// `&mut ExecutableArg`. This has two lifetimes: `'a`, the lifetime of the
     /// [`ExecutableArg`], and `'b`, the lifetime of the borrow.
@@ -249,6 +251,7 @@ pub trait ExecutableArgRef {
 }
 impl<EA: ExecutableArg> ExecutableArgRef for &EA {
     type EA = EA;
+    const MUTABLE: bool = false;
     type Borrowed<'a: 'b, 'b> = &'b EA::Arg<'a> where EA::Arg<'a>: 'a;
 
     #[inline(always)]
@@ -263,6 +266,7 @@ impl<EA: ExecutableArg> ExecutableArgRef for &EA {
 }
 impl<EA: ExecutableArg> ExecutableArgRef for &mut EA {
     type EA = EA;
+    const MUTABLE: bool = true;
     type Borrowed<'a: 'b, 'b> = &'b mut EA::Arg<'a> where EA::Arg<'a>: 'a;
 
     #[inline(always)]
@@ -386,6 +390,8 @@ macro_rules! impl_executable_workaround {
             fn execute(self, world: &World) -> Output {
                 let world_extended: &World = unsafe { &*(world as *const World) };
 
+                #[cfg(debug_assertions)]
+                let _guard = MutableBorrowGuard::new(world, false $(|| $tyref::MUTABLE)*);
                 $(let mut $ty = $ty::build(world_extended);)*
                 let result = self($($tyref::borrow(unsafe { &mut *(&mut $ty as *mut $ty::Arg<'_>) })),*);
                 $($ty.drop(world);)*
@@ -414,6 +420,8 @@ macro_rules! impl_executable_workaround {
             fn execute(self, state: State, world: &World) -> Self::Output {
                 let world_extended: &World = unsafe { &*(world as *const World) };
 
+                #[cfg(debug_assertions)]
+                let _guard = MutableBorrowGuard::new(world, false $(|| $tyref::MUTABLE)*);
                 $(let mut $ty = $ty::build(world_extended);)*
                 let result = self(state, $($tyref::borrow(unsafe { &mut *(&mut $ty as *mut $ty::Arg<'_>) })),*);
                 $($ty.drop(world);)*
@@ -427,6 +435,59 @@ macro_rules! impl_executable_workaround {
 }
 impl_executable_workaround!(A ARef A ARef B BRef C CRef D DRef E ERef F FRef);
 
+/// Counts an executable as holding mutable borrows in
+/// [`World::mutable_borrows`] while it runs, if it takes any
+/// `&mut ExecutableArg`s.
+#[cfg(debug_assertions)]
+struct MutableBorrowGuard<'a> {
+    world: &'a World,
+    mutable: bool,
+}
+#[cfg(debug_assertions)]
+impl<'a> MutableBorrowGuard<'a> {
+    fn new(world: &'a World, mutable: bool) -> Self {
+        if mutable {
+            world.mutable_borrows.set(world.mutable_borrows.get() + 1);
+        }
+        Self { world, mutable }
+    }
+}
+#[cfg(debug_assertions)]
+impl Drop for MutableBorrowGuard<'_> {
+    fn drop(&mut self) {
+        if self.mutable {
+            let borrows = &self.world.mutable_borrows;
+            borrows.set(borrows.get() - 1);
+        }
+    }
+}
+
+/// The arguments of an [`Executable`] that only takes `&ExecutableArg`s, and
+/// no `&mut ExecutableArg`s. See [`World::execute_immut`].
+///
+/// ```compile_fail
+/// use scaffolding::prelude::*;
+///
+/// let world = World::new();
+/// world.execute_immut(|_: &mut Singleton<u32>| {});
+/// ```
+pub trait ImmutableArgs {}
+/// Implemented for `&T`, so [`ImmutableArgs`] can be implemented for tuples of
+/// shared borrows.
+pub trait SharedBorrow {}
+impl<T: ?Sized> SharedBorrow for &T {}
+
+macro_rules! impl_immutable_args {
+    () => {
+        impl ImmutableArgs for () {}
+    };
+    ($first:ident $($ty:ident)*) => {
+        impl<$first: SharedBorrow, $($ty: SharedBorrow),*> ImmutableArgs for ($first, $($ty,)*) {}
+        impl_immutable_args!($($ty)*);
+    };
+}
+impl_immutable_args!(A B C D E F);
+
 #[cfg(test)]
 mod tests {
     use {super::*, crate::prelude::*};
@@ -442,6 +503,10 @@ mod tests {
     fn executable2(_num: &Singleton<i32>, _num2: &mut Singleton<u32>) {}
     fn executable3() {}
 
+    fn immutable_executable(_num: &Singleton<i32>, _num2: &Singleton<u32>) -> bool {
+        true
+    }
+
     fn stateful_executable(_state: u32) {}
     fn stateful_executable2(_state: u32, _num: &mut Singleton<i32>) {}
 
@@ -473,4 +538,14 @@ mod tests {
             drop(val);
         });
     }
+    #[test]
+    fn execute_immut() {
+        let mut world = World::new();
+        world.add_singleton(0_u32).add_singleton(1_i32);
+
+        assert!(world.execute_immut(immutable_executable));
+        assert_eq!(world.execute_immut(|| 5), 5);
+        assert_eq!(world.execute_immut(|num: &Singleton<i32>| **num), 1);
+        world.execute_immut(stateful_executable.with_state(0));
+    }
 }
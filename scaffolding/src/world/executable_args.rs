@@ -1,10 +1,11 @@
 //! Types that can be used as arguments in [`Executable`]s.
 
 use {
-    crate::{datatypes::uniq::UniqKey, plugin_prelude::*},
+    crate::{datatypes::uniq::UniqKey, plugin_prelude::*, world::ImmutableArgs},
     core::{
         fmt::{Debug, Formatter},
-        ops::Deref,
+        marker::PhantomData,
+        ops::{Deref, DerefMut},
     },
 };
 
@@ -18,11 +19,19 @@ pub trait ExecutableArg {
 
 // Included executable args below
 
-/// Gets a singleton from the [`World`].
+/// Gets a singleton from the [`World`]. This derefs to the singleton.
+///
+/// Other arguments to the same executable can borrow the same singleton, so
+/// this is only a shared reference, and there's no `DerefMut`. Singletons
+/// that need to change from executables should use interior mutability, like
+/// [`Cell`](core::cell::Cell) or [`RefCell`](core::cell::RefCell).
 pub struct Singleton<'a, T: 'static> {
     val: &'a T,
 }
 impl<'a, T: 'static> Singleton<'a, T> {
+    /// Wrap a reference to a value that isn't in a [`World`]. This is useful
+    /// for calling code that takes a [`Singleton`] from outside an executable,
+    /// or for tests.
     pub fn new(val: &'a T) -> Self {
         Self { val }
     }
@@ -44,6 +53,18 @@ impl<'a, T> Deref for Singleton<'a, T> {
         self.val
     }
 }
+impl<T> Clone for Singleton<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for Singleton<'_, T> {}
+impl<T: PartialEq> PartialEq for Singleton<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.val == other.val
+    }
+}
+impl<T: Eq> Eq for Singleton<'_, T> {}
 impl<T: Debug> Debug for Singleton<'_, T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "State {{ val: {:?} }}", self.val)
@@ -65,6 +86,60 @@ impl MsgSender<'_> {
     }
 }
 
+/// Queues changes to the [`World`] that are applied after the executable
+/// returns. See [`World::queue_command`].
+pub struct CommandQueue<'a>(&'a World);
+impl ExecutableArg for CommandQueue<'_> {
+    type Arg<'a> = CommandQueue<'a>;
+
+    fn build(world: &World) -> Self::Arg<'_> {
+        CommandQueue(world)
+    }
+    fn drop(self, _: &World) {}
+}
+impl CommandQueue<'_> {
+    pub fn queue(&self, command: impl FnOnce(&mut World) + 'static) {
+        self.0.queue_command(command);
+    }
+}
+
+/// Runs other executables from inside an executable.
+///
+/// Sub-executables borrow the same [`World`] as the executable running them,
+/// so to keep borrows from aliasing, they can only take `&ExecutableArg`s
+/// (see [`ImmutableArgs`]). The executable running them shouldn't take any
+/// `&mut ExecutableArg`s either; in debug builds, running a sub-executable
+/// from one that does panics.
+///
+/// Messages sent by sub-executables are processed along with the outer
+/// executable's messages, after it returns.
+pub struct SubExecutor<'a>(&'a World);
+impl ExecutableArg for SubExecutor<'_> {
+    type Arg<'a> = SubExecutor<'a>;
+
+    fn build(world: &World) -> Self::Arg<'_> {
+        SubExecutor(world)
+    }
+    fn drop(self, _: &World) {}
+}
+impl SubExecutor<'_> {
+    pub fn execute<'a, Args: ImmutableArgs, E: Executable<'a, Args>>(
+        &self,
+        executable: E,
+    ) -> E::Output {
+        #[cfg(debug_assertions)]
+        assert_eq!(
+            self.0.mutable_borrows.get(),
+            0,
+            "Scaffolding error: Tried to run a sub-executable from an executable that takes an `&mut ExecutableArg`"
+        );
+
+        self.0.profile(core::any::type_name::<E>(), || {
+            self.0.execute_immut(executable)
+        })
+    }
+}
+
 pub struct Uniqs<'a>(&'a World);
 impl ExecutableArg for Uniqs<'_> {
     type Arg<'a> = Uniqs<'a>;
@@ -81,4 +156,374 @@ impl Uniqs<'_> {
     pub fn get_or_insert<T>(&self, key: UniqKey, default: impl FnOnce() -> T) -> &mut T {
         self.0.states.get(key, default)
     }
+    /// See [`Uniq::get_tagged`](crate::datatypes::Uniq::get_tagged).
+    pub fn get_tagged<T>(
+        &self,
+        key: UniqKey,
+        tag: &'static str,
+        default: impl FnOnce() -> T,
+    ) -> &mut T {
+        self.0.states.get_tagged(key, tag, default)
+    }
+}
+
+/// A singleton that's namespaced by the type `S`. `Scoped<PluginA, Config>`
+/// and `Scoped<PluginB, Config>` are different singletons, so two plugins can
+/// each store their own `Config` in the [`World`]. Use [`Scope`] to get one in
+/// an executable.
+pub struct Scoped<S: 'static, T> {
+    pub val: T,
+    _scope: PhantomData<fn() -> S>,
+}
+impl<S: 'static, T> Scoped<S, T> {
+    pub fn new(val: T) -> Self {
+        Self {
+            val,
+            _scope: PhantomData,
+        }
+    }
+}
+impl<S: 'static, T> Deref for Scoped<S, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.val
+    }
+}
+impl<S: 'static, T> DerefMut for Scoped<S, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.val
+    }
+}
+
+/// Gets a [`Scoped`] singleton from the [`World`]. This derefs to the
+/// singleton's inner value.
+pub struct Scope<'a, S: 'static, T: 'static> {
+    val: &'a T,
+    _scope: PhantomData<fn() -> S>,
+}
+impl<S: 'static, T: 'static> ExecutableArg for Scope<'_, S, T> {
+    type Arg<'a> = Scope<'a, S, T>;
+
+    fn build(world: &World) -> Self::Arg<'_> {
+        Scope {
+            val: &world.get_singleton::<Scoped<S, T>>().val,
+            _scope: PhantomData,
+        }
+    }
+    fn drop(self, _: &World) {}
+}
+impl<S: 'static, T: 'static> Deref for Scope<'_, S, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.val
+    }
+}
+
+/// Frame timing. This is a singleton that a runloop updates at the start of
+/// each frame with [`Time::tick`], and can also be used as an executable arg
+/// to read the current frame's timing.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct Time {
+    start: std::time::Instant,
+    frame_start: std::time::Instant,
+    delta: core::time::Duration,
+}
+#[cfg(feature = "std")]
+impl Time {
+    /// Create a new [`Time`], starting now.
+    pub fn new() -> Self {
+        Self::starting_at(std::time::Instant::now())
+    }
+    /// Create a new [`Time`], starting at the given instant.
+    pub fn starting_at(start: std::time::Instant) -> Self {
+        Self {
+            start,
+            frame_start: start,
+            delta: core::time::Duration::ZERO,
+        }
+    }
+
+    /// How long it's been since the last frame started.
+    pub fn delta(&self) -> core::time::Duration {
+        self.delta
+    }
+    /// How long it's been since the first frame started.
+    pub fn elapsed(&self) -> core::time::Duration {
+        self.frame_start - self.start
+    }
+
+    /// Start a new frame now.
+    pub fn tick(&mut self) {
+        self.tick_at(std::time::Instant::now());
+    }
+    /// Start a new frame at the given instant.
+    pub fn tick_at(&mut self, now: std::time::Instant) {
+        self.delta = now.saturating_duration_since(self.frame_start);
+        self.frame_start = now;
+    }
+}
+#[cfg(feature = "std")]
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(feature = "std")]
+impl ExecutableArg for Time {
+    type Arg<'a> = Time;
+
+    fn build(world: &World) -> Self::Arg<'_> {
+        *world.get_singleton()
+    }
+    fn drop(self, _: &World) {}
+}
+
+/// How long executables took to run. When this is added to the [`World`] as a
+/// singleton, [`World::execute`] and [`World::profile`] record how long each
+/// executable takes, labelled by the executable's type name (or the label
+/// passed to [`World::profile`]). Without this singleton, nothing is timed.
+///
+/// Timings add up until they're cleared, so to see how long one frame took,
+/// call [`ProfileData::clear`] at the start of each frame.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct ProfileData {
+    timings: core::cell::RefCell<alloc::vec::Vec<(&'static str, core::time::Duration)>>,
+}
+#[cfg(feature = "std")]
+impl ProfileData {
+    /// Add `duration` to the time spent on `label`.
+    pub fn record(&self, label: &'static str, duration: core::time::Duration) {
+        let mut timings = self.timings.borrow_mut();
+        match timings.iter_mut().find(|(other, _)| *other == label) {
+            Some((_, total)) => *total += duration,
+            None => timings.push((label, duration)),
+        }
+    }
+    /// The total time spent on `label`, if it's been recorded.
+    pub fn get(&self, label: &str) -> Option<core::time::Duration> {
+        self.timings
+            .borrow()
+            .iter()
+            .find(|(other, _)| *other == label)
+            .map(|(_, duration)| *duration)
+    }
+    /// Every label and its total time, in the order they were first recorded.
+    pub fn timings(&self) -> alloc::vec::Vec<(&'static str, core::time::Duration)> {
+        self.timings.borrow().clone()
+    }
+    /// Forget all of the recorded timings.
+    pub fn clear(&self) {
+        self.timings.borrow_mut().clear();
+    }
+}
+
+/// The program's command-line arguments, excluding the program name. This is
+/// read from [`std::env::args`] the first time [`Args`] is used. To use
+/// different arguments (for example, in tests), add a [`CommandLine`]
+/// singleton to the [`World`].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct CommandLine {
+    args: alloc::vec::Vec<alloc::string::String>,
+}
+#[cfg(feature = "std")]
+impl CommandLine {
+    pub fn new<S: Into<alloc::string::String>>(args: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+    /// Read the arguments this program was launched with.
+    pub fn from_env() -> Self {
+        Self::new(std::env::args().skip(1))
+    }
+
+    /// All of the arguments, in order.
+    pub fn all(&self) -> &[alloc::string::String] {
+        &self.args
+    }
+    /// Check if a flag (like `--verbose`) was passed.
+    pub fn flag(&self, name: &str) -> bool {
+        self.args.iter().any(|arg| arg == name)
+    }
+    /// Get the value passed to an option, either as `--name value` or
+    /// `--name=value`.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        let mut args = self.args.iter();
+        while let Some(arg) = args.next() {
+            if arg == name {
+                return args.next().map(|val| val.as_str());
+            }
+            if let Some(val) = arg.strip_prefix(name).and_then(|arg| arg.strip_prefix('=')) {
+                return Some(val);
+            }
+        }
+
+        None
+    }
+}
+
+/// Gets the program's [`CommandLine`] arguments.
+#[cfg(feature = "std")]
+pub struct Args<'a>(&'a CommandLine);
+#[cfg(feature = "std")]
+impl ExecutableArg for Args<'_> {
+    type Arg<'a> = Args<'a>;
+
+    fn build(world: &World) -> Self::Arg<'_> {
+        static ENV_ARGS: std::sync::OnceLock<CommandLine> = std::sync::OnceLock::new();
+
+        Args(
+            world
+                .try_get_singleton()
+                .unwrap_or_else(|| ENV_ARGS.get_or_init(CommandLine::from_env)),
+        )
+    }
+    fn drop(self, _: &World) {}
+}
+#[cfg(feature = "std")]
+impl Deref for Args<'_> {
+    type Target = CommandLine;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn singleton_from_reference() {
+        let val = 5_u32;
+        let singleton = Singleton::new(&val);
+        assert_eq!(*singleton, 5);
+        assert_eq!(singleton, Singleton::new(&5));
+        assert_ne!(singleton, Singleton::new(&6));
+
+        // Singletons built by hand work the same as ones from the world
+        fn double(val: &Singleton<u32>) -> u32 {
+            **val * 2
+        }
+        assert_eq!(double(&singleton), 10);
+
+        let mut world = World::new();
+        world.add_singleton(5_u32);
+        assert_eq!(world.execute(double), 10);
+    }
+
+    #[test]
+    fn scoped_singletons() {
+        struct PluginA;
+        struct PluginB;
+
+        let mut world = World::new();
+        world
+            .add_singleton(Scoped::<PluginA, u32>::new(1))
+            .add_singleton(Scoped::<PluginB, u32>::new(2));
+
+        world.get_singleton_mut::<Scoped<PluginB, u32>>().val += 1;
+
+        let (a, b) = world.execute(|a: &Scope<PluginA, u32>, b: &Scope<PluginB, u32>| (**a, **b));
+        assert_eq!(a, 1);
+        assert_eq!(b, 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn time() {
+        use {core::time::Duration, std::time::Instant};
+
+        let start = Instant::now();
+        let mut world = World::new();
+        world.add_singleton(Time::starting_at(start));
+
+        let time: &mut Time = world.get_singleton_mut();
+        time.tick_at(start + Duration::from_millis(16));
+        time.tick_at(start + Duration::from_millis(40));
+
+        let (delta, elapsed) = world.execute(|time: &Time| (time.delta(), time.elapsed()));
+        assert_eq!(delta, Duration::from_millis(24));
+        assert_eq!(elapsed, Duration::from_millis(40));
+    }
+
+    #[test]
+    fn sub_executor() {
+        fn outer(config: &Singleton<u32>, sub: &SubExecutor) -> u32 {
+            **config + sub.execute(|config: &Singleton<u32>| **config * 2)
+        }
+
+        let mut world = World::new();
+        world.add_singleton(21_u32);
+        assert_eq!(world.execute(outer), 63);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "sub-executable")]
+    fn sub_executor_with_mutable_borrow() {
+        let mut world = World::new();
+        world.add_singleton(0_u32);
+        world.execute(|_: &mut Singleton<u32>, sub: &SubExecutor| {
+            sub.execute(|_: &Singleton<u32>| {});
+        });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn profile() {
+        use {core::time::Duration, std::thread};
+
+        fn slow() {
+            thread::sleep(Duration::from_millis(30));
+        }
+        fn fast() {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let mut world = World::new();
+        world.execute(slow);
+        assert!(world.try_get_singleton::<ProfileData>().is_none());
+
+        world.add_singleton(ProfileData::default());
+        world.execute(fast);
+        world.execute(slow);
+        world.profile("label", || thread::sleep(Duration::from_millis(1)));
+
+        let profile: &ProfileData = world.get_singleton();
+        let slow = profile.get(core::any::type_name_of_val(&slow)).unwrap();
+        let fast = profile.get(core::any::type_name_of_val(&fast)).unwrap();
+        assert!(slow > fast);
+        assert!(fast >= Duration::from_millis(5));
+        assert!(profile.get("label").unwrap() >= Duration::from_millis(1));
+        assert_eq!(profile.timings().len(), 3);
+
+        profile.clear();
+        assert!(profile.get("label").is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn args() {
+        let mut world = World::new();
+        world.add_singleton(CommandLine::new([
+            "--verbose",
+            "--out",
+            "a.txt",
+            "--mode=fast",
+        ]));
+
+        world.execute(|args: &Args| {
+            assert!(args.flag("--verbose"));
+            assert!(!args.flag("--quiet"));
+            assert_eq!(args.value("--out"), Some("a.txt"));
+            assert_eq!(args.value("--mode"), Some("fast"));
+            assert_eq!(args.value("--missing"), None);
+        });
+    }
 }
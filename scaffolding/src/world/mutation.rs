@@ -0,0 +1,72 @@
+//! Changes to a [`World`] that can be stored, applied later, and undone.
+
+use {super::World, core::any::Any};
+
+/// A change to a [`World`].
+pub trait Mutation {
+    /// Make the change.
+    fn apply(self, world: &mut World);
+}
+
+/// A [`Mutation`] that can be undone.
+pub trait ReversibleMutation: Mutation {
+    /// The mutation that undoes this one.
+    type Reverse: Mutation;
+
+    /// Build the mutation that undoes this one, from the world's state before
+    /// this mutation is applied.
+    fn build_reverse(&self, world: &World) -> Self::Reverse;
+}
+
+/// Sets a singleton to a new value.
+///
+/// The reverse of this mutation sets the singleton back to its current value,
+/// so the singleton must already be in the world to build it.
+pub struct SetSingleton<T: Any + Clone>(pub T);
+impl<T: Any + Clone> Mutation for SetSingleton<T> {
+    fn apply(self, world: &mut World) {
+        match world.try_get_singleton_mut::<T>() {
+            Some(singleton) => *singleton = self.0,
+            None => {
+                world.add_singleton(self.0);
+            }
+        }
+    }
+}
+impl<T: Any + Clone> ReversibleMutation for SetSingleton<T> {
+    type Reverse = Self;
+
+    fn build_reverse(&self, world: &World) -> Self::Reverse {
+        Self(world.get_singleton::<T>().clone())
+    }
+}
+
+/// Changes a singleton in-place with a function.
+pub struct ModifySingleton<T: Any>(pub fn(&mut T));
+impl<T: Any> Mutation for ModifySingleton<T> {
+    fn apply(self, world: &mut World) {
+        (self.0)(world.get_singleton_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn singleton_mutations() {
+        let mut world = World::new();
+        world.add_singleton(1_u32);
+
+        let set = SetSingleton(5_u32);
+        let reverse = set.build_reverse(&world);
+        set.apply(&mut world);
+        assert_eq!(*world.get_singleton::<u32>(), 5);
+
+        ModifySingleton(|val: &mut u32| *val *= 2).apply(&mut world);
+        assert_eq!(*world.get_singleton::<u32>(), 10);
+
+        reverse.apply(&mut world);
+        assert_eq!(*world.get_singleton::<u32>(), 1);
+    }
+}
@@ -0,0 +1,128 @@
+//! Saving and restoring singletons. See [`Snapshot`].
+
+use {
+    crate::world::World,
+    alloc::vec::Vec,
+    core::any::{type_name, Any},
+};
+
+/// A singleton that can be saved to and restored from bytes with
+/// [`World::snapshot_singletons`] and [`World::restore_singletons`].
+///
+/// Singletons are only included in snapshots if they were added with
+/// [`World::add_snapshot_singleton`].
+pub trait Snapshot: Sized {
+    /// Serialise this singleton.
+    fn to_bytes(&self) -> Vec<u8>;
+    /// Deserialise a singleton from the bytes made by [`Snapshot::to_bytes`].
+    /// Returns `None` if the bytes are invalid.
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+/// Errors that can occur in [`World::restore_singletons`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum RestoreError {
+    /// The snapshot ended in the middle of a singleton.
+    Truncated,
+    /// The snapshot has a singleton that wasn't registered with
+    /// [`World::add_snapshot_singleton`].
+    UnknownSingleton,
+    /// [`Snapshot::from_bytes`] rejected the bytes for this singleton.
+    InvalidSingleton(&'static str),
+}
+
+/// Type-erased functions to save and restore one singleton type.
+pub(crate) struct Snapshotter {
+    pub type_name: &'static str,
+    pub snapshot: fn(&World, &mut Vec<u8>),
+    pub restore: fn(&mut World, &[u8]) -> bool,
+}
+impl Snapshotter {
+    pub fn of<S: Snapshot + Any>() -> Self {
+        Self {
+            type_name: type_name::<S>(),
+            snapshot: |world, out| out.extend(world.get_singleton::<S>().to_bytes()),
+            restore: |world, bytes| match S::from_bytes(bytes) {
+                Some(singleton) => {
+                    *world.get_singleton_mut::<S>() = singleton;
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// Reads a length-prefixed chunk of bytes from the front of `bytes`.
+pub(crate) fn read_chunk<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], RestoreError> {
+    let (len, rest) = bytes
+        .split_first_chunk::<8>()
+        .ok_or(RestoreError::Truncated)?;
+    let len = u64::from_le_bytes(*len) as usize;
+    if rest.len() < len {
+        return Err(RestoreError::Truncated);
+    }
+
+    let (chunk, rest) = rest.split_at(len);
+    *bytes = rest;
+    Ok(chunk)
+}
+/// Writes a length-prefixed chunk of bytes to `out`.
+pub(crate) fn write_chunk(out: &mut Vec<u8>, chunk: &[u8]) {
+    out.extend((chunk.len() as u64).to_le_bytes());
+    out.extend_from_slice(chunk);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Score(u32);
+    impl Snapshot for Score {
+        fn to_bytes(&self) -> Vec<u8> {
+            self.0.to_le_bytes().to_vec()
+        }
+        fn from_bytes(bytes: &[u8]) -> Option<Self> {
+            Some(Self(u32::from_le_bytes(bytes.try_into().ok()?)))
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Name(String);
+    impl Snapshot for Name {
+        fn to_bytes(&self) -> Vec<u8> {
+            self.0.as_bytes().to_vec()
+        }
+        fn from_bytes(bytes: &[u8]) -> Option<Self> {
+            Some(Self(String::from_utf8(bytes.to_vec()).ok()?))
+        }
+    }
+
+    #[test]
+    fn snapshot_and_restore() {
+        let mut world = World::new();
+        world
+            .add_snapshot_singleton(Score(10))
+            .add_snapshot_singleton(Name("scaffolding".into()))
+            .add_singleton(5u8);
+
+        let snapshot = world.snapshot_singletons();
+
+        world.get_singleton_mut::<Score>().0 = 20;
+        world.get_singleton_mut::<Name>().0.push_str("-tui");
+        *world.get_singleton_mut::<u8>() = 6;
+
+        world.restore_singletons(&snapshot).unwrap();
+
+        assert_eq!(*world.get_singleton::<Score>(), Score(10));
+        assert_eq!(*world.get_singleton::<Name>(), Name("scaffolding".into()));
+        // Not registered for snapshots, so it isn't restored
+        assert_eq!(*world.get_singleton::<u8>(), 6);
+
+        assert_eq!(
+            world.restore_singletons(&snapshot[..snapshot.len() - 1]),
+            Err(RestoreError::Truncated)
+        );
+    }
+}